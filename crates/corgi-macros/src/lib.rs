@@ -11,20 +11,72 @@
 use proc_macro::TokenStream;
 use proc_macro2::Span;
 use quote::quote;
-use syn::{FnArg, ItemFn, ReturnType, parse_macro_input};
+use syn::{DeriveInput, FnArg, ItemFn, ReturnType, parse_macro_input};
 
 /// Marks an async function as an RPC-capable function.
 ///
-/// This attribute does two things:
+/// This attribute does three things:
 /// 1. It keeps the original function intact so it can be called locally.
 /// 2. It generates a global `static` variable named `__CORGI_RPC_<fn_name>`
 ///    of type [`corgi::container::RpcFunction`].
+/// 3. It generates `__corgi_invoke_<fn_name>(args, codec)`, the dispatch
+///    logic behind the handler as a plain async function, so it can be
+///    called directly in tests.
+///
+/// # Borrowed arguments
+/// A `&str` argument is decoded as a `String` and a `&[u8]` argument as a
+/// `Vec<u8>`, and the function is called with a borrow of it. Reflection
+/// describes such arguments by their owned type. Other references are
+/// rejected, since the decoded value has to live somewhere.
 ///
 /// # Requirements
-/// - All arguments must implement `wincode::SchemaReadOwned`.
-/// - The return type must implement `wincode::SchemaWrite`.
+/// - All arguments and the return type must be encodable by the function's
+///   codec: protobuf messages by default, see the `codec` attribute.
 /// - The function must be `async`.
 ///
+/// # Errors
+/// A function returning `Result<T, E>` declares `E` as its error type, which
+/// must be a protobuf message. `Ok` values are encoded like any other return
+/// value; `Err` values are encoded with the codec and reach the client as
+/// `RpcError::Application`, tagged with the schema id of `E` so they can be
+/// decoded back into `E`. Reflection exposes that schema id next to the
+/// return type's.
+///
+/// Returning `Result<T, corgi::protocol::types::RpcError>` instead fails the
+/// call with that error as-is, without declaring an error type. In both
+/// cases `T` may be a raw return type.
+///
+/// # Raw returns
+/// A function returning `bytes::Bytes` or `Cow<'static, [u8]>` has its return
+/// value sent as-is instead of being encoded with the codec. Static data is
+/// shared rather than copied, which suits fixed responses such as cached
+/// schema blobs.
+///
+/// # Raw arguments
+/// A `bytes::Bytes` argument is passed as received instead of being decoded
+/// with the codec. It is a slice of the reassembled request and shares its
+/// buffer, so an echo-like handler can return a sub-slice of it (see
+/// `Bytes::slice`) and have it sent without copying. Every slice holds a
+/// reference count on the request buffer, which stays alive until the last
+/// of them, including the response, is dropped.
+///
+/// # Attributes
+/// - `on_decode_error = "fail"` (default): an argument that fails to decode
+///   fails the whole call with `RpcError::ArgumentDecodeFailed`.
+/// - `on_decode_error = "default"`: an argument that is missing or fails to
+///   decode is replaced by its `Default` value.
+/// - `version = N`: registers the function under the wire name `name@N`.
+///   Version 1 (the default) keeps the plain name.
+/// - `name = "..."`: registers the function under this wire name instead
+///   of its Rust name, so either can change without the other. The
+///   generated items keep the Rust name: `__CORGI_RPC_<fn_name>` and
+///   `__corgi_invoke_<fn_name>`. The name must be non-empty and must not
+///   contain `@`.
+/// - `codec = path::to::Codec`: encodes arguments, return value and error
+///   value with this `corgi::protocol::codec::PayloadCodec` instead of
+///   `ProtobufCodec`, e.g. `corgi::protocol::codec::JsonCodec` for serde
+///   types. Callers have to encode the arguments with the same codec.
+///
 /// # Example
 /// ```rust
 /// use corgi_macros::rpc_fn;
@@ -34,98 +86,261 @@ use syn::{FnArg, ItemFn, ReturnType, parse_macro_input};
 ///     a + b
 /// }
 ///
+/// #[rpc_fn(on_decode_error = "default")]
+/// async fn lenient_add(a: i32, b: i32) -> i32 {
+///     a + b
+/// }
+///
 /// // You can now access the metadata:
 /// println!("RPC Name: {}", __CORGI_RPC_add.name);
 /// ```
 #[proc_macro_attribute]
-pub fn rpc_fn(_attr: TokenStream, input: TokenStream) -> TokenStream {
+pub fn rpc_fn(attr: TokenStream, input: TokenStream) -> TokenStream {
+    let mut decode_policy = DecodePolicy::Fail;
+    let mut version = 1_u32;
+    let mut name = None;
+    let mut codec: Option<syn::Path> = None;
+    let attr_parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("codec") {
+            codec = Some(meta.value()?.parse()?);
+            Ok(())
+        } else if meta.path.is_ident("name") {
+            let value: syn::LitStr = meta.value()?.parse()?;
+            if value.value().is_empty() {
+                return Err(syn::Error::new_spanned(value, "name must not be empty"));
+            }
+            if value.value().contains('@') {
+                return Err(syn::Error::new_spanned(
+                    value,
+                    "name must not contain `@`, which separates the version",
+                ));
+            }
+            name = Some(value.value());
+            Ok(())
+        } else if meta.path.is_ident("version") {
+            let value: syn::LitInt = meta.value()?.parse()?;
+            version = value.base10_parse()?;
+            if version == 0 {
+                return Err(syn::Error::new_spanned(value, "version must start at 1"));
+            }
+            Ok(())
+        } else if meta.path.is_ident("on_decode_error") {
+            let value: syn::LitStr = meta.value()?.parse()?;
+            decode_policy = match value.value().as_str() {
+                "fail" => DecodePolicy::Fail,
+                "default" => DecodePolicy::Default,
+                _ => return Err(meta.error("expected `fail` or `default`")),
+            };
+            Ok(())
+        } else {
+            Err(meta.error("unsupported rpc_fn attribute"))
+        }
+    });
+    parse_macro_input!(attr with attr_parser);
+
     let func = parse_macro_input!(input as ItemFn);
+    if func.sig.asyncness.is_none() {
+        return syn::Error::new_spanned(
+            func.sig.fn_token,
+            "rpc_fn requires an `async fn`, since the generated handler awaits it; \
+             declare the function as `async fn`",
+        )
+        .to_compile_error()
+        .into();
+    }
     let fn_ident = &func.sig.ident;
-    let fn_name_str = fn_ident.to_string();
+    let base_name = name.unwrap_or_else(|| fn_ident.to_string());
+    let fn_name_str = if version > 1 {
+        format!("{base_name}@{version}")
+    } else {
+        base_name
+    };
+
+    let custom_codec = codec.is_some();
+    let codec_ty: syn::Path =
+        codec.unwrap_or_else(|| syn::parse_quote! { corgi::protocol::codec::ProtobufCodec });
 
     let rpc_ident = syn::Ident::new(&format!("__CORGI_RPC_{}", fn_ident), Span::call_site());
+    let invoke_ident = syn::Ident::new(&format!("__corgi_invoke_{}", fn_ident), Span::call_site());
 
-    let params: Vec<(syn::Ident, &syn::Type)> = func
+    let params: Result<Vec<(syn::Ident, syn::Type, bool)>, syn::Error> = func
         .sig
         .inputs
         .iter()
         .map(|arg| match arg {
-            FnArg::Typed(pat) => {
-                let ident = match &*pat.pat {
-                    syn::Pat::Ident(pat_ident) => pat_ident.ident.clone(),
-                    _ => panic!("rpc_fn only supports simple identifiers"),
-                };
-                (ident, &*pat.ty)
-            }
-            FnArg::Receiver(_) => panic!("rpc_fn does not support self"),
+            FnArg::Typed(pat) => match &*pat.pat {
+                syn::Pat::Ident(pat_ident) => Ok(match owned_type(&pat.ty)? {
+                    Some(owned_ty) => (pat_ident.ident.clone(), owned_ty, true),
+                    None => (pat_ident.ident.clone(), (*pat.ty).clone(), false),
+                }),
+                pattern => Err(syn::Error::new_spanned(
+                    pattern,
+                    "rpc_fn arguments must be plain identifiers, such as `value: T`",
+                )),
+            },
+            FnArg::Receiver(receiver) => Err(syn::Error::new_spanned(
+                receiver,
+                "rpc_fn cannot be applied to methods taking `self`",
+            )),
         })
         .collect();
+    let params = match params {
+        Ok(params) => params,
+        Err(error) => return error.to_compile_error().into(),
+    };
 
-    let param_descriptors = params.iter().map(|(ident, ty)| {
+    let param_descriptors = params.iter().map(|(ident, ty, _)| {
         let name_str = ident.to_string();
         quote! {
             corgi::container::Param {
                 name: #name_str,
                 type_id: std::any::TypeId::of::<#ty>(),
+                schema_id: corgi::schema_id::<#ty>(),
             }
         }
     });
 
-    let param_types: Vec<_> = params.iter().map(|(_, ty)| ty).collect();
-    let arg_idents: Vec<_> = params.iter().map(|(ident, _)| ident.clone()).collect();
-
-    let has_return = match &func.sig.output {
-        ReturnType::Default => false,
-        ReturnType::Type(_, _) => true,
-    };
+    let param_types: Vec<_> = params.iter().map(|(_, ty, _)| ty).collect();
+    let arg_idents: Vec<_> = params.iter().map(|(ident, _, _)| ident.clone()).collect();
+    let call_args: Vec<_> = params
+        .iter()
+        .map(|(ident, _, borrowed)| {
+            if *borrowed {
+                quote! { &#ident }
+            } else {
+                quote! { #ident }
+            }
+        })
+        .collect();
 
     let decoders = param_types.iter().enumerate().map(|(i, ty)| {
         let ident = &arg_idents[i];
-        quote! {
-            let #ident: #ty = codec.decode(&args[#i])?;
+        if is_bytes(ty) {
+            let missing = match decode_policy {
+                DecodePolicy::Fail => quote! {
+                    .ok_or(corgi::protocol::types::RpcError::ArgumentDecodeFailed)?
+                },
+                DecodePolicy::Default => quote! { .unwrap_or_default() },
+            };
+            return quote! {
+                let #ident: #ty = args.get(#i).cloned()#missing;
+            };
+        }
+        match decode_policy {
+            DecodePolicy::Fail => quote! {
+                let #ident: #ty = args
+                    .get(#i)
+                    .and_then(|arg| {
+                        corgi::protocol::codec::PayloadCodec::<#ty>::decode(&codec, arg).ok()
+                    })
+                    .ok_or(corgi::protocol::types::RpcError::ArgumentDecodeFailed)?;
+            },
+            DecodePolicy::Default => quote! {
+                let #ident: #ty = args
+                    .get(#i)
+                    .and_then(|arg| {
+                        corgi::protocol::codec::PayloadCodec::<#ty>::decode(&codec, arg).ok()
+                    })
+                    .unwrap_or_default();
+            },
         }
     });
 
-    let return_type_expr = if has_return {
-        if let ReturnType::Type(_, ty) = &func.sig.output {
-            quote! { Some(std::any::TypeId::of::<#ty>()) }
-        } else {
-            unreachable!()
-        }
-    } else {
-        quote! { None }
+    let decode_policy_expr = match decode_policy {
+        DecodePolicy::Fail => quote! { corgi::container::DecodePolicy::Fail },
+        DecodePolicy::Default => quote! { corgi::container::DecodePolicy::Default },
     };
 
-    let handler_body = if has_return {
-        quote! {
-            let result = #fn_ident( #(#arg_idents),* ).await;
-            codec.encode(&result)
+    let result_types = match &func.sig.output {
+        ReturnType::Type(_, ty) => result_types(ty),
+        ReturnType::Default => None,
+    };
+
+    let return_ty = match (&func.sig.output, result_types) {
+        (_, Some((ok_ty, _))) => Some(ok_ty),
+        (ReturnType::Type(_, ty), None) => Some(&**ty),
+        (ReturnType::Default, None) => None,
+    };
+
+    let (return_type_expr, return_schema_id_expr) = match return_ty {
+        Some(ty) => (
+            quote! { Some(std::any::TypeId::of::<#ty>()) },
+            quote! { Some(corgi::schema_id::<#ty>()) },
+        ),
+        None => (quote! { None }, quote! { None }),
+    };
+
+    let (error_type_expr, error_schema_id_expr) = match result_types {
+        Some((_, err_ty)) if !is_rpc_error(err_ty) => (
+            quote! { Some(std::any::TypeId::of::<#err_ty>()) },
+            quote! { Some(corgi::schema_id::<#err_ty>()) },
+        ),
+        _ => (quote! { None }, quote! { None }),
+    };
+
+    let handler_body = match (&func.sig.output, result_types) {
+        (_, Some((ok_ty, err_ty))) => {
+            let encode_ok = encode_result(ok_ty, custom_codec);
+            let into_error = if is_rpc_error(err_ty) {
+                quote! { error }
+            } else {
+                quote! {
+                    corgi::protocol::types::RpcError::Application {
+                        schema_id: corgi::schema_id::<#err_ty>(),
+                        payload: corgi::protocol::codec::encode_payload::<_, #err_ty>(
+                            &codec, &error,
+                        )?,
+                    }
+                }
+            };
+            quote! {
+                match #fn_ident( #(#call_args),* ).await {
+                    Ok(result) => #encode_ok,
+                    Err(error) => Err(#into_error),
+                }
+            }
         }
-    } else {
-        quote! {
-            #fn_ident( #(#arg_idents),* ).await;
-            Ok(bytes::Bytes::new())
+        (ReturnType::Type(_, ty), None) => {
+            let encode = encode_result(ty, custom_codec);
+            quote! {
+                let result = #fn_ident( #(#call_args),* ).await;
+                #encode
+            }
         }
+        (ReturnType::Default, None) => quote! {
+            #fn_ident( #(#call_args),* ).await;
+            Ok(bytes::Bytes::new())
+        },
     };
 
     let expanded = quote! {
         #func
 
+        pub async fn #invoke_ident(
+            args: Vec<bytes::Bytes>,
+            codec: #codec_ty,
+        ) -> Result<bytes::Bytes, corgi::protocol::types::RpcError> {
+            #(#decoders)*
+            #handler_body
+        }
+
         #[allow(non_upper_case_globals)]
         pub static #rpc_ident: std::sync::LazyLock<corgi::container::RpcFunction> =
         std::sync::LazyLock::new(|| {
             corgi::container::RpcFunction {
                 name: #fn_name_str,
+                version: #version,
                 params: vec![ #(#param_descriptors),* ],
                 return_type: #return_type_expr,
+                return_schema_id: #return_schema_id_expr,
+                error_type: #error_type_expr,
+                error_schema_id: #error_schema_id_expr,
+                decode_policy: #decode_policy_expr,
                 handler: std::sync::Arc::new(
-                    |args: Vec<bytes::Bytes>, codec: corgi::protocol::codec::ProtobufCodec| {
+                    |args: Vec<bytes::Bytes>| {
                         use futures::FutureExt;
 
-                        async move {
-                            #(#decoders)*
-                            #handler_body
-                        }.boxed()
+                        #invoke_ident(args, <#codec_ty as Default>::default()).boxed()
                     }
                 ),
             }
@@ -134,3 +349,206 @@ pub fn rpc_fn(_attr: TokenStream, input: TokenStream) -> TokenStream {
 
     expanded.into()
 }
+
+/// Mirrors `corgi::container::DecodePolicy` at macro-expansion time.
+enum DecodePolicy {
+    Fail,
+    Default,
+}
+
+/// Encodes a struct returned by an [`rpc_fn`] as named response fields.
+///
+/// Each named field is encoded on its own with the codec, and the response
+/// is sent as `corgi::protocol::types::ResponseFields`. Clients can then read
+/// `fields.get("total")` without decoding the whole struct. Every field type
+/// must be a protobuf message.
+///
+/// # Example
+/// ```rust
+/// use corgi_macros::{rpc_fn, RpcResponse};
+///
+/// #[derive(RpcResponse)]
+/// struct Totals {
+///     total: u64,
+///     count: u32,
+/// }
+///
+/// #[rpc_fn]
+/// async fn totals() -> Totals {
+///     Totals { total: 42, count: 2 }
+/// }
+/// ```
+#[proc_macro_derive(RpcResponse)]
+pub fn rpc_response(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let fields = match &input.data {
+        syn::Data::Struct(syn::DataStruct {
+            fields: syn::Fields::Named(fields),
+            ..
+        }) => &fields.named,
+        _ => {
+            return syn::Error::new_spanned(
+                ident,
+                "RpcResponse can only be derived for structs with named fields",
+            )
+            .to_compile_error()
+            .into();
+        }
+    };
+
+    let encoders = fields.iter().map(|field| {
+        let field_ident = field.ident.as_ref().unwrap();
+        let field_name = field_ident.to_string();
+        quote! {
+            (#field_name.to_owned(), codec.encode(&self.#field_ident)?)
+        }
+    });
+
+    let expanded = quote! {
+        impl #impl_generics corgi::protocol::codec::EncodeResponse for #ident #ty_generics #where_clause {
+            fn encode_response(
+                &self,
+                codec: &corgi::protocol::codec::ProtobufCodec,
+            ) -> Result<bytes::Bytes, corgi::protocol::types::RpcError> {
+                let fields = corgi::protocol::types::ResponseFields::new(vec![ #(#encoders),* ]);
+                corgi::protocol::codec::ResponseFieldsCodec.encode(&fields)
+            }
+        }
+    };
+
+    expanded.into()
+}
+
+/// Splits a `Result<T, E>` return type into `T` and `E`.
+fn result_types(ty: &syn::Type) -> Option<(&syn::Type, &syn::Type)> {
+    let syn::Type::Path(type_path) = ty else {
+        return None;
+    };
+    let segment = type_path.path.segments.last()?;
+    if segment.ident != "Result" {
+        return None;
+    }
+    let syn::PathArguments::AngleBracketed(arguments) = &segment.arguments else {
+        return None;
+    };
+
+    let mut types = arguments.args.iter().filter_map(|argument| match argument {
+        syn::GenericArgument::Type(ty) => Some(ty),
+        _ => None,
+    });
+    match (types.next(), types.next(), types.next()) {
+        (Some(ok_ty), Some(err_ty), None) => Some((ok_ty, err_ty)),
+        _ => None,
+    }
+}
+
+/// Returns the owned type a borrowed argument is decoded into, `None` for
+/// arguments that are owned already.
+fn owned_type(ty: &syn::Type) -> Result<Option<syn::Type>, syn::Error> {
+    let syn::Type::Reference(reference) = ty else {
+        return Ok(None);
+    };
+
+    let owned_ty = match &*reference.elem {
+        syn::Type::Path(path) if reference.mutability.is_none() && path.path.is_ident("str") => {
+            syn::parse_quote! { String }
+        }
+        syn::Type::Slice(slice)
+            if reference.mutability.is_none()
+                && matches!(&*slice.elem, syn::Type::Path(elem) if elem.path.is_ident("u8")) =>
+        {
+            syn::parse_quote! { Vec<u8> }
+        }
+        _ => {
+            return Err(syn::Error::new_spanned(
+                ty,
+                "rpc_fn arguments are decoded into owned values; only `&str` and `&[u8]` \
+                 can be borrowed, use the owned type instead",
+            ));
+        }
+    };
+
+    Ok(Some(owned_ty))
+}
+
+/// Whether `ty` names corgi's own `RpcError`, which handlers return as-is
+/// instead of declaring it as an application error type.
+fn is_rpc_error(ty: &syn::Type) -> bool {
+    matches!(
+        ty,
+        syn::Type::Path(type_path)
+            if type_path.path.segments.last().is_some_and(|segment| segment.ident == "RpcError")
+    )
+}
+
+/// Turns a successful return value `result` of type `ty` into the
+/// handler's `Ok` response bytes. Only `ProtobufCodec` functions support
+/// `RpcResponse` types; others encode through their codec.
+fn encode_result(ty: &syn::Type, custom_codec: bool) -> proc_macro2::TokenStream {
+    match raw_return_kind(ty) {
+        Some(RawReturn::Bytes) => quote! {
+            corgi::protocol::codec::check_response_size(result)
+        },
+        Some(RawReturn::Cow) => quote! {
+            corgi::protocol::codec::check_response_size(match result {
+                std::borrow::Cow::Borrowed(slice) => bytes::Bytes::from_static(slice),
+                std::borrow::Cow::Owned(vec) => bytes::Bytes::from(vec),
+            })
+        },
+        None if custom_codec => quote! {
+            corgi::protocol::codec::encode_payload(&codec, &result)
+        },
+        None => quote! {
+            corgi::protocol::codec::EncodeResponse::encode_response(&result, &codec)
+        },
+    }
+}
+
+/// Whether `ty` names `bytes::Bytes`, which is passed and returned as-is.
+fn is_bytes(ty: &syn::Type) -> bool {
+    matches!(
+        ty,
+        syn::Type::Path(type_path)
+            if type_path.path.segments.last().is_some_and(|segment| {
+                segment.ident == "Bytes" && segment.arguments.is_none()
+            })
+    )
+}
+
+/// Return types that are sent without going through the codec.
+enum RawReturn {
+    Bytes,
+    Cow,
+}
+
+fn raw_return_kind(ty: &syn::Type) -> Option<RawReturn> {
+    let syn::Type::Path(type_path) = ty else {
+        return None;
+    };
+    let segment = type_path.path.segments.last()?;
+
+    if is_bytes(ty) {
+        return Some(RawReturn::Bytes);
+    }
+
+    if segment.ident == "Cow" {
+        let syn::PathArguments::AngleBracketed(arguments) = &segment.arguments else {
+            return None;
+        };
+        let is_static_byte_slice = arguments.args.iter().any(|argument| {
+            matches!(
+                argument,
+                syn::GenericArgument::Type(syn::Type::Slice(slice))
+                    if matches!(&*slice.elem, syn::Type::Path(elem) if elem.path.is_ident("u8"))
+            )
+        });
+        if is_static_byte_slice {
+            return Some(RawReturn::Cow);
+        }
+    }
+
+    None
+}
@@ -88,6 +88,23 @@ fn rpc_fn_should_create_rpc_function_instance_with_multiple_arguments_fn_with_re
     );
 }
 
+#[test]
+fn rpc_fn_should_populate_param_schema_ids() {
+    #[rpc_fn]
+    async fn foo_schema_ids(arg1: i32, arg2: String) {
+        println!("{arg1}-{arg2}");
+    }
+
+    assert_eq!(
+        __CORGI_RPC_foo_schema_ids.params[0].schema_id,
+        corgi::schema_id::<i32>()
+    );
+    assert_eq!(
+        __CORGI_RPC_foo_schema_ids.params[1].schema_id,
+        corgi::schema_id::<String>()
+    );
+}
+
 #[derive(Message, Clone, PartialEq)]
 struct Arg1(#[prost(string, tag = "1")] String);
 
@@ -160,8 +177,336 @@ async fn test_rpc_execution() {
     ];
 
     let handler = __CORGI_RPC_foo_multiple_args_return_type.handler.clone();
-    let result_bytes = handler(args, codec.clone()).await.unwrap();
+    let result_bytes = handler(args).await.unwrap();
 
     let result: i32 = codec.decode(&result_bytes).unwrap();
     assert_eq!(result, 30);
 }
+
+#[tokio::test]
+async fn rpc_fn_should_fail_call_on_corrupt_argument_by_default() {
+    #[rpc_fn]
+    async fn foo_strict_decode(arg1: i32, arg2: i32) -> i32 {
+        arg1 + arg2
+    }
+
+    let codec = corgi::protocol::codec::ProtobufCodec;
+    let args = vec![
+        codec.encode(&10_i32).unwrap(),
+        bytes::Bytes::from_static(&[0xff]),
+    ];
+
+    let handler = __CORGI_RPC_foo_strict_decode.handler.clone();
+    let result = handler(args).await;
+
+    assert_eq!(
+        __CORGI_RPC_foo_strict_decode.decode_policy,
+        corgi::container::DecodePolicy::Fail
+    );
+    assert!(matches!(
+        result,
+        Err(corgi::protocol::types::RpcError::ArgumentDecodeFailed)
+    ));
+}
+
+#[tokio::test]
+async fn rpc_fn_should_substitute_default_for_corrupt_argument_with_default_policy() {
+    #[rpc_fn(on_decode_error = "default")]
+    async fn foo_lenient_decode(arg1: i32, arg2: i32) -> i32 {
+        arg1 + arg2
+    }
+
+    let codec = corgi::protocol::codec::ProtobufCodec;
+    let args = vec![
+        codec.encode(&10_i32).unwrap(),
+        bytes::Bytes::from_static(&[0xff]),
+    ];
+
+    let handler = __CORGI_RPC_foo_lenient_decode.handler.clone();
+    let result_bytes = handler(args).await.unwrap();
+
+    let result: i32 = codec.decode(&result_bytes).unwrap();
+    assert_eq!(result, 10);
+}
+
+static SCHEMA_BLOB: &[u8] = b"static schema blob";
+
+#[tokio::test]
+async fn rpc_fn_should_send_static_bytes_return_without_copying() {
+    #[rpc_fn]
+    async fn foo_static_bytes() -> bytes::Bytes {
+        bytes::Bytes::from_static(SCHEMA_BLOB)
+    }
+
+    let handler = __CORGI_RPC_foo_static_bytes.handler.clone();
+
+    let first = handler(vec![]).await.unwrap();
+    let second = handler(vec![]).await.unwrap();
+
+    assert_eq!(first.as_ref(), SCHEMA_BLOB);
+    assert_eq!(first.as_ptr(), SCHEMA_BLOB.as_ptr());
+    assert_eq!(second.as_ptr(), SCHEMA_BLOB.as_ptr());
+}
+
+#[tokio::test]
+async fn rpc_fn_should_echo_slice_of_raw_argument_without_copying() {
+    #[rpc_fn]
+    async fn foo_echo_tail(payload: bytes::Bytes) -> bytes::Bytes {
+        payload.slice(4..)
+    }
+
+    let handler = __CORGI_RPC_foo_echo_tail.handler.clone();
+    let argument = bytes::Bytes::from(b"skipecho".to_vec());
+
+    let echoed = handler(vec![argument.clone()]).await.unwrap();
+
+    assert_eq!(echoed.as_ref(), b"echo");
+    assert_eq!(echoed.as_ptr(), argument[4..].as_ptr());
+}
+
+#[tokio::test]
+async fn rpc_fn_should_send_borrowed_cow_return_without_copying() {
+    use std::borrow::Cow;
+
+    #[rpc_fn]
+    async fn foo_static_cow() -> Cow<'static, [u8]> {
+        Cow::Borrowed(SCHEMA_BLOB)
+    }
+
+    let handler = __CORGI_RPC_foo_static_cow.handler.clone();
+
+    let result = handler(vec![]).await.unwrap();
+
+    assert_eq!(result.as_ptr(), SCHEMA_BLOB.as_ptr());
+}
+
+#[tokio::test]
+async fn rpc_fn_should_return_named_fields_for_rpc_response_struct() {
+    use corgi::protocol::codec::ResponseFieldsCodec;
+
+    #[derive(corgi_macros::RpcResponse)]
+    struct Totals {
+        total: u64,
+        label: String,
+    }
+
+    #[rpc_fn]
+    async fn foo_totals(a: u64, b: u64) -> Totals {
+        Totals {
+            total: a + b,
+            label: "sum".to_owned(),
+        }
+    }
+
+    let codec = corgi::protocol::codec::ProtobufCodec;
+    let args = vec![codec.encode(&2_u64).unwrap(), codec.encode(&3_u64).unwrap()];
+
+    let handler = __CORGI_RPC_foo_totals.handler.clone();
+    let result_bytes = handler(args).await.unwrap();
+    let fields = ResponseFieldsCodec.decode(&result_bytes).unwrap();
+
+    let total: u64 = codec.decode(fields.get("total").unwrap()).unwrap();
+    let label: String = codec.decode(fields.get("label").unwrap()).unwrap();
+    assert_eq!(total, 5);
+    assert_eq!(label, "sum");
+    assert!(fields.get("missing").is_none());
+}
+
+#[tokio::test]
+async fn rpc_fn_should_generate_invoke_function_for_direct_dispatch() {
+    #[rpc_fn]
+    async fn add(a: i32, b: i32) -> i32 {
+        a + b
+    }
+
+    let codec = corgi::protocol::codec::ProtobufCodec;
+    let args = vec![codec.encode(&4_i32).unwrap(), codec.encode(&5_i32).unwrap()];
+
+    let result_bytes = __corgi_invoke_add(args, codec.clone()).await.unwrap();
+
+    let result: i32 = codec.decode(&result_bytes).unwrap();
+    assert_eq!(result, 9);
+}
+
+#[tokio::test]
+async fn rpc_fn_should_fail_when_return_value_exceeds_max_response_size() {
+    use corgi::protocol::{codec::MAX_RESPONSE_SIZE, types::RpcError};
+
+    #[rpc_fn]
+    async fn oversized() -> Vec<u8> {
+        vec![0; MAX_RESPONSE_SIZE + 1]
+    }
+
+    let result = __corgi_invoke_oversized(vec![], corgi::protocol::codec::ProtobufCodec).await;
+
+    assert!(matches!(result, Err(RpcError::ResponseTooLarge)));
+}
+
+#[tokio::test]
+async fn rpc_fn_should_fail_when_raw_return_exceeds_max_response_size() {
+    use std::borrow::Cow;
+
+    use corgi::protocol::{codec::MAX_RESPONSE_SIZE, types::RpcError};
+
+    #[rpc_fn]
+    async fn oversized_bytes() -> bytes::Bytes {
+        bytes::Bytes::from(vec![0; MAX_RESPONSE_SIZE + 1])
+    }
+
+    #[rpc_fn]
+    async fn oversized_cow() -> Cow<'static, [u8]> {
+        Cow::Owned(vec![0; MAX_RESPONSE_SIZE + 1])
+    }
+
+    let bytes = (__CORGI_RPC_oversized_bytes.handler)(vec![]).await;
+    let cow = (__CORGI_RPC_oversized_cow.handler)(vec![]).await;
+
+    assert!(matches!(bytes, Err(RpcError::ResponseTooLarge)));
+    assert!(matches!(cow, Err(RpcError::ResponseTooLarge)));
+}
+
+#[tokio::test]
+async fn rpc_fn_should_fail_when_application_error_exceeds_max_response_size() {
+    use corgi::protocol::{codec::MAX_RESPONSE_SIZE, types::RpcError};
+
+    #[rpc_fn]
+    async fn oversized_error() -> Result<i32, Vec<u8>> {
+        Err(vec![0; MAX_RESPONSE_SIZE + 1])
+    }
+
+    let result = (__CORGI_RPC_oversized_error.handler)(vec![]).await;
+
+    assert!(matches!(result, Err(RpcError::ResponseTooLarge)));
+}
+
+#[test]
+fn rpc_fn_should_expose_declared_error_type_in_metadata() {
+    #[rpc_fn]
+    async fn foo_fallible(arg: i32) -> Result<i32, String> {
+        Ok(arg)
+    }
+
+    #[rpc_fn]
+    async fn foo_schema_free(arg: i32) -> i32 {
+        arg
+    }
+
+    assert_eq!(
+        __CORGI_RPC_foo_fallible.return_schema_id,
+        Some(corgi::schema_id::<i32>())
+    );
+    assert_eq!(
+        __CORGI_RPC_foo_fallible.error_schema_id,
+        Some(corgi::schema_id::<String>())
+    );
+    assert_eq!(__CORGI_RPC_foo_schema_free.error_schema_id, None);
+}
+
+#[tokio::test]
+async fn rpc_fn_should_encode_err_as_application_error_of_declared_type() {
+    use corgi::protocol::types::RpcError;
+
+    #[rpc_fn]
+    async fn foo_checked_div(a: i32, b: i32) -> Result<i32, String> {
+        a.checked_div(b)
+            .ok_or_else(|| "division by zero".to_owned())
+    }
+
+    let codec = corgi::protocol::codec::ProtobufCodec;
+    let ok = __corgi_invoke_foo_checked_div(
+        vec![codec.encode(&6_i32).unwrap(), codec.encode(&3_i32).unwrap()],
+        codec.clone(),
+    )
+    .await
+    .unwrap();
+    let error = __corgi_invoke_foo_checked_div(
+        vec![codec.encode(&6_i32).unwrap(), codec.encode(&0_i32).unwrap()],
+        codec.clone(),
+    )
+    .await
+    .unwrap_err();
+
+    assert_eq!(codec.decode::<i32>(&ok).unwrap(), 2);
+    assert!(matches!(
+        error,
+        RpcError::Application { schema_id, .. } if schema_id == corgi::schema_id::<String>()
+    ));
+    assert_eq!(
+        error.application_error::<String>().as_deref(),
+        Some("division by zero")
+    );
+}
+
+#[tokio::test]
+async fn rpc_fn_should_pass_rpc_error_through_and_send_raw_ok_as_is() {
+    use corgi::protocol::types::RpcError;
+
+    #[rpc_fn]
+    async fn foo_raw_or_timeout(ready: bool) -> Result<bytes::Bytes, RpcError> {
+        if ready {
+            Ok(bytes::Bytes::from_static(b"raw"))
+        } else {
+            Err(RpcError::Timeout)
+        }
+    }
+
+    let codec = corgi::protocol::codec::ProtobufCodec;
+    let ok = __corgi_invoke_foo_raw_or_timeout(vec![codec.encode(&true).unwrap()], codec.clone())
+        .await
+        .unwrap();
+    let error =
+        __corgi_invoke_foo_raw_or_timeout(vec![codec.encode(&false).unwrap()], codec.clone()).await;
+
+    assert_eq!(ok.as_ref(), b"raw");
+    assert!(matches!(error, Err(RpcError::Timeout)));
+    assert_eq!(__CORGI_RPC_foo_raw_or_timeout.error_schema_id, None);
+}
+
+#[test]
+fn rpc_fn_should_register_custom_name_independently_of_rust_name() {
+    #[rpc_fn(name = "math.sum")]
+    async fn foo_renamed(a: i32, b: i32) -> i32 {
+        a + b
+    }
+
+    #[rpc_fn(name = "math.sum", version = 2)]
+    async fn foo_renamed_v2(a: i32, b: i32) -> i32 {
+        a + b
+    }
+
+    let mut container = corgi::Container::default();
+    container.register(&__CORGI_RPC_foo_renamed);
+
+    assert_eq!(__CORGI_RPC_foo_renamed.name, "math.sum");
+    assert_eq!(__CORGI_RPC_foo_renamed_v2.name, "math.sum@2");
+    assert!(container.find("math.sum").is_some());
+    assert!(container.find("foo_renamed").is_none());
+}
+
+#[tokio::test]
+async fn rpc_fn_should_decode_borrowed_arguments_into_owned_values() {
+    #[rpc_fn]
+    async fn foo_borrowed(name: &str, data: &[u8]) -> String {
+        format!("{name}:{}", data.len())
+    }
+
+    let codec = corgi::protocol::codec::ProtobufCodec;
+    let args = vec![
+        codec.encode(&"corgi".to_owned()).unwrap(),
+        codec.encode(&vec![1_u8, 2, 3]).unwrap(),
+    ];
+
+    let result = __corgi_invoke_foo_borrowed(args, codec.clone())
+        .await
+        .unwrap();
+
+    assert_eq!(codec.decode::<String>(&result).unwrap(), "corgi:3");
+    assert_eq!(
+        __CORGI_RPC_foo_borrowed.params[0].schema_id,
+        corgi::schema_id::<String>()
+    );
+    assert_eq!(
+        __CORGI_RPC_foo_borrowed.params[1].schema_id,
+        corgi::schema_id::<Vec<u8>>()
+    );
+}
@@ -0,0 +1,8 @@
+//! Diagnostics `rpc_fn` emits for signatures it cannot turn into RPC
+//! functions. Run with `TRYBUILD=overwrite` to accept changed messages.
+
+#[test]
+fn rpc_fn_should_reject_unsupported_signatures_with_spanned_errors() {
+    let cases = trybuild::TestCases::new();
+    cases.compile_fail("tests/ui/*.rs");
+}
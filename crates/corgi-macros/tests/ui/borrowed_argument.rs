@@ -0,0 +1,8 @@
+use corgi::rpc_fn;
+
+#[rpc_fn]
+async fn double(value: &i32) -> i32 {
+    value * 2
+}
+
+fn main() {}
@@ -0,0 +1,12 @@
+use corgi::rpc_fn;
+
+struct Counter(u32);
+
+impl Counter {
+    #[rpc_fn]
+    async fn increment(&self, by: u32) -> u32 {
+        self.0 + by
+    }
+}
+
+fn main() {}
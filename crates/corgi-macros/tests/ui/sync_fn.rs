@@ -0,0 +1,8 @@
+use corgi::rpc_fn;
+
+#[rpc_fn]
+fn add(a: i32, b: i32) -> i32 {
+    a + b
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use corgi::rpc_fn;
+
+#[rpc_fn]
+async fn add((a, b): (i32, i32)) -> i32 {
+    a + b
+}
+
+fn main() {}
@@ -0,0 +1,169 @@
+//! Functions a server answers itself, without any registration.
+//!
+//! Their names live under the reserved `__corgi.` prefix and shadow any
+//! function registered under the same name. They are disabled unless
+//! [`ServerConfig::builtin_functions`] is set, and a call to one of them is
+//! then answered with [`RpcError::FeatureDisabled`], so clients can tell a
+//! server that doesn't offer them from one that never heard of the name.
+//!
+//! Built-in calls go through the same [`RpcService`](crate::RpcService)
+//! as any other, so `tower` layers passed to
+//! [`RpcServer::serve`](crate::RpcServer::serve) can authorize them by
+//! function name.
+
+use std::time::Duration;
+
+use bytes::Bytes;
+
+use crate::{
+    Container, ServerConfig,
+    metrics::ServerCounters,
+    protocol::{
+        codec::{
+            Endianness, MAX_MESSAGE_SIZE, MAX_RESPONSE_SIZE, ProtobufCodec, ResponseFieldsCodec,
+            UDP_CHUNK_SIZE,
+        },
+        types::{Envelope, ResponseFields, RpcError},
+    },
+};
+
+/// Returns the container's [`Container::export_schema`], for clients to
+/// discover the functions a server offers. Takes no arguments.
+pub const REFLECT: &str = "__corgi.reflect";
+
+/// Returns the server's effective configuration as
+/// [`ResponseFields`], for diagnosing settings that drifted apart between
+/// client and server. Takes no arguments.
+///
+/// Every field is a protobuf `uint64`, durations in milliseconds, except
+/// `endianness`, `response_ids` and `unknown_message_kinds`, which are
+/// strings:
+///
+/// - `udp_chunk_size`: size of the datagrams messages are cut into, the
+///   path MTU the server assumes.
+/// - `max_message_size`, `max_response_size`
+/// - `max_partial_messages`, `reassembly_timeout_ms`
+/// - `dedup_cache_capacity`, `dedup_cache_ttl_ms`
+/// - `maintenance_interval_ms`
+/// - `peer_rate_limit_bytes_per_second`, `peer_rate_limit_burst`: only when
+///   a rate limit is set.
+/// - `quarantine_max_violations`, `quarantine_window_ms`,
+///   `quarantine_duration_ms`: only when a quarantine policy is set.
+///
+/// The configuration holds no secrets, so nothing is redacted.
+pub const CONFIG: &str = "__corgi.config";
+
+/// Returns [`RpcServer::metrics_prometheus`](crate::RpcServer::metrics_prometheus)
+/// as a protobuf `string`, for pulling counters without a scrape endpoint.
+/// Takes no arguments.
+pub const METRICS: &str = "__corgi.metrics";
+
+/// Like [`METRICS`], zeroing the counters in the same step. Any peer reaching
+/// the server can call it, so put a `tower` layer in front that only lets
+/// operators through.
+pub const METRICS_RESET: &str = "__corgi.metrics_reset";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Builtin {
+    Reflect,
+    Config,
+    Metrics,
+    MetricsReset,
+}
+
+impl Builtin {
+    fn find(name: &str) -> Option<Self> {
+        match name {
+            REFLECT => Some(Builtin::Reflect),
+            CONFIG => Some(Builtin::Config),
+            METRICS => Some(Builtin::Metrics),
+            METRICS_RESET => Some(Builtin::MetricsReset),
+            _ => None,
+        }
+    }
+}
+
+/// The server state built-in functions report on.
+#[derive(Clone, Copy)]
+pub(crate) struct Builtins<'a> {
+    pub(crate) container: &'a Container,
+    pub(crate) config: &'a ServerConfig,
+    pub(crate) counters: &'a ServerCounters,
+}
+
+/// Answers `envelope` when it calls a built-in function, or returns `None`
+/// for it to be dispatched to the container. Without `builtins`, as for an
+/// [`RpcService`](crate::RpcService) not created by a server, built-in
+/// functions are disabled.
+pub(crate) fn call(
+    builtins: Option<Builtins<'_>>,
+    envelope: &Envelope,
+) -> Option<Result<Bytes, RpcError>> {
+    let builtin = Builtin::find(envelope.fn_name())?;
+    let Some(builtins) = builtins.filter(|builtins| builtins.config.builtin_functions) else {
+        return Some(Err(RpcError::FeatureDisabled));
+    };
+
+    if !envelope.parameters().is_empty() {
+        return Some(Err(RpcError::ArityMismatch));
+    }
+
+    Some(match builtin {
+        Builtin::Reflect => builtins.container.export_schema(),
+        Builtin::Config => config(builtins.config),
+        Builtin::Metrics => ProtobufCodec.encode(&builtins.counters.snapshot().to_prometheus()),
+        Builtin::MetricsReset => ProtobufCodec.encode(&builtins.counters.reset().to_prometheus()),
+    })
+}
+
+fn config(config: &ServerConfig) -> Result<Bytes, RpcError> {
+    let codec = ProtobufCodec;
+    let mut fields = Vec::new();
+    let mut number = |name: &str, value: u64| -> Result<(), RpcError> {
+        fields.push((name.to_owned(), codec.encode(&value)?));
+        Ok(())
+    };
+    let millis = |duration: Duration| u64::try_from(duration.as_millis()).unwrap_or(u64::MAX);
+
+    number("udp_chunk_size", UDP_CHUNK_SIZE as u64)?;
+    number("max_message_size", MAX_MESSAGE_SIZE as u64)?;
+    number("max_response_size", MAX_RESPONSE_SIZE as u64)?;
+    number("max_partial_messages", config.max_partial_messages as u64)?;
+    number("reassembly_timeout_ms", millis(config.reassembly_timeout))?;
+    number("dedup_cache_capacity", config.dedup_cache_capacity as u64)?;
+    number("dedup_cache_ttl_ms", millis(config.dedup_cache_ttl))?;
+    number(
+        "maintenance_interval_ms",
+        millis(config.maintenance_interval),
+    )?;
+    if let Some(limit) = config.peer_rate_limit {
+        number("peer_rate_limit_bytes_per_second", limit.bytes_per_second)?;
+        number("peer_rate_limit_burst", limit.burst)?;
+    }
+    if let Some(policy) = config.quarantine {
+        number(
+            "quarantine_max_violations",
+            u64::from(policy.max_violations),
+        )?;
+        number("quarantine_window_ms", millis(policy.window))?;
+        number("quarantine_duration_ms", millis(policy.duration))?;
+    }
+
+    let endianness = match config.endianness {
+        Endianness::Little => "little",
+        Endianness::Big => "big",
+    };
+    let names = [
+        ("endianness", endianness.to_owned()),
+        ("response_ids", format!("{:?}", config.response_ids)),
+        (
+            "unknown_message_kinds",
+            format!("{:?}", config.unknown_message_kinds),
+        ),
+    ];
+    for (name, value) in names {
+        fields.push((name.to_owned(), codec.encode(&value)?));
+    }
+
+    ResponseFieldsCodec.encode(&ResponseFields::new(fields))
+}
@@ -0,0 +1,694 @@
+use std::{
+    collections::{HashMap, VecDeque, hash_map::RandomState},
+    hash::BuildHasher,
+    io,
+    net::{Ipv4Addr, Ipv6Addr, SocketAddr},
+    pin::pin,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use bytes::{Bytes, BytesMut};
+use tokio::{
+    net::UdpSocket,
+    sync::{Notify, oneshot},
+    task::JoinHandle,
+};
+
+use crate::protocol::{
+    codec::{Endianness, EnvelopeCodec, FailureCodec, PackageChunkCodec, UDP_CHUNK_SIZE},
+    parser::Parser,
+    types::{CallId, Envelope, MessageKind, RpcError, TraceContext},
+};
+
+/// Produces the [`CallId`] of every call an [`RpcClient`] issues.
+///
+/// Replies are matched to calls by id alone, so a generator must not hand out
+/// an id that is still in flight.
+pub trait CallIdGenerator: Send + Sync {
+    fn next_call_id(&self) -> CallId;
+}
+
+/// Hands out `1, 2, 3, ...`. The default generator.
+#[derive(Debug, Default)]
+pub struct MonotonicCallIds {
+    next: AtomicU64,
+}
+
+impl CallIdGenerator for MonotonicCallIds {
+    fn next_call_id(&self) -> CallId {
+        self.next.fetch_add(1, Ordering::Relaxed).wrapping_add(1)
+    }
+}
+
+/// Hands out unpredictable ids, so that clients restarting on the same
+/// address do not reuse the ids of their previous run.
+#[derive(Debug, Default)]
+pub struct RandomCallIds {
+    state: RandomState,
+    counter: AtomicU64,
+}
+
+impl CallIdGenerator for RandomCallIds {
+    fn next_call_id(&self) -> CallId {
+        self.state
+            .hash_one(self.counter.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+type PendingCalls = Arc<Mutex<HashMap<CallId, oneshot::Sender<Result<Bytes, RpcError>>>>>;
+
+/// Bytes held by the calls of a client, checked against its
+/// [`MemoryBudget`].
+#[derive(Debug, Default)]
+struct MemoryUsage {
+    /// Encoded requests of calls in flight.
+    requests: AtomicUsize,
+    /// Chunks of replies being reassembled, as last reported by the receive
+    /// task.
+    replies: AtomicUsize,
+    /// Woken whenever bytes are released.
+    released: Notify,
+}
+
+impl MemoryUsage {
+    fn try_reserve(&self, bytes: usize, budget: usize) -> bool {
+        self.requests
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |requests| {
+                let held = requests + self.replies.load(Ordering::SeqCst);
+                (requests == 0 || held + bytes <= budget).then_some(requests + bytes)
+            })
+            .is_ok()
+    }
+
+    fn release(&self, bytes: usize) {
+        self.requests.fetch_sub(bytes, Ordering::SeqCst);
+        self.released.notify_waiters();
+    }
+
+    fn set_replies(&self, bytes: usize) {
+        if self.replies.swap(bytes, Ordering::SeqCst) > bytes {
+            self.released.notify_waiters();
+        }
+    }
+}
+
+/// Bytes of a call's request counted against the budget until it completes.
+struct Reservation<'a> {
+    usage: &'a MemoryUsage,
+    bytes: usize,
+}
+
+impl Drop for Reservation<'_> {
+    fn drop(&mut self) {
+        self.usage.release(self.bytes);
+    }
+}
+
+/// Whether a socket error means no server listens at the peer address
+/// anymore, as reported by an ICMP unreachable, rather than a local hiccup.
+fn is_peer_gone(error: &io::Error) -> bool {
+    matches!(
+        error.kind(),
+        io::ErrorKind::ConnectionRefused
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::HostUnreachable
+            | io::ErrorKind::NetworkUnreachable
+    )
+}
+
+/// Fails every call waiting on `pending` with [`RpcError::PeerUnreachable`].
+fn fail_pending(pending: &PendingCalls, server_address: SocketAddr) {
+    let senders: Vec<_> = pending.lock().unwrap().drain().collect();
+    if !senders.is_empty() {
+        tracing::warn!(
+            "{server_address} is unreachable, failing {} calls in flight",
+            senders.len()
+        );
+    }
+    for (_, sender) in senders {
+        drop(sender.send(Err(RpcError::PeerUnreachable)));
+    }
+}
+
+/// LATENCY_BUCKETS indicates upper bounds of the client call latency histogram buckets
+const LATENCY_BUCKETS: [Duration; 8] = [
+    Duration::from_millis(1),
+    Duration::from_millis(5),
+    Duration::from_millis(10),
+    Duration::from_millis(50),
+    Duration::from_millis(100),
+    Duration::from_millis(500),
+    Duration::from_secs(1),
+    Duration::from_secs(5),
+];
+
+/// End-to-end latencies of the completed calls of an [`RpcClient`], measured
+/// from sending the first chunk to receiving the whole reply.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LatencyHistogram {
+    /// Calls per bucket of [`LATENCY_BUCKETS`], plus one for slower calls.
+    counts: [u64; LATENCY_BUCKETS.len() + 1],
+    count: u64,
+    sum: Duration,
+    max: Duration,
+}
+
+impl LatencyHistogram {
+    fn record(&mut self, latency: Duration) {
+        let bucket = LATENCY_BUCKETS
+            .iter()
+            .position(|bound| latency <= *bound)
+            .unwrap_or(LATENCY_BUCKETS.len());
+        self.counts[bucket] += 1;
+        self.count += 1;
+        self.sum += latency;
+        self.max = self.max.max(latency);
+    }
+
+    /// Number of completed calls.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Total latency of all completed calls.
+    pub fn sum(&self) -> Duration {
+        self.sum
+    }
+
+    /// Latency of the slowest completed call.
+    pub fn max(&self) -> Duration {
+        self.max
+    }
+
+    /// Returns `(upper bound, calls)` per bucket, from fastest to slowest.
+    /// The last bucket has no upper bound.
+    pub fn buckets(&self) -> impl Iterator<Item = (Option<Duration>, u64)> + '_ {
+        LATENCY_BUCKETS
+            .iter()
+            .copied()
+            .map(Some)
+            .chain([None])
+            .zip(self.counts.iter().copied())
+    }
+}
+
+/// Paces the chunks an [`RpcClient`] sends, so a large message is spread
+/// out instead of dumped onto a constrained link at once.
+///
+/// There are no per-chunk acknowledgements, so a chunk counts as outstanding
+/// for `interval` after it was sent, an estimate of the time the link takes
+/// to drain it. Once `chunks` are outstanding, the next chunk waits for the
+/// oldest to expire.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkWindow {
+    /// Chunks outstanding at most, across all calls of the client.
+    pub chunks: usize,
+    /// How long a sent chunk stays outstanding.
+    pub interval: Duration,
+}
+
+/// Caps the memory the calls of an [`RpcClient`] hold at once: their
+/// encoded requests, kept for retransmits, and the chunks of replies still
+/// being reassembled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryBudget {
+    /// Bytes held at most. A single call larger than the whole budget is
+    /// let through once no other call is in flight, rather than never.
+    pub bytes: usize,
+    /// What a call that doesn't fit does.
+    pub when_exhausted: BudgetExhausted,
+}
+
+/// What a call does when it would exceed the [`MemoryBudget`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BudgetExhausted {
+    /// The call waits until enough calls in flight completed.
+    #[default]
+    Wait,
+    /// The call fails at once with [`RpcError::ClientBusy`].
+    Fail,
+}
+
+/// Tunables of an [`RpcClient`].
+#[derive(Debug, Clone)]
+pub struct ClientConfig {
+    /// How long each attempt of a call waits for its reply. Defaults to 5
+    /// seconds.
+    pub timeout: Duration,
+    /// Times a call is sent again, under the same [`CallId`], after an
+    /// attempt timed out. A call fails with [`RpcError::Timeout`] once the
+    /// last attempt timed out. Defaults to 2.
+    ///
+    /// The server answers a retransmit of a completed call from its response
+    /// cache, but one arriving while the handler still runs executes it
+    /// again, so keep `timeout` above the handler's running time for calls
+    /// that must not run twice.
+    pub retries: u32,
+    /// Byte order of chunk headers, which must match the server's. Defaults
+    /// to [`Endianness::Little`].
+    pub endianness: Endianness,
+    /// How long an incomplete reply waits for its remaining chunks before
+    /// it is dropped. Defaults to 10 seconds, like the server's.
+    ///
+    /// Replies are swept about this often, so the chunks a lossy link never
+    /// delivers can't fill the reassembly table for good.
+    pub reassembly_timeout: Duration,
+    /// Total time a call may take, all attempts included. A call still
+    /// unanswered by then fails with [`RpcError::Timeout`], even if it has
+    /// retries left. Disabled by default, leaving calls bounded by
+    /// `timeout` and `retries` alone.
+    pub deadline: Option<Duration>,
+    /// Window pacing the chunks of all calls, retransmits included.
+    /// Disabled by default, sending every chunk as soon as possible.
+    pub chunk_window: Option<ChunkWindow>,
+    /// Budget for the memory held by calls in flight. Disabled by default.
+    pub memory_budget: Option<MemoryBudget>,
+    /// Whether request chunks carry a payload checksum. Reply chunks are
+    /// validated whenever the server included one. Defaults to true.
+    pub checksums: bool,
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(5),
+            retries: 2,
+            endianness: Endianness::default(),
+            reassembly_timeout: Duration::from_secs(10),
+            deadline: None,
+            chunk_window: None,
+            memory_budget: None,
+            checksums: true,
+        }
+    }
+}
+
+/// Issues calls to an [`RpcServer`](crate::RpcServer) over UDP.
+///
+/// The socket is connected to a single server. A background task reassembles
+/// incoming replies and hands each one to the call waiting on its
+/// [`CallId`], so any number of calls can be in flight at once.
+pub struct RpcClient {
+    connection: Arc<UdpSocket>,
+    server_address: SocketAddr,
+    call_ids: Box<dyn CallIdGenerator>,
+    config: ClientConfig,
+    pending: PendingCalls,
+    receiver: JoinHandle<()>,
+    latencies: Mutex<LatencyHistogram>,
+    /// When the chunks still outstanding under [`ClientConfig::chunk_window`]
+    /// were sent, oldest first. Held across sends, so concurrent calls take
+    /// turns.
+    sent_chunks: tokio::sync::Mutex<VecDeque<Instant>>,
+    memory: Arc<MemoryUsage>,
+    chunk_codec: PackageChunkCodec,
+    envelope_codec: EnvelopeCodec,
+}
+
+impl RpcClient {
+    /// Binds an ephemeral local port of the server's address family and
+    /// connects it to `server_address`.
+    pub async fn connect_udp(server_address: SocketAddr) -> Result<Self, RpcError> {
+        let bind_address = match server_address {
+            SocketAddr::V4(_) => SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0)),
+            SocketAddr::V6(_) => SocketAddr::from((Ipv6Addr::UNSPECIFIED, 0)),
+        };
+        tracing::trace!("Creating RpcClient. binding UDP socket on address {bind_address}");
+        let socket = UdpSocket::bind(bind_address)
+            .await
+            .map_err(RpcError::SocketBinding)?;
+        socket
+            .connect(server_address)
+            .await
+            .map_err(RpcError::SocketBinding)?;
+
+        let connection = Arc::new(socket);
+        let pending = PendingCalls::default();
+        let memory = Arc::new(MemoryUsage::default());
+        let config = ClientConfig::default();
+        let receiver = tokio::spawn(receive_replies(
+            Arc::clone(&connection),
+            server_address,
+            Arc::clone(&pending),
+            Arc::clone(&memory),
+            PackageChunkCodec::new(config.endianness),
+            config.reassembly_timeout,
+        ));
+        tracing::debug!("Successfully connected RpcClient to {server_address}.");
+
+        Ok(Self {
+            connection,
+            server_address,
+            call_ids: Box::new(MonotonicCallIds::default()),
+            config,
+            pending,
+            receiver,
+            latencies: Mutex::default(),
+            sent_chunks: tokio::sync::Mutex::default(),
+            memory,
+            chunk_codec: PackageChunkCodec::default(),
+            envelope_codec: EnvelopeCodec,
+        })
+    }
+
+    /// Renders [`RpcClient::latency_histogram`] in Prometheus text
+    /// exposition format.
+    pub fn metrics_prometheus(&self) -> String {
+        self.latency_histogram().to_prometheus()
+    }
+
+    /// Replaces the [`CallId`] generator, e.g. with [`RandomCallIds`].
+    pub fn with_call_ids(mut self, call_ids: impl CallIdGenerator + 'static) -> Self {
+        self.call_ids = Box::new(call_ids);
+        self
+    }
+
+    /// Replaces the [`ClientConfig`], e.g. to change timeouts and retries.
+    ///
+    /// Changing [`ClientConfig::endianness`] or
+    /// [`ClientConfig::reassembly_timeout`] aborts the background receive
+    /// task and spawns a new one using them, dropping any reply it was
+    /// still reassembling.
+    pub fn with_config(mut self, config: ClientConfig) -> Self {
+        self.chunk_codec =
+            PackageChunkCodec::new(config.endianness).with_checksums(config.checksums);
+        if config.endianness != self.config.endianness
+            || config.reassembly_timeout != self.config.reassembly_timeout
+        {
+            // No call can be in flight while the client is moved in here, so
+            // the receive task can be replaced by one using the new settings.
+            self.receiver.abort();
+            self.receiver = tokio::spawn(receive_replies(
+                Arc::clone(&self.connection),
+                self.server_address,
+                Arc::clone(&self.pending),
+                Arc::clone(&self.memory),
+                self.chunk_codec,
+                config.reassembly_timeout,
+            ));
+        }
+        self.config = config;
+        self
+    }
+
+    /// Returns the address of the server this client is connected to.
+    pub fn server_address(&self) -> SocketAddr {
+        self.server_address
+    }
+
+    /// Returns a snapshot of the latencies of all calls completed so far.
+    pub fn latency_histogram(&self) -> LatencyHistogram {
+        self.latencies.lock().unwrap().clone()
+    }
+
+    /// Calls `fn_name` with already encoded `args` and returns the raw reply.
+    ///
+    /// A call the server answers with a failure fails with the error it
+    /// reported: [`RpcError::Application`] for the handler's declared error
+    /// type, decoded with [`RpcError::application_error`], and
+    /// [`RpcError::Remote`] carrying the stable code of any other error.
+    ///
+    /// Unanswered calls are retransmitted as configured by [`ClientConfig`]
+    /// and fail with [`RpcError::Timeout`] after the last attempt or once
+    /// the [`ClientConfig::deadline`] passed, whichever comes first. A call
+    /// whose datagrams the socket refuses fails with
+    /// [`RpcError::Transport`] carrying the socket's error.
+    ///
+    /// Once the socket reports the server as unreachable, on sending or
+    /// receiving, this call and every other one in flight fail at once with
+    /// [`RpcError::PeerUnreachable`] instead of waiting for their timeouts.
+    pub async fn call(&self, fn_name: &str, args: Vec<Bytes>) -> Result<Bytes, RpcError> {
+        self.call_timed(fn_name, args)
+            .await
+            .map(|(reply, _latency)| reply)
+    }
+
+    /// Like [`RpcClient::call`], also returning the call's end-to-end
+    /// latency, retransmits included.
+    pub async fn call_timed(
+        &self,
+        fn_name: &str,
+        args: Vec<Bytes>,
+    ) -> Result<(Bytes, Duration), RpcError> {
+        self.send_call(Envelope::new(fn_name.to_owned(), args))
+            .await
+    }
+
+    /// Like [`RpcClient::call`], attaching `headers` to the call. The
+    /// handler reads them with [`RpcContext::headers`](crate::RpcContext::headers).
+    pub async fn call_with_headers(
+        &self,
+        fn_name: &str,
+        args: Vec<Bytes>,
+        headers: Vec<(Bytes, Bytes)>,
+    ) -> Result<Bytes, RpcError> {
+        self.send_call(Envelope::new(fn_name.to_owned(), args).with_headers(headers))
+            .await
+            .map(|(reply, _latency)| reply)
+    }
+
+    /// Like [`RpcClient::call`], asking the server to give the handler at
+    /// most `deadline` to finish. A handler exceeding it is cancelled and
+    /// the call fails with [`RpcError::Remote`] carrying the code of
+    /// [`RpcError::DeadlineExceeded`].
+    ///
+    /// The deadline only bounds the handler's running time: transit and
+    /// retransmits are governed by [`ClientConfig`] as for any call.
+    pub async fn call_with_deadline(
+        &self,
+        fn_name: &str,
+        args: Vec<Bytes>,
+        deadline: Duration,
+    ) -> Result<Bytes, RpcError> {
+        self.send_call(Envelope::new(fn_name.to_owned(), args).with_deadline(deadline))
+            .await
+            .map(|(reply, _latency)| reply)
+    }
+
+    /// Like [`RpcClient::call`], making the call part of `trace`, see
+    /// [`TraceContext`].
+    pub async fn call_with_trace(
+        &self,
+        fn_name: &str,
+        args: Vec<Bytes>,
+        trace: TraceContext,
+    ) -> Result<Bytes, RpcError> {
+        self.send_call(Envelope::new(fn_name.to_owned(), args).with_trace(trace))
+            .await
+            .map(|(reply, _latency)| reply)
+    }
+
+    async fn send_call(&self, envelope: Envelope) -> Result<(Bytes, Duration), RpcError> {
+        let payload = self.envelope_codec.encode(envelope)?;
+        let (call_id, reply) = self.register_call();
+        let _pending = PendingCall {
+            pending: &self.pending,
+            call_id,
+        };
+
+        let datagrams =
+            self.chunk_codec
+                .encode_message(call_id, 0, MessageKind::Request, &payload)?;
+        let mut reply = reply;
+        let started_at = Instant::now();
+
+        let attempts = async {
+            let request_bytes = datagrams.iter().map(Bytes::len).sum();
+            let _reservation = self.reserve(request_bytes).await?;
+
+            for attempt in 0..=self.config.retries {
+                if attempt > 0 {
+                    tracing::debug!(
+                        "Call {call_id} to {} timed out, retransmitting (attempt {})",
+                        self.server_address,
+                        attempt + 1
+                    );
+                }
+                self.send_datagrams(&datagrams).await?;
+
+                if let Ok(reply) = tokio::time::timeout(self.config.timeout, &mut reply).await {
+                    // The sender only disappears when the receive task is gone.
+                    let reply = reply.map_err(|_| RpcError::ConnectionClosed)?;
+                    let latency = started_at.elapsed();
+                    self.latencies.lock().unwrap().record(latency);
+                    return Ok((reply?, latency));
+                }
+            }
+
+            Err(RpcError::Timeout)
+        };
+
+        match self.config.deadline {
+            Some(deadline) => tokio::time::timeout(deadline, attempts)
+                .await
+                .unwrap_or(Err(RpcError::Timeout)),
+            None => attempts.await,
+        }
+    }
+
+    fn register_call(&self) -> (CallId, oneshot::Receiver<Result<Bytes, RpcError>>) {
+        let (sender, receiver) = oneshot::channel();
+        let mut pending = self.pending.lock().unwrap();
+        let mut call_id = self.call_ids.next_call_id();
+        while pending.contains_key(&call_id) {
+            call_id = self.call_ids.next_call_id();
+        }
+        pending.insert(call_id, sender);
+        (call_id, receiver)
+    }
+
+    /// Counts `bytes` against the [`ClientConfig::memory_budget`], waiting
+    /// for room or failing as it says.
+    async fn reserve(&self, bytes: usize) -> Result<Option<Reservation<'_>>, RpcError> {
+        let Some(budget) = self.config.memory_budget else {
+            return Ok(None);
+        };
+
+        loop {
+            // Registered before checking, so a release in between still
+            // wakes this call.
+            let mut released = pin!(self.memory.released.notified());
+            released.as_mut().enable();
+            if self.memory.try_reserve(bytes, budget.bytes) {
+                return Ok(Some(Reservation {
+                    usage: &self.memory,
+                    bytes,
+                }));
+            }
+            if budget.when_exhausted == BudgetExhausted::Fail {
+                return Err(RpcError::ClientBusy);
+            }
+            released.await;
+        }
+    }
+
+    async fn send_datagrams(&self, datagrams: &[Bytes]) -> Result<(), RpcError> {
+        let mut window = match self.config.chunk_window {
+            Some(window) => Some((window, self.sent_chunks.lock().await)),
+            None => None,
+        };
+
+        for datagram in datagrams {
+            if let Some((window, sent_chunks)) = window.as_mut() {
+                while sent_chunks.len() >= window.chunks.max(1) {
+                    let sent_at = sent_chunks.pop_front().unwrap_or_else(Instant::now);
+                    tokio::time::sleep_until((sent_at + window.interval).into()).await;
+                }
+            }
+            if let Err(error) = self.connection.send(datagram).await {
+                tracing::error!(
+                    "Failed to send chunk to {}. Error: {error}",
+                    self.server_address
+                );
+                if is_peer_gone(&error) {
+                    fail_pending(&self.pending, self.server_address);
+                    return Err(RpcError::PeerUnreachable);
+                }
+                return Err(RpcError::Transport(error));
+            }
+            if let Some((_, sent_chunks)) = window.as_mut() {
+                sent_chunks.push_back(Instant::now());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Forgets a call once its caller stops waiting, whether it got a reply,
+/// failed to send or was cancelled.
+struct PendingCall<'a> {
+    pending: &'a PendingCalls,
+    call_id: CallId,
+}
+
+impl Drop for PendingCall<'_> {
+    fn drop(&mut self) {
+        self.pending.lock().unwrap().remove(&self.call_id);
+    }
+}
+
+impl Drop for RpcClient {
+    fn drop(&mut self) {
+        self.receiver.abort();
+    }
+}
+
+async fn receive_replies(
+    connection: Arc<UdpSocket>,
+    server_address: SocketAddr,
+    pending: PendingCalls,
+    memory: Arc<MemoryUsage>,
+    chunk_codec: PackageChunkCodec,
+    reassembly_timeout: Duration,
+) {
+    let mut buf = BytesMut::with_capacity(UDP_CHUNK_SIZE);
+    let mut parser = Parser::default().with_chunk_codec(chunk_codec);
+    let error_codec = FailureCodec;
+    // `interval` panics on a zero period.
+    let mut maintenance = tokio::time::interval(reassembly_timeout.max(Duration::from_millis(1)));
+
+    loop {
+        buf.clear();
+        buf.resize(UDP_CHUNK_SIZE, 0);
+        let received = tokio::select! {
+            received = connection.recv(&mut buf) => received,
+            _ = maintenance.tick() => {
+                let timed_out = parser.maintenance_tick(Instant::now(), reassembly_timeout);
+                memory.set_replies(parser.buffered_bytes());
+                if timed_out > 0 {
+                    tracing::debug!("Dropped {timed_out} incomplete replies from {server_address}");
+                }
+                continue;
+            }
+        };
+        let len = match received {
+            Ok(len) => len,
+            // A connected socket reports an ICMP unreachable answering an
+            // earlier send on the next receive.
+            Err(error) if is_peer_gone(&error) => {
+                tracing::debug!("Receiving from {server_address} failed. Error: {error}");
+                fail_pending(&pending, server_address);
+                continue;
+            }
+            Err(error) => {
+                tracing::error!("Failed to receive from socket connection. Error: {error}");
+                continue;
+            }
+        };
+        buf.truncate(len);
+
+        // Responses name the call they answer in `correlation_id`; their own
+        // call id only keys reassembly.
+        let reassembled = parser.reassemble(server_address, &buf);
+        memory.set_replies(parser.buffered_bytes());
+        let (call_id, reply) = match reassembled {
+            Ok(Some(message)) => match message.kind {
+                MessageKind::Response => (message.correlation_id, Ok(message.payload)),
+                MessageKind::Failure => (
+                    message.correlation_id,
+                    error_codec.decode(&message.payload).and_then(Err),
+                ),
+                kind => {
+                    tracing::debug!("Dropping {kind:?} message {}", message.call_id);
+                    continue;
+                }
+            },
+            Ok(None) => continue,
+            Err(error) => {
+                tracing::debug!("Dropping datagram from {server_address}. Error: {error:?}");
+                continue;
+            }
+        };
+
+        match pending.lock().unwrap().remove(&call_id) {
+            // The caller may have given up already; nothing to do then.
+            Some(sender) => drop(sender.send(reply)),
+            None => tracing::debug!("Dropping reply for unknown call {call_id}"),
+        }
+    }
+}
@@ -1,38 +1,307 @@
 use bytes::Bytes;
-use std::{any::TypeId, collections::HashMap, sync::Arc};
+use std::{
+    any::TypeId,
+    collections::{HashMap, hash_map::Entry},
+    sync::Arc,
+};
 
 use futures::future::BoxFuture;
 
-use crate::protocol::{codec::ProtobufCodec, types::RpcError};
+use crate::{
+    context::Extensions,
+    protocol::{
+        codec::{MAX_ARGUMENT_SIZE, MAX_ARGUMENTS_COUNT, MAX_FUNCTION_NAME_SIZE, SchemaCodec},
+        types::{Envelope, FunctionDescriptor, ParamDescriptor, RpcError},
+    },
+};
 
 #[derive(Debug, Clone)]
 pub struct Param {
     pub name: &'static str,
     pub type_id: TypeId,
+    pub schema_id: u64,
 }
 
-type Handler =
-    dyn Fn(Vec<Bytes>, ProtobufCodec) -> BoxFuture<'static, Result<Bytes, RpcError>> + Send + Sync;
+/// Controls how a handler treats an argument that is missing or fails to decode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DecodePolicy {
+    /// Fails the whole call with [`RpcError::ArgumentDecodeFailed`].
+    #[default]
+    Fail,
+    /// Substitutes the argument type's `Default` value.
+    Default,
+}
+
+/// Controls how [`Container::validate`] treats calls carrying more
+/// arguments than the function declares.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ArityPolicy {
+    /// Rejects them with [`RpcError::ArityMismatch`].
+    #[default]
+    Strict,
+    /// Accepts them and leaves the extra trailing arguments undecoded, so a
+    /// newer client can add arguments an older server does not know yet.
+    /// Their size is still validated.
+    IgnoreExtra,
+}
+
+/// Decodes the arguments with the function's codec, runs it and encodes its
+/// result.
+type Handler = dyn Fn(Vec<Bytes>) -> BoxFuture<'static, Result<Bytes, RpcError>> + Send + Sync;
 
 #[derive(Clone)]
 pub struct RpcFunction {
+    /// Wire name: the function name, suffixed with `@<version>` for
+    /// versions above 1.
     pub name: &'static str,
+    pub version: u32,
     pub params: Vec<Param>,
     pub return_type: Option<TypeId>,
+    pub return_schema_id: Option<u64>,
+    /// Error type of a function returning `Result<T, E>`. Its values reach
+    /// the client as [`RpcError::Application`].
+    pub error_type: Option<TypeId>,
+    pub error_schema_id: Option<u64>,
+    pub decode_policy: DecodePolicy,
     pub handler: Arc<Handler>,
 }
 
-#[derive(Default)]
+/// DEFAULT_FUNCTION_NAME_LIMIT indicates default soft limit of RPC function name length accepted by
+/// [`Container::validate`]
+pub const DEFAULT_FUNCTION_NAME_LIMIT: usize = 256;
+
 pub struct Container {
     functions: HashMap<&'static str, &'static RpcFunction>,
+    extensions: Arc<Extensions>,
+    function_name_limit: usize,
+    arity_policy: ArityPolicy,
+}
+
+impl Default for Container {
+    fn default() -> Self {
+        Self {
+            functions: HashMap::new(),
+            extensions: Arc::default(),
+            function_name_limit: DEFAULT_FUNCTION_NAME_LIMIT,
+            arity_policy: ArityPolicy::default(),
+        }
+    }
 }
 
 impl Container {
+    /// Sets the longest function name, in bytes, [`Container::validate`]
+    /// accepts before looking it up. Defaults to
+    /// [`DEFAULT_FUNCTION_NAME_LIMIT`].
+    ///
+    /// Real function names are short, so an overlong one is almost certainly
+    /// malicious. The limit is capped at the codec's hard limit of
+    /// `u16::MAX` bytes.
+    pub fn set_function_name_limit(&mut self, limit: usize) {
+        self.function_name_limit = limit.min(MAX_FUNCTION_NAME_SIZE);
+    }
+
+    /// Sets how calls with extra trailing arguments are treated. Defaults to
+    /// [`ArityPolicy::Strict`].
+    pub fn set_arity_policy(&mut self, policy: ArityPolicy) {
+        self.arity_policy = policy;
+    }
+
+    /// Makes `extension` available to every handler through
+    /// [`RpcContext::extension`](crate::RpcContext::extension), keyed by its
+    /// type. Inserting a second value of the same type replaces the first.
+    ///
+    /// Any number of shared resources (a database pool, configuration, a
+    /// cache) can be registered this way, one per type.
+    pub fn insert_extension<T: Send + Sync + 'static>(&mut self, extension: Arc<T>) {
+        Arc::make_mut(&mut self.extensions).insert(TypeId::of::<T>(), extension);
+    }
+
+    pub(crate) fn extensions(&self) -> Arc<Extensions> {
+        Arc::clone(&self.extensions)
+    }
+
+    /// Registers `function` under its name.
+    ///
+    /// Functions are registered as `&*__CORGI_RPC_<name>`, and that deref is
+    /// what initializes the generated `LazyLock`. Registering every function
+    /// at startup therefore already warms them up: no call pays the
+    /// initialization cost.
+    ///
+    /// If the name is already taken by another function, the first one is
+    /// kept and a warning is logged; use [`Container::try_register`] to
+    /// handle the collision instead.
     pub fn register(&mut self, function: &'static RpcFunction) {
-        self.functions.entry(function.name).or_insert(function);
+        if self.try_register(function).is_err() {
+            tracing::warn!(
+                "Not registering function {}: the name is already taken by another function",
+                function.name
+            );
+        }
+    }
+
+    /// Registers `function` under its name, failing if the name is already
+    /// taken by another function or alias. Registering the same function
+    /// again is a no-op.
+    ///
+    /// # Errors
+    ///
+    /// - [`RpcError::DuplicateFunction`] if the name is taken
+    pub fn try_register(&mut self, function: &'static RpcFunction) -> Result<(), RpcError> {
+        match self.functions.entry(function.name) {
+            Entry::Occupied(entry) if std::ptr::eq(*entry.get(), function) => Ok(()),
+            Entry::Occupied(_) => Err(RpcError::DuplicateFunction),
+            Entry::Vacant(entry) => {
+                entry.insert(function);
+                Ok(())
+            }
+        }
+    }
+
+    /// Like [`Container::register`], consuming and returning the container
+    /// so registrations can be chained while building it.
+    pub fn with(mut self, function: &'static RpcFunction) -> Self {
+        self.register(function);
+        self
+    }
+
+    /// Registers `alias` as an additional name for an already registered
+    /// function, so a renamed function keeps answering to its old name.
+    ///
+    /// # Errors
+    ///
+    /// - [`RpcError::UnknownFunction`] if `existing` is not registered
+    /// - [`RpcError::DuplicateFunction`] if `alias` is already taken
+    pub fn register_alias(&mut self, existing: &str, alias: &'static str) -> Result<(), RpcError> {
+        let function = self.find(existing).ok_or(RpcError::UnknownFunction)?;
+
+        if self.functions.contains_key(alias) {
+            return Err(RpcError::DuplicateFunction);
+        }
+
+        self.functions.insert(alias, function);
+        Ok(())
     }
 
+    /// Returns every name a call can use, aliases included, in no
+    /// particular order.
+    pub fn names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.functions.keys().copied()
+    }
+
+    /// Returns every registered function once, however many names it is
+    /// registered under, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = &'static RpcFunction> + '_ {
+        self.functions
+            .iter()
+            .filter(|(name, function)| **name == function.name)
+            .map(|(_, function)| *function)
+    }
+
+    /// Number of registered functions, not counting aliases.
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.functions.is_empty()
+    }
+
+    /// Finds a function by wire name.
+    ///
+    /// Version 1 is registered under the plain name, so `name@1` resolves to
+    /// the same function as `name`.
     pub fn find(&self, name: &str) -> Option<&'static RpcFunction> {
-        self.functions.get(name).copied()
+        self.functions.get(name).copied().or_else(|| {
+            name.strip_suffix("@1")
+                .and_then(|name| self.functions.get(name).copied())
+        })
+    }
+
+    /// Checks that `envelope` is a call this container can execute, without
+    /// executing it.
+    ///
+    /// A function using [`DecodePolicy::Default`] accepts fewer arguments than
+    /// it declares, since missing ones fall back to their defaults. Under
+    /// [`ArityPolicy::IgnoreExtra`], every function accepts more.
+    ///
+    /// # Errors
+    ///
+    /// - size violations [`crate::protocol::codec::EnvelopeCodec`] enforces
+    /// - [`RpcError::FunctionNameTooLong`] if the name exceeds the soft limit
+    ///   set with [`Container::set_function_name_limit`]
+    /// - [`RpcError::UnknownFunction`] if no function matches the name
+    /// - [`RpcError::ArityMismatch`] if the argument count doesn't match
+    pub fn validate(&self, envelope: &Envelope) -> Result<&'static RpcFunction, RpcError> {
+        let args = envelope.parameters();
+
+        if envelope.fn_name().len() > MAX_FUNCTION_NAME_SIZE {
+            return Err(RpcError::MaxFunctionNameConstraintViolation);
+        }
+
+        if envelope.fn_name().len() > self.function_name_limit {
+            return Err(RpcError::FunctionNameTooLong);
+        }
+
+        if args.len() > MAX_ARGUMENTS_COUNT {
+            return Err(RpcError::MaxArgumentsConstraintViolation);
+        }
+
+        if args.iter().any(|arg| arg.len() > MAX_ARGUMENT_SIZE) {
+            return Err(RpcError::MaxArgumentSizeConstraintViolation);
+        }
+
+        let function = self
+            .find(envelope.fn_name())
+            .ok_or(RpcError::UnknownFunction)?;
+
+        let declared = function.params.len();
+        let arity_matches = match (function.decode_policy, self.arity_policy) {
+            (DecodePolicy::Fail, ArityPolicy::Strict) => args.len() == declared,
+            (DecodePolicy::Fail, ArityPolicy::IgnoreExtra) => args.len() >= declared,
+            (DecodePolicy::Default, ArityPolicy::Strict) => args.len() <= declared,
+            (DecodePolicy::Default, ArityPolicy::IgnoreExtra) => true,
+        };
+
+        if !arity_matches {
+            return Err(RpcError::ArityMismatch);
+        }
+
+        Ok(function)
+    }
+
+    /// Describes every registered name (aliases included), sorted by name.
+    ///
+    /// Names are stored in a map for lookup speed; sorting here keeps the
+    /// output identical across calls and registration orders.
+    pub fn descriptors(&self) -> Vec<FunctionDescriptor> {
+        let mut functions: Vec<_> = self.functions.iter().collect();
+        functions.sort_unstable_by_key(|(name, _)| **name);
+
+        functions
+            .into_iter()
+            .map(|(name, function)| FunctionDescriptor {
+                name: name.to_string(),
+                params: function
+                    .params
+                    .iter()
+                    .map(|param| ParamDescriptor {
+                        name: param.name.to_owned(),
+                        schema_id: param.schema_id,
+                    })
+                    .collect(),
+                return_schema_id: function.return_schema_id,
+                error_schema_id: function.error_schema_id,
+            })
+            .collect()
+    }
+
+    /// Exports [`Container::descriptors`] in a portable binary document, see
+    /// [`SchemaCodec`]. The same registrations always export the same bytes.
+    ///
+    /// This is the offline counterpart of runtime reflection, meant for
+    /// generating clients in other languages. Parse it back with
+    /// [`SchemaCodec::decode`].
+    pub fn export_schema(&self) -> Result<Bytes, RpcError> {
+        SchemaCodec.encode(&self.descriptors())
     }
 }
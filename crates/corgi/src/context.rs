@@ -0,0 +1,96 @@
+//! Per-call context available to handlers.
+
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    sync::Arc,
+};
+
+use bytes::Bytes;
+
+use crate::protocol::types::{TRACE_HEADER, TraceContext};
+
+/// Shared resources registered on a [`Container`](crate::Container), keyed by
+/// their type.
+pub(crate) type Extensions = HashMap<TypeId, Arc<dyn Any + Send + Sync>>;
+
+tokio::task_local! {
+    static CURRENT: RpcContext;
+}
+
+/// Context of the call a handler is currently serving.
+///
+/// The server installs it around every handler invocation, so handlers reach
+/// it through associated functions instead of an extra parameter. It is only
+/// visible on the handler's own task: work spawned onto other tasks has to
+/// take what it needs along.
+#[derive(Clone, Default)]
+pub struct RpcContext {
+    extensions: Arc<Extensions>,
+    headers: Vec<(Bytes, Bytes)>,
+}
+
+impl RpcContext {
+    pub(crate) fn new(extensions: Arc<Extensions>, headers: Vec<(Bytes, Bytes)>) -> Self {
+        Self {
+            extensions,
+            headers,
+        }
+    }
+
+    /// Returns the headers the caller attached to the call, in the order
+    /// they were sent, e.g. with
+    /// [`RpcClient::call_with_headers`](crate::RpcClient::call_with_headers).
+    ///
+    /// Returns no headers when called outside of a handler.
+    pub fn headers() -> Vec<(Bytes, Bytes)> {
+        CURRENT
+            .try_with(|context| context.headers.clone())
+            .unwrap_or_default()
+    }
+
+    /// Returns the value of the first header of the call matching `key`.
+    ///
+    /// Returns `None` if the caller sent no such header, or when called
+    /// outside of a handler.
+    pub fn header(key: &[u8]) -> Option<Bytes> {
+        CURRENT
+            .try_with(|context| {
+                context
+                    .headers
+                    .iter()
+                    .find(|(header_key, _)| header_key.as_ref() == key)
+                    .map(|(_, value)| value.clone())
+            })
+            .ok()
+            .flatten()
+    }
+
+    /// Returns the trace the caller made the call part of, e.g. with
+    /// [`RpcClient::call_with_trace`](crate::RpcClient::call_with_trace), for
+    /// passing on to the calls the handler makes in turn.
+    ///
+    /// Returns `None` if the caller sent no valid trace, or when called
+    /// outside of a handler.
+    pub fn trace() -> Option<TraceContext> {
+        TraceContext::decode(&Self::header(TRACE_HEADER)?)
+    }
+
+    /// Returns the extension of type `T` registered with
+    /// [`Container::insert_extension`](crate::Container::insert_extension).
+    ///
+    /// Returns `None` if no such extension was registered, or when called
+    /// outside of a handler.
+    pub fn extension<T: Send + Sync + 'static>() -> Option<Arc<T>> {
+        CURRENT
+            .try_with(|context| context.extensions.get(&TypeId::of::<T>()).cloned())
+            .ok()
+            .flatten()
+            .and_then(|extension| extension.downcast::<T>().ok())
+    }
+
+    /// Runs `future` with this context installed.
+    pub(crate) async fn scope<F: Future>(self, future: F) -> F::Output {
+        CURRENT.scope(self, future).await
+    }
+}
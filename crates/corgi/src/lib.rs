@@ -36,17 +36,35 @@
 //! #[tokio::main]
 //! async fn main() -> Result<(), Box<dyn std::error::Error>> {
 //!     let container = Container::default()
-//!         .register(&*__CORGI_RPC_hello_world);
+//!         .with(&*__CORGI_RPC_hello_world);
 //!
 //!     // Start UDP listener / event loop here
 //!
 //!     Ok(())
 //! }
 //! ```
+pub mod builtins;
+pub mod client;
 pub mod container;
+pub mod context;
+pub mod metrics;
+mod outbox;
+mod peer_table;
+pub mod pool;
 pub mod protocol;
+pub mod quarantine;
+pub mod rate_limit;
+mod response_cache;
+pub mod schema;
 pub mod server;
+pub mod service;
 
+pub use client::{ClientConfig, RpcClient};
 pub use container::Container;
-pub use corgi_macros::rpc_fn;
-pub use server::RpcServer;
+pub use context::RpcContext;
+pub use corgi_macros::{RpcResponse, rpc_fn};
+pub use metrics::ServerMetrics;
+pub use pool::ClientPool;
+pub use schema::schema_id;
+pub use server::{RpcServer, ServerConfig};
+pub use service::RpcService;
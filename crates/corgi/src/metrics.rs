@@ -0,0 +1,261 @@
+//! Server counters and their Prometheus text exposition.
+//!
+//! [`RpcServer::metrics`](crate::RpcServer::metrics) snapshots the counters
+//! as a [`ServerMetrics`];
+//! [`RpcServer::metrics_prometheus`](crate::RpcServer::metrics_prometheus)
+//! and [`RpcClient::metrics_prometheus`](crate::RpcClient::metrics_prometheus)
+//! render them for a scrape endpoint.
+//!
+//! Per-function series are labelled with `fn_name`. Only functions
+//! registered on the container get a series, so the label's cardinality is
+//! bounded by the container and names sent by peers never reach it.
+
+use std::{
+    collections::HashMap,
+    fmt::Write,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use crate::{Container, client::LatencyHistogram};
+
+/// Snapshot of the counters of an [`RpcServer`](crate::RpcServer).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ServerMetrics {
+    /// Datagrams received, including dropped ones.
+    pub datagrams_received: u64,
+    /// Messages waiting for their remaining chunks.
+    pub partial_messages: u64,
+    /// Incomplete messages dropped because their chunks stopped arriving.
+    pub reassemblies_timed_out: u64,
+    /// Times a peer got quarantined.
+    pub peers_quarantined: u64,
+    /// Datagrams dropped because their peer was quarantined.
+    pub quarantined_datagrams: u64,
+    /// Calls per registered function, sorted by name.
+    pub functions: Vec<FunctionMetrics>,
+}
+
+/// Calls of one registered function whose handler ran.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FunctionMetrics {
+    pub name: &'static str,
+    /// Calls that were executed, successfully or not.
+    pub calls: u64,
+    /// Calls that failed validation or whose handler returned an error.
+    pub failures: u64,
+}
+
+#[derive(Debug, Default)]
+struct FunctionCounters {
+    calls: AtomicU64,
+    failures: AtomicU64,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct ServerCounters {
+    pub(crate) datagrams_received: AtomicU64,
+    pub(crate) partial_messages: AtomicU64,
+    pub(crate) reassemblies_timed_out: AtomicU64,
+    pub(crate) peers_quarantined: AtomicU64,
+    pub(crate) quarantined_datagrams: AtomicU64,
+    /// Keyed by the name functions are registered under, fixed once the
+    /// server is created, so counting a call never locks.
+    functions: HashMap<&'static str, FunctionCounters>,
+}
+
+impl ServerCounters {
+    pub(crate) fn new(container: &Container) -> Self {
+        Self {
+            functions: container
+                .iter()
+                .map(|function| (function.name, FunctionCounters::default()))
+                .collect(),
+            ..Self::default()
+        }
+    }
+
+    /// Counts an executed call of the function registered as `name`.
+    pub(crate) fn record_call(&self, name: &'static str, failed: bool) {
+        let Some(counters) = self.functions.get(name) else {
+            return;
+        };
+
+        counters.calls.fetch_add(1, Ordering::Relaxed);
+        if failed {
+            counters.failures.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub(crate) fn snapshot(&self) -> ServerMetrics {
+        let mut functions: Vec<_> = self
+            .functions
+            .iter()
+            .map(|(name, counters)| FunctionMetrics {
+                name,
+                calls: counters.calls.load(Ordering::Relaxed),
+                failures: counters.failures.load(Ordering::Relaxed),
+            })
+            .collect();
+        functions.sort_unstable_by_key(|function| function.name);
+
+        ServerMetrics {
+            datagrams_received: self.datagrams_received.load(Ordering::Relaxed),
+            partial_messages: self.partial_messages.load(Ordering::Relaxed),
+            reassemblies_timed_out: self.reassemblies_timed_out.load(Ordering::Relaxed),
+            peers_quarantined: self.peers_quarantined.load(Ordering::Relaxed),
+            quarantined_datagrams: self.quarantined_datagrams.load(Ordering::Relaxed),
+            functions,
+        }
+    }
+
+    /// Zeroes the counters and returns their values from right before, so
+    /// nothing counted in between is lost. `partial_messages` is a gauge of
+    /// the current state and is kept.
+    pub(crate) fn reset(&self) -> ServerMetrics {
+        let mut functions: Vec<_> = self
+            .functions
+            .iter()
+            .map(|(name, counters)| FunctionMetrics {
+                name,
+                calls: counters.calls.swap(0, Ordering::Relaxed),
+                failures: counters.failures.swap(0, Ordering::Relaxed),
+            })
+            .collect();
+        functions.sort_unstable_by_key(|function| function.name);
+
+        ServerMetrics {
+            datagrams_received: self.datagrams_received.swap(0, Ordering::Relaxed),
+            partial_messages: self.partial_messages.load(Ordering::Relaxed),
+            reassemblies_timed_out: self.reassemblies_timed_out.swap(0, Ordering::Relaxed),
+            peers_quarantined: self.peers_quarantined.swap(0, Ordering::Relaxed),
+            quarantined_datagrams: self.quarantined_datagrams.swap(0, Ordering::Relaxed),
+            functions,
+        }
+    }
+}
+
+impl ServerMetrics {
+    /// Renders the metrics in Prometheus text exposition format.
+    pub fn to_prometheus(&self) -> String {
+        let mut out = String::with_capacity(1024 + self.functions.len() * 128);
+
+        for (name, kind, help, value) in [
+            (
+                "corgi_server_datagrams_received_total",
+                "counter",
+                "Datagrams received, including dropped ones.",
+                self.datagrams_received,
+            ),
+            (
+                "corgi_server_partial_messages",
+                "gauge",
+                "Messages waiting for their remaining chunks.",
+                self.partial_messages,
+            ),
+            (
+                "corgi_server_reassemblies_timed_out_total",
+                "counter",
+                "Incomplete messages dropped because their chunks stopped arriving.",
+                self.reassemblies_timed_out,
+            ),
+            (
+                "corgi_server_peers_quarantined_total",
+                "counter",
+                "Times a peer got quarantined.",
+                self.peers_quarantined,
+            ),
+            (
+                "corgi_server_quarantined_datagrams_total",
+                "counter",
+                "Datagrams dropped because their peer was quarantined.",
+                self.quarantined_datagrams,
+            ),
+        ] {
+            write_header(&mut out, name, kind, help);
+            let _ = writeln!(out, "{name} {value}");
+        }
+
+        self.write_function_counter(
+            &mut out,
+            "corgi_server_calls_total",
+            "Executed calls per function.",
+            |function| function.calls,
+        );
+        self.write_function_counter(
+            &mut out,
+            "corgi_server_call_failures_total",
+            "Failed calls per function.",
+            |function| function.failures,
+        );
+
+        out
+    }
+
+    fn write_function_counter(
+        &self,
+        out: &mut String,
+        name: &str,
+        help: &str,
+        value: impl Fn(&FunctionMetrics) -> u64,
+    ) {
+        write_header(out, name, "counter", help);
+        for function in &self.functions {
+            let _ = write!(out, "{name}{{fn_name=\"");
+            write_label_value(out, function.name);
+            let _ = writeln!(out, "\"}} {}", value(function));
+        }
+    }
+}
+
+impl LatencyHistogram {
+    /// Renders the histogram in Prometheus text exposition format, as
+    /// `corgi_client_call_duration_seconds`.
+    pub fn to_prometheus(&self) -> String {
+        const NAME: &str = "corgi_client_call_duration_seconds";
+        let mut out = String::with_capacity(1024);
+
+        write_header(
+            &mut out,
+            NAME,
+            "histogram",
+            "End-to-end latency of completed calls.",
+        );
+        let mut cumulative = 0;
+        for (bound, count) in self.buckets() {
+            cumulative += count;
+            match bound {
+                Some(bound) => {
+                    let _ = writeln!(
+                        out,
+                        "{NAME}_bucket{{le=\"{}\"}} {cumulative}",
+                        bound.as_secs_f64()
+                    );
+                }
+                None => {
+                    let _ = writeln!(out, "{NAME}_bucket{{le=\"+Inf\"}} {cumulative}");
+                }
+            }
+        }
+        let _ = writeln!(out, "{NAME}_sum {}", self.sum().as_secs_f64());
+        let _ = writeln!(out, "{NAME}_count {}", self.count());
+
+        out
+    }
+}
+
+fn write_header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+/// Escapes a label value as the exposition format requires.
+fn write_label_value(out: &mut String, value: &str) {
+    for character in value.chars() {
+        match character {
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            character => out.push(character),
+        }
+    }
+}
@@ -0,0 +1,318 @@
+//! Coalescing of the datagrams a server sends.
+//!
+//! Sending one datagram per system call makes multi-chunk responses
+//! syscall-heavy. The [`Outbox`] hands all chunks of a response to the
+//! socket in one batch, which on Linux is a single `sendmmsg`. With a
+//! [`ServerConfig::coalesce_window`](crate::ServerConfig::coalesce_window)
+//! set, multi-chunk responses becoming ready within the window share a
+//! batch too. Single-chunk responses, the latency-sensitive common case,
+//! never wait for the window.
+//!
+//! The outbox also counts the responses still owed, for
+//! [`RpcServer::flush`](crate::RpcServer::flush) to wait on.
+
+use std::{io, net::SocketAddr, pin::pin, sync::Mutex, time::Duration};
+
+use bytes::Bytes;
+use tokio::{net::UdpSocket, sync::Notify};
+
+/// MAX_BATCH indicates number of datagrams handed to the socket at once at most; a full batch is
+/// sent without waiting for the rest of the window
+const MAX_BATCH: usize = 64;
+
+/// Where an [`Outbox`] sends its batches.
+pub(crate) trait DatagramSink: Sync {
+    /// Sends every datagram of `batch` to its address, in order.
+    fn send_batch(
+        &self,
+        batch: &[(SocketAddr, Bytes)],
+    ) -> impl Future<Output = io::Result<()>> + Send;
+}
+
+impl DatagramSink for UdpSocket {
+    async fn send_batch(&self, batch: &[(SocketAddr, Bytes)]) -> io::Result<()> {
+        let mut rest = batch;
+        while !rest.is_empty() {
+            #[cfg(target_os = "linux")]
+            let sent = self
+                .async_io(tokio::io::Interest::WRITABLE, || sendmmsg(self, rest))
+                .await?;
+            #[cfg(not(target_os = "linux"))]
+            let sent = {
+                let (peer, datagram) = &rest[0];
+                self.send_to(datagram, *peer).await?;
+                1
+            };
+            rest = &rest[sent..];
+        }
+        Ok(())
+    }
+}
+
+/// Sends as much of `batch` as the socket takes in one `sendmmsg` call and
+/// returns how many datagrams that was.
+#[cfg(target_os = "linux")]
+fn sendmmsg(socket: &UdpSocket, batch: &[(SocketAddr, Bytes)]) -> io::Result<usize> {
+    use std::os::fd::AsRawFd;
+
+    use socket2::SockAddr;
+
+    let batch = &batch[..batch.len().min(MAX_BATCH)];
+    let addresses: Vec<SockAddr> = batch.iter().map(|(peer, _)| (*peer).into()).collect();
+    let mut iovecs: Vec<libc::iovec> = batch
+        .iter()
+        .map(|(_, datagram)| libc::iovec {
+            iov_base: datagram.as_ptr() as *mut libc::c_void,
+            iov_len: datagram.len(),
+        })
+        .collect();
+    let mut messages: Vec<libc::mmsghdr> = iovecs
+        .iter_mut()
+        .zip(&addresses)
+        .map(|(iovec, address)| {
+            // SAFETY: `msghdr` is plain old data, for which all zeroes is a
+            // valid, empty value.
+            let mut header: libc::msghdr = unsafe { std::mem::zeroed() };
+            header.msg_name = address.as_ptr() as *mut libc::c_void;
+            header.msg_namelen = address.len();
+            header.msg_iov = iovec;
+            header.msg_iovlen = 1;
+            libc::mmsghdr {
+                msg_hdr: header,
+                msg_len: 0,
+            }
+        })
+        .collect();
+
+    // SAFETY: every header points into `addresses`, `iovecs` and the
+    // datagrams of `batch`, which all outlive the call, and `messages.len()`
+    // is at most `MAX_BATCH`.
+    let sent = unsafe {
+        libc::sendmmsg(
+            socket.as_raw_fd(),
+            messages.as_mut_ptr(),
+            messages.len() as libc::c_uint,
+            0,
+        )
+    };
+    if sent < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(sent as usize)
+}
+
+/// Batches the datagrams of the responses a server sends.
+#[derive(Debug)]
+pub(crate) struct Outbox {
+    window: Duration,
+    /// Datagrams waiting for the current window to pass.
+    pending: Mutex<Vec<(SocketAddr, Bytes)>>,
+    /// Responses owed plus sends in progress, see [`Outbox::busy`].
+    busy: Mutex<usize>,
+    /// Woken when `busy` drops to zero.
+    idle: Notify,
+}
+
+impl Outbox {
+    pub(crate) fn new(window: Duration) -> Self {
+        Self {
+            window,
+            pending: Mutex::default(),
+            busy: Mutex::new(0),
+            idle: Notify::new(),
+        }
+    }
+
+    /// Marks a response as owed until the returned guard is dropped, for
+    /// [`Outbox::idle`] to wait on.
+    pub(crate) fn busy(&self) -> Busy<'_> {
+        *self.busy.lock().unwrap() += 1;
+        Busy(self)
+    }
+
+    /// Waits until no response is owed and every queued datagram was handed
+    /// to the sink.
+    pub(crate) async fn idle(&self) {
+        loop {
+            // Registered before checking, so dropping the last guard in
+            // between still wakes this.
+            let mut idle = pin!(self.idle.notified());
+            idle.as_mut().enable();
+            if *self.busy.lock().unwrap() == 0 {
+                return;
+            }
+            idle.await;
+        }
+    }
+
+    /// Sends the chunks of one response to `peer` through `sink`.
+    ///
+    /// The first multi-chunk response of a window waits it out and sends
+    /// every datagram queued in the meantime along with its own; later ones
+    /// only queue theirs, unless that fills a batch.
+    pub(crate) async fn send(
+        &self,
+        sink: &impl DatagramSink,
+        peer: SocketAddr,
+        datagrams: Vec<Bytes>,
+    ) -> io::Result<()> {
+        // Queued datagrams are sent by the response that opened the window,
+        // which stays busy until then.
+        let _busy = self.busy();
+        let own = datagrams.into_iter().map(|datagram| (peer, datagram));
+        if own.len() == 1 || self.window.is_zero() {
+            return sink.send_batch(&own.collect::<Vec<_>>()).await;
+        }
+
+        let batch = {
+            let mut pending = self.pending.lock().unwrap();
+            let opens_window = pending.is_empty();
+            pending.extend(own);
+            if pending.len() >= MAX_BATCH {
+                std::mem::take(&mut *pending)
+            } else if opens_window {
+                Vec::new()
+            } else {
+                return Ok(());
+            }
+        };
+        if !batch.is_empty() {
+            return sink.send_batch(&batch).await;
+        }
+
+        tokio::time::sleep(self.window).await;
+        let batch = std::mem::take(&mut *self.pending.lock().unwrap());
+        sink.send_batch(&batch).await
+    }
+}
+
+/// A response owed or a send in progress, see [`Outbox::busy`].
+pub(crate) struct Busy<'a>(&'a Outbox);
+
+impl Drop for Busy<'_> {
+    fn drop(&mut self) {
+        let mut busy = self.0.busy.lock().unwrap();
+        *busy -= 1;
+        if *busy == 0 {
+            self.0.idle.notify_waiters();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use super::*;
+
+    /// Records every batch instead of sending it.
+    #[derive(Debug, Default)]
+    struct MockSink {
+        batches: Mutex<Vec<Vec<(SocketAddr, Bytes)>>>,
+    }
+
+    impl DatagramSink for MockSink {
+        async fn send_batch(&self, batch: &[(SocketAddr, Bytes)]) -> io::Result<()> {
+            self.batches.lock().unwrap().push(batch.to_vec());
+            Ok(())
+        }
+    }
+
+    fn peer() -> SocketAddr {
+        "127.0.0.1:4000".parse().unwrap()
+    }
+
+    fn chunks(count: u8) -> Vec<Bytes> {
+        (0..count).map(|index| Bytes::from(vec![index])).collect()
+    }
+
+    #[tokio::test]
+    async fn outbox_should_send_multi_chunk_response_in_one_batch() {
+        let sink = MockSink::default();
+        let outbox = Outbox::new(Duration::ZERO);
+
+        outbox.send(&sink, peer(), chunks(5)).await.unwrap();
+
+        let batches = sink.batches.lock().unwrap();
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].len(), 5);
+    }
+
+    #[tokio::test]
+    async fn outbox_should_coalesce_responses_ready_within_window() {
+        let sink = MockSink::default();
+        let outbox = Outbox::new(Duration::from_millis(20));
+
+        let (first, second) = tokio::join!(outbox.send(&sink, peer(), chunks(3)), async {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+            outbox.send(&sink, peer(), chunks(2)).await
+        });
+
+        first.unwrap();
+        second.unwrap();
+        let batches = sink.batches.lock().unwrap();
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].len(), 5);
+    }
+
+    #[tokio::test]
+    async fn outbox_should_be_idle_once_queued_datagrams_were_sent() {
+        let sink = MockSink::default();
+        let outbox = Outbox::new(Duration::from_millis(20));
+
+        // The send is polled first, so it is busy before `idle` checks.
+        let (sent, batches) = tokio::join!(outbox.send(&sink, peer(), chunks(3)), async {
+            outbox.idle().await;
+            sink.batches.lock().unwrap().len()
+        });
+
+        sent.unwrap();
+        assert_eq!(batches, 1);
+    }
+
+    #[tokio::test]
+    async fn outbox_should_send_full_batch_without_waiting_for_window() {
+        let sink = MockSink::default();
+        let outbox = Outbox::new(Duration::from_secs(60));
+
+        outbox
+            .send(&sink, peer(), chunks(MAX_BATCH as u8))
+            .await
+            .unwrap();
+
+        assert_eq!(sink.batches.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn outbox_should_send_single_chunk_response_without_waiting_for_window() {
+        let sink = MockSink::default();
+        let outbox = Outbox::new(Duration::from_secs(60));
+        let started_at = Instant::now();
+
+        outbox.send(&sink, peer(), chunks(1)).await.unwrap();
+
+        assert!(started_at.elapsed() < Duration::from_secs(1));
+        assert_eq!(sink.batches.lock().unwrap().len(), 1);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn udp_socket_should_deliver_every_datagram_of_batch() {
+        let receiver = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let sender = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let address = receiver.local_addr().unwrap();
+        let batch: Vec<_> = chunks(3)
+            .into_iter()
+            .map(|datagram| (address, datagram))
+            .collect();
+
+        sender.send_batch(&batch).await.unwrap();
+
+        let mut buf = [0_u8; 16];
+        for index in 0..3 {
+            let (len, from) = receiver.recv_from(&mut buf).await.unwrap();
+            assert_eq!(&buf[..len], [index]);
+            assert_eq!(from, sender.local_addr().unwrap());
+        }
+    }
+}
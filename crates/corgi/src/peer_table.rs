@@ -0,0 +1,135 @@
+//! Bounded per-peer state.
+//!
+//! The rate limiter and the quarantine both keep a record per source
+//! address. Source addresses are trivially spoofed, so the table holding
+//! them is capped: once full, a new peer gets a record only after an
+//! evictable one made room.
+
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
+
+/// MAX_TRACKED_PEERS indicates number of peer records kept at most; new peers beyond it are
+/// turned away until evictable records get dropped
+pub(crate) const MAX_TRACKED_PEERS: usize = 4096;
+
+/// EVICTION_BACKOFF indicates how long a full table waits before it is swept for evictable
+/// records again, so a flood of new peers costs one sweep per period instead of one per datagram
+const EVICTION_BACKOFF: Duration = Duration::from_millis(100);
+
+#[derive(Debug)]
+pub(crate) struct PeerTable<V> {
+    peers: HashMap<SocketAddr, V>,
+    /// When the full table was last swept from the receive path.
+    evicted_at: Option<Instant>,
+}
+
+impl<V> Default for PeerTable<V> {
+    fn default() -> Self {
+        Self {
+            peers: HashMap::new(),
+            evicted_at: None,
+        }
+    }
+}
+
+impl<V> PeerTable<V> {
+    pub(crate) fn get(&self, peer: &SocketAddr) -> Option<&V> {
+        self.peers.get(peer)
+    }
+
+    /// Returns the record of `peer`, creating it with `init` when it is new.
+    ///
+    /// Returns `None` for a new peer while [`MAX_TRACKED_PEERS`] records are
+    /// kept and none of them is evictable. A full table is swept with
+    /// `is_evictable` at most once per [`EVICTION_BACKOFF`].
+    pub(crate) fn get_or_insert_with(
+        &mut self,
+        peer: SocketAddr,
+        now: Instant,
+        is_evictable: impl FnMut(&V) -> bool,
+        init: impl FnOnce() -> V,
+    ) -> Option<&mut V> {
+        if self.peers.len() >= MAX_TRACKED_PEERS && !self.peers.contains_key(&peer) {
+            if self
+                .evicted_at
+                .is_none_or(|at| now.saturating_duration_since(at) >= EVICTION_BACKOFF)
+            {
+                self.evicted_at = Some(now);
+                self.evict(is_evictable);
+            }
+            if self.peers.len() >= MAX_TRACKED_PEERS {
+                return None;
+            }
+        }
+
+        Some(self.peers.entry(peer).or_insert_with(init))
+    }
+
+    /// Periodic cleanup: drops evictable records, then gives memory left
+    /// over from bursts back.
+    pub(crate) fn maintenance_tick(&mut self, is_evictable: impl FnMut(&V) -> bool) {
+        self.evict(is_evictable);
+        self.peers.shrink_to(self.peers.len() * 2);
+    }
+
+    #[cfg(test)]
+    pub(crate) fn len(&self) -> usize {
+        self.peers.len()
+    }
+
+    #[cfg(test)]
+    pub(crate) fn is_empty(&self) -> bool {
+        self.peers.is_empty()
+    }
+
+    fn evict(&mut self, mut is_evictable: impl FnMut(&V) -> bool) {
+        self.peers.retain(|_, record| !is_evictable(record));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn peer_table_should_sweep_full_table_at_most_once_per_backoff() {
+        let mut table = PeerTable::default();
+        let now = Instant::now();
+        for port in 0..MAX_TRACKED_PEERS as u16 {
+            table.get_or_insert_with(
+                SocketAddr::from(([127, 0, 0, 1], port)),
+                now,
+                |_| false,
+                || (),
+            );
+        }
+        let mut sweeps = 0;
+
+        let newcomers = (0..10_u16)
+            .filter_map(|port| {
+                let newcomer = SocketAddr::from(([127, 0, 0, 2], port));
+                let at = now + Duration::from_millis(u64::from(port) * 20);
+                table
+                    .get_or_insert_with(
+                        newcomer,
+                        at,
+                        |_| {
+                            sweeps += 1;
+                            false
+                        },
+                        || (),
+                    )
+                    .map(|_| ())
+            })
+            .count();
+
+        assert_eq!(newcomers, 0);
+        // Sweeps at 0ms and 100ms visit every record; the ones in between
+        // are skipped.
+        assert_eq!(sweeps, 2 * MAX_TRACKED_PEERS);
+        assert_eq!(table.len(), MAX_TRACKED_PEERS);
+    }
+}
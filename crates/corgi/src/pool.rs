@@ -0,0 +1,147 @@
+//! Clients for many servers, created on first use and reused afterwards.
+//!
+//! A gateway talking to many backends would otherwise bind a socket per
+//! call. [`ClientPool`] keeps one [`RpcClient`] per server address and
+//! tracks whether the server answers: a client whose calls keep timing out
+//! is dropped, and its server reported unhealthy until a call to it gets an
+//! answer again.
+
+use std::{
+    collections::{HashMap, HashSet},
+    net::SocketAddr,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU32, Ordering},
+    },
+};
+
+use bytes::Bytes;
+
+use crate::{ClientConfig, RpcClient, protocol::types::RpcError};
+
+/// Tunables of a [`ClientPool`].
+#[derive(Debug, Clone)]
+pub struct PoolConfig {
+    /// Configuration of every client the pool creates.
+    pub client: ClientConfig,
+    /// Calls in a row failing with [`RpcError::Timeout`] or
+    /// [`RpcError::PeerUnreachable`] after which a server counts as
+    /// unhealthy and its client is dropped. Defaults to 3.
+    pub max_consecutive_timeouts: u32,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        Self {
+            client: ClientConfig::default(),
+            max_consecutive_timeouts: 3,
+        }
+    }
+}
+
+struct PooledClient {
+    client: RpcClient,
+    consecutive_timeouts: AtomicU32,
+}
+
+#[derive(Default)]
+struct Clients {
+    pooled: HashMap<SocketAddr, Arc<PooledClient>>,
+    unhealthy: HashSet<SocketAddr>,
+}
+
+/// [`RpcClient`]s keyed by the address of their server.
+#[derive(Default)]
+pub struct ClientPool {
+    config: PoolConfig,
+    clients: Mutex<Clients>,
+}
+
+impl ClientPool {
+    pub fn new(config: PoolConfig) -> Self {
+        Self {
+            config,
+            clients: Mutex::default(),
+        }
+    }
+
+    /// Calls `fn_name` on the server at `address` with already encoded
+    /// `args`, like [`RpcClient::call`], connecting a client to it first if
+    /// the pool has none.
+    ///
+    /// Once [`PoolConfig::max_consecutive_timeouts`] calls in a row timed
+    /// out, the client is dropped and the next call connects a new one. Any
+    /// answer from the server, a failure included, makes it healthy again.
+    pub async fn call(
+        &self,
+        address: SocketAddr,
+        fn_name: &str,
+        args: Vec<Bytes>,
+    ) -> Result<Bytes, RpcError> {
+        let pooled = self.client(address).await?;
+        let result = pooled.client.call(fn_name, args).await;
+
+        match &result {
+            Err(RpcError::Timeout | RpcError::PeerUnreachable) => {
+                let timeouts = pooled.consecutive_timeouts.fetch_add(1, Ordering::Relaxed) + 1;
+                if timeouts >= self.config.max_consecutive_timeouts {
+                    self.evict(address, &pooled);
+                }
+            }
+            Ok(_) | Err(RpcError::Remote { .. } | RpcError::Application { .. }) => {
+                pooled.consecutive_timeouts.store(0, Ordering::Relaxed);
+                self.clients.lock().unwrap().unhealthy.remove(&address);
+            }
+            Err(_) => {}
+        }
+
+        result
+    }
+
+    /// Whether the server at `address` answered since its calls last kept
+    /// timing out. Servers never called count as healthy.
+    pub fn is_healthy(&self, address: SocketAddr) -> bool {
+        !self.clients.lock().unwrap().unhealthy.contains(&address)
+    }
+
+    /// Number of clients in the pool.
+    pub fn len(&self) -> usize {
+        self.clients.lock().unwrap().pooled.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    async fn client(&self, address: SocketAddr) -> Result<Arc<PooledClient>, RpcError> {
+        if let Some(pooled) = self.clients.lock().unwrap().pooled.get(&address) {
+            return Ok(Arc::clone(pooled));
+        }
+
+        // Connecting happens without the lock held, so concurrent first
+        // calls may both connect; only the client inserted first is kept.
+        let client = RpcClient::connect_udp(address)
+            .await?
+            .with_config(self.config.client.clone());
+        let pooled = Arc::new(PooledClient {
+            client,
+            consecutive_timeouts: AtomicU32::new(0),
+        });
+        let mut clients = self.clients.lock().unwrap();
+        Ok(Arc::clone(clients.pooled.entry(address).or_insert(pooled)))
+    }
+
+    fn evict(&self, address: SocketAddr, pooled: &Arc<PooledClient>) {
+        let mut clients = self.clients.lock().unwrap();
+        // A concurrent call may have replaced the client already.
+        if clients
+            .pooled
+            .get(&address)
+            .is_some_and(|current| Arc::ptr_eq(current, pooled))
+        {
+            tracing::warn!("Dropping client of unresponsive server {address}");
+            clients.pooled.remove(&address);
+        }
+        clients.unhealthy.insert(address);
+    }
+}
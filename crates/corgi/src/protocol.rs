@@ -1,4 +1,23 @@
 pub mod codec;
+pub mod codes;
 pub mod parser;
 pub mod types;
 
+use bytes::Bytes;
+
+use crate::protocol::{
+    codec::PackageChunkCodec,
+    types::{CallId, ChunkHeader, PackageChunk},
+};
+
+/// Encodes one request chunk of call `call_id`, as a client would send it.
+///
+/// Meant for tests and tools feeding hand-picked chunks to a server: the
+/// header is laid out by [`PackageChunkCodec`] in the default little-endian
+/// order, with a valid checksum and no correlation id.
+pub fn make_datagram(call_id: CallId, index: u16, total: u16, payload: &[u8]) -> Bytes {
+    let header = ChunkHeader::new(call_id, index, total, payload.len() as u32);
+    PackageChunkCodec::default()
+        .encode(PackageChunk::new(header, Bytes::copy_from_slice(payload)))
+        .expect("encoding a chunk header never fails")
+}
@@ -8,23 +8,183 @@
 //! All parsing logic in this module is designed to be
 //! deterministic, panic-free, and safe for untrusted UDP input.
 
+use std::sync::atomic::{AtomicBool, Ordering};
+
 use bytes::{BufMut, Bytes, BytesMut};
 use prost::Message;
 
-use crate::protocol::types::{ChunkHeader, Envelope, PackageChunk, RpcError};
+use crate::protocol::types::{
+    CallId, ChunkHeader, Envelope, FunctionDescriptor, MessageKind, PackageChunk, ParamDescriptor,
+    ResponseFields, RpcError,
+};
 
 /// CHUNK_HEADER_SIZE indicates protocol chunk header size, where call_id, chunk index, total
-/// chunks and paylaod len is stored.
-const CHUNK_HEADER_SIZE: usize = 16;
+/// chunks, paylaod len, message kind, payload checksum and correlation id is stored, prefixed
+/// by magic and protocol version.
+pub(crate) const CHUNK_HEADER_SIZE: usize = 32;
+
+/// LEGACY_CHUNK_HEADER_SIZE indicates header size of version 0 chunks, which carry only call_id,
+/// chunk index, total chunks and payload len
+const LEGACY_CHUNK_HEADER_SIZE: usize = 16;
+
+/// CHUNK_MAGIC indicates marker every chunk starts with, so stray datagrams are told apart from
+/// corgi traffic
+pub const CHUNK_MAGIC: [u8; 2] = *b"CG";
+
+/// PROTOCOL_VERSION indicates wire format version written into every chunk. Chunks carrying
+/// another version are rejected
+pub const PROTOCOL_VERSION: u8 = 2;
+
+/// BIG_ENDIAN_FLAG indicates bit set in the version byte of chunks whose header fields are
+/// big-endian
+pub const BIG_ENDIAN_FLAG: u8 = 0x80;
+
+/// NO_CHECKSUM_FLAG indicates bit set in the version byte of chunks sent without a payload
+/// checksum, whose crc32 field is zero and not validated
+pub const NO_CHECKSUM_FLAG: u8 = 0x40;
+
+/// VERSION_FLAGS indicates every flag bit of the version byte
+const VERSION_FLAGS: u8 = BIG_ENDIAN_FLAG | NO_CHECKSUM_FLAG;
+
+/// Byte order of the integer fields of a chunk header.
+///
+/// Little-endian is the canonical order. Big-endian exists for interop
+/// with legacy systems; both ends of a deployment must agree, and a chunk
+/// in the other order is rejected with [`RpcError::EndiannessMismatch`]
+/// rather than misread.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Endianness {
+    #[default]
+    Little,
+    Big,
+}
+
+impl Endianness {
+    fn u16_bytes(self, value: u16) -> [u8; 2] {
+        match self {
+            Endianness::Little => value.to_le_bytes(),
+            Endianness::Big => value.to_be_bytes(),
+        }
+    }
+
+    fn u32_bytes(self, value: u32) -> [u8; 4] {
+        match self {
+            Endianness::Little => value.to_le_bytes(),
+            Endianness::Big => value.to_be_bytes(),
+        }
+    }
+
+    fn u64_bytes(self, value: u64) -> [u8; 8] {
+        match self {
+            Endianness::Little => value.to_le_bytes(),
+            Endianness::Big => value.to_be_bytes(),
+        }
+    }
+
+    fn read_u16(self, bytes: &[u8]) -> Result<u16, RpcError> {
+        let bytes = bytes.try_into().map_err(|_| RpcError::Decode)?;
+        Ok(match self {
+            Endianness::Little => u16::from_le_bytes(bytes),
+            Endianness::Big => u16::from_be_bytes(bytes),
+        })
+    }
+
+    fn read_u32(self, bytes: &[u8]) -> Result<u32, RpcError> {
+        let bytes = bytes.try_into().map_err(|_| RpcError::Decode)?;
+        Ok(match self {
+            Endianness::Little => u32::from_le_bytes(bytes),
+            Endianness::Big => u32::from_be_bytes(bytes),
+        })
+    }
+
+    fn read_u64(self, bytes: &[u8]) -> Result<u64, RpcError> {
+        let bytes = bytes.try_into().map_err(|_| RpcError::Decode)?;
+        Ok(match self {
+            Endianness::Little => u64::from_le_bytes(bytes),
+            Endianness::Big => u64::from_be_bytes(bytes),
+        })
+    }
+}
+
+/// UDP_CHUNK_SIZE indicates the datagram size chunks are cut to on the wire, chosen to stay
+/// below common path MTUs
+pub(crate) const UDP_CHUNK_SIZE: usize = 1200;
 
 /// MAX_ARGUMENTS_COUNT indicates RPC function maxiumum arguments count
-const MAX_ARGUMENTS_COUNT: usize = 16;
+pub(crate) const MAX_ARGUMENTS_COUNT: usize = 16;
 
 /// MAX_ARGUMENTS_SIZE indicates RPC function maximum arguments size which is equals to 16MB
-const MAX_ARGUMENT_SIZE: usize = 16 * 1024 * 1024;
+pub(crate) const MAX_ARGUMENT_SIZE: usize = 16 * 1024 * 1024;
 
 /// MAX_FUNCTION_NAME_SIZE indicates RPC function name length which must not exceed 65536
-const MAX_FUNCTION_NAME_SIZE: usize = u16::MAX as usize;
+pub(crate) const MAX_FUNCTION_NAME_SIZE: usize = u16::MAX as usize;
+
+/// MAX_HEADERS_COUNT indicates RPC call maximum metadata headers count
+const MAX_HEADERS_COUNT: usize = 64;
+
+/// MAX_HEADERS_SIZE indicates RPC call maximum total size of metadata header keys and values
+/// which is equals to 16KB
+const MAX_HEADERS_SIZE: usize = 16 * 1024;
+
+/// MAX_RESPONSE_FIELDS_COUNT indicates RPC response maximum named fields count
+const MAX_RESPONSE_FIELDS_COUNT: usize = 256;
+
+/// MAX_DATAGRAM_SIZE indicates the largest payload a single UDP datagram can carry over IPv4
+const MAX_DATAGRAM_SIZE: usize = 65_507;
+
+/// MAX_CHUNK_PAYLOAD_SIZE indicates chunk payload length which must fit into a single datagram
+/// together with the chunk header
+pub const MAX_CHUNK_PAYLOAD_SIZE: usize = MAX_DATAGRAM_SIZE - CHUNK_HEADER_SIZE;
+
+/// MAX_RESPONSE_SIZE indicates RPC function maximum encoded return value size which is equals
+/// to 16MB, the same bound as a single argument
+pub const MAX_RESPONSE_SIZE: usize = 16 * 1024 * 1024;
+
+/// MAX_MESSAGE_SIZE indicates the largest message that can be chunked, bounded by the `u16`
+/// chunk count of the header. Requests close to the argument limits can exceed it
+pub const MAX_MESSAGE_SIZE: usize = u16::MAX as usize * (UDP_CHUNK_SIZE - CHUNK_HEADER_SIZE);
+
+/// Encodes and decodes the arguments, return values and error values of a
+/// function, as chosen with `#[rpc_fn(codec = ...)]`.
+///
+/// Implemented by [`ProtobufCodec`], the default, for every protobuf
+/// message and by [`JsonCodec`] for every serde type. Any other `Default`
+/// type implementing it can be plugged in the same way. The codec is fixed
+/// per function, so the client has to encode the arguments of a function
+/// with the codec it was declared with.
+///
+/// There is no server-wide codec. Codecs are implemented per value type,
+/// [`ProtobufCodec`] only for protobuf messages and [`JsonCodec`] only for
+/// serde types, so a function's signature decides which codecs it can use
+/// when `rpc_fn` expands; a codec picked when creating the server could
+/// not decode arguments of types it is not implemented for.
+pub trait PayloadCodec<T>: Default + Send + Sync + 'static {
+    fn encode(&self, value: &T) -> Result<Bytes, RpcError>;
+
+    fn decode(&self, bytes: &[u8]) -> Result<T, RpcError>;
+}
+
+/// Encodes a return value with `codec`, failing with
+/// [`RpcError::ResponseTooLarge`] when it exceeds [`MAX_RESPONSE_SIZE`].
+///
+/// Functions declared with a codec other than [`ProtobufCodec`] encode
+/// their return values through it; protobuf ones go through
+/// [`EncodeResponse`] instead.
+pub fn encode_payload<C: PayloadCodec<T>, T>(codec: &C, value: &T) -> Result<Bytes, RpcError> {
+    check_response_size(codec.encode(value)?)
+}
+
+/// Passes an already encoded return value through, failing with
+/// [`RpcError::ResponseTooLarge`] when it exceeds [`MAX_RESPONSE_SIZE`].
+///
+/// Functions returning raw `Bytes` or `Cow<'static, [u8]>` skip the codec,
+/// but not this limit.
+pub fn check_response_size(bytes: Bytes) -> Result<Bytes, RpcError> {
+    if bytes.len() > MAX_RESPONSE_SIZE {
+        return Err(RpcError::ResponseTooLarge);
+    }
+    Ok(bytes)
+}
 
 #[derive(Default, Clone)]
 pub struct ProtobufCodec;
@@ -41,20 +201,102 @@ impl ProtobufCodec {
     }
 }
 
+impl<T: Message + Default> PayloadCodec<T> for ProtobufCodec {
+    fn encode(&self, value: &T) -> Result<Bytes, RpcError> {
+        ProtobufCodec::encode(self, value)
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<T, RpcError> {
+        ProtobufCodec::decode(self, bytes)
+    }
+}
+
+/// Human-readable payload codec for debugging and interop with non-Rust
+/// peers, with the same shape as [`ProtobufCodec`] but bounded on serde.
+///
+/// Functions opt into it with
+/// `#[rpc_fn(codec = corgi::protocol::codec::JsonCodec)]`; their argument,
+/// return and error types then have to be serde types instead of protobuf
+/// messages.
+///
+/// JSON payloads can be printed and written by hand, but they are several
+/// times larger than protobuf and slower to encode and decode, since every
+/// field name travels with every value and numbers are formatted as text.
+/// [`ProtobufCodec`] remains the default; this one is meant for development,
+/// tooling and peers without protobuf support.
+#[derive(Default, Clone)]
+pub struct JsonCodec;
+
+impl JsonCodec {
+    pub fn encode<T: serde::Serialize>(&self, value: &T) -> Result<Bytes, RpcError> {
+        serde_json::to_vec(value)
+            .map(Bytes::from)
+            .map_err(|_| RpcError::Encode)
+    }
+
+    pub fn decode<T: serde::de::DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, RpcError> {
+        serde_json::from_slice(bytes).map_err(|_| RpcError::Decode)
+    }
+}
+
+impl<T: serde::Serialize + serde::de::DeserializeOwned> PayloadCodec<T> for JsonCodec {
+    fn encode(&self, value: &T) -> Result<Bytes, RpcError> {
+        JsonCodec::encode(self, value)
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<T, RpcError> {
+        JsonCodec::decode(self, bytes)
+    }
+}
+
+/// Encodes a handler's return value into response bytes.
+///
+/// Every protobuf [`Message`] is encoded as-is through [`ProtobufCodec`].
+/// Types deriving `RpcResponse` are encoded as [`ResponseFields`] instead, so
+/// clients can read individual fields by name.
+pub trait EncodeResponse {
+    fn encode_response(&self, codec: &ProtobufCodec) -> Result<Bytes, RpcError>;
+}
+
+impl<T: Message> EncodeResponse for T {
+    /// Fails with [`RpcError::ResponseTooLarge`] before allocating anything
+    /// when the value would encode to more than [`MAX_RESPONSE_SIZE`] bytes.
+    fn encode_response(&self, codec: &ProtobufCodec) -> Result<Bytes, RpcError> {
+        if self.encoded_len() > MAX_RESPONSE_SIZE {
+            return Err(RpcError::ResponseTooLarge);
+        }
+        codec.encode(self)
+    }
+}
+
 ///
 /// Binary wire format for a single RPC message chunk.
 ///
 /// Layout (byte offsets):
 ///
 /// ```text
-/// 0        8       10      12      16
-/// |---------|-------|-------|-------|-------------------|
-/// | call_id | index | total | len   | payload bytes...  |
-/// | u64     | u16   | u16   | u32   | len bytes         |
+/// 0       2         3         11      13      15      19     20      24               32
+/// |-------|---------|---------|-------|-------|-------|------|-------|----------------|-------------------|
+/// | magic | version | call_id | index | total | len   | kind | crc32 | correlation_id | payload bytes...  |
+/// | "CG"  | u8      | u64     | u16   | u16   | u32   | u8   | u32   | u64            | len bytes         |
 /// ```
 ///
 /// Field descriptions:
 ///
+/// - `magic`
+///   Always [`CHUNK_MAGIC`]. Anything else is not a corgi chunk and is
+///   rejected with [`RpcError::BadMagic`].
+///
+/// - `version`
+///   The wire format version, [`PROTOCOL_VERSION`]. Chunks of any other
+///   version are rejected with [`RpcError::UnsupportedVersion`], so format
+///   changes fail cleanly instead of being misparsed. Its high bit,
+///   [`BIG_ENDIAN_FLAG`], is set when the header's integer fields are
+///   big-endian; a chunk whose order differs from the codec's
+///   [`Endianness`] is rejected with [`RpcError::EndiannessMismatch`].
+///   The next bit, [`NO_CHECKSUM_FLAG`], is set when the chunk was sent
+///   without a checksum.
+///
 /// - `call_id`
 ///   A unique identifier for the RPC call or message.
 ///   All chunks belonging to the same logical message share the same `call_id`.
@@ -68,74 +310,277 @@ impl ProtobufCodec {
 /// - `len`
 ///   Length (in bytes) of the payload that immediately follows the header.
 ///
+/// - `kind`
+///   The [`MessageKind`] of the message: `0` for a request, `1` for a
+///   response, `2` for a failure response. A response reuses the `call_id`
+///   of the request it answers.
+///
+/// - `crc32`
+///   CRC-32 (IEEE) of the payload. UDP's own 16-bit checksum is weak and
+///   optional, so corrupted payloads are rejected here instead of being
+///   reassembled and decoded into garbage. Zero when the chunk carries
+///   [`NO_CHECKSUM_FLAG`]: the flag, not the receiving codec, decides
+///   whether the checksum is validated, so peers with checksums disabled
+///   and enabled understand each other.
+///
+/// - `correlation_id`
+///   The `call_id` of the request a response answers, `0` on requests.
+///   Depending on [`ResponseIds`](crate::server::ResponseIds), a response
+///   either reuses that `call_id` or gets an id of its own, so clients
+///   match responses to calls by this field.
+///
 /// - `payload`
 ///   Raw binary payload bytes. The payload is opaque to the transport layer
 ///   and is interpreted by higher-level protocol logic.
 ///
 /// Notes:
 ///
-/// - All integer fields are encoded in **little-endian** order.
-/// - The header size is fixed (`CHUNK_HEADER_SIZE = 16` bytes).
+/// - All integer fields are encoded in **little-endian** order, unless the
+///   codec is configured for [`Endianness::Big`]. The payload's own layout
+///   does not depend on it.
+/// - The header size is fixed (`CHUNK_HEADER_SIZE = 32` bytes).
 /// - The codec performs strict bounds checking to prevent malformed or
 ///   truncated packets from causing panics.
 ///
-#[derive(Default, Clone)]
-pub struct PackageChunkCodec;
+/// Version 0 chunks, written before the header had a magic and a version,
+/// start right with a 16-byte `call_id | index | total | len` header in
+/// little-endian order. They are rejected with [`RpcError::BadMagic`],
+/// unless [`PackageChunkCodec::with_compat_v0`] is enabled.
+///
+#[derive(Debug, Default, Clone, Copy)]
+pub struct PackageChunkCodec {
+    endianness: Endianness,
+    compat_v0: bool,
+    skip_checksums: bool,
+}
 
 impl PackageChunkCodec {
+    /// Creates a codec writing and expecting headers in `endianness`.
+    pub fn new(endianness: Endianness) -> Self {
+        Self {
+            endianness,
+            compat_v0: false,
+            skip_checksums: false,
+        }
+    }
+
+    /// Sets whether encoded chunks carry a payload checksum, which is the
+    /// default. Without, they are sent with [`NO_CHECKSUM_FLAG`] and
+    /// receivers skip validating them, saving a CRC over every payload on
+    /// both ends.
+    pub fn with_checksums(mut self, checksums: bool) -> Self {
+        self.skip_checksums = !checksums;
+        self
+    }
+
+    /// Makes [`PackageChunkCodec::decode`] accept version 0 request chunks
+    /// too, for peers not upgraded yet.
+    ///
+    /// Version 0 chunks have no magic, so a datagram without one is taken
+    /// for a version 0 chunk when its `len` matches the datagram's size, its
+    /// `index` is below `total`, and a first chunk starts with a function
+    /// name length that fits the payload. Such chunks carry no checksum and
+    /// decode as [`MessageKind::Request`]s; the first one decoded logs a
+    /// deprecation warning. Chunks are always encoded in the current
+    /// version.
+    pub fn with_compat_v0(mut self, compat_v0: bool) -> Self {
+        self.compat_v0 = compat_v0;
+        self
+    }
+
+    pub fn endianness(&self) -> Endianness {
+        self.endianness
+    }
+
     pub fn encode(&self, value: PackageChunk) -> Result<Bytes, RpcError> {
         let header = value.header();
+        let order = self.endianness;
+        let mut version = match order {
+            Endianness::Little => PROTOCOL_VERSION,
+            Endianness::Big => PROTOCOL_VERSION | BIG_ENDIAN_FLAG,
+        };
+        let checksum = if self.skip_checksums {
+            version |= NO_CHECKSUM_FLAG;
+            0
+        } else {
+            crc32fast::hash(value.payload())
+        };
         let mut bytes = BytesMut::with_capacity(CHUNK_HEADER_SIZE + header.payload_len() as usize);
 
-        bytes.put_u64(header.call_id());
-        bytes.put_u16(header.index());
-        bytes.put_u16(header.total());
-        bytes.put_u32(header.payload_len());
+        bytes.put_slice(&CHUNK_MAGIC);
+        bytes.put_u8(version);
+        bytes.put_slice(&order.u64_bytes(header.call_id()));
+        bytes.put_slice(&order.u16_bytes(header.index()));
+        bytes.put_slice(&order.u16_bytes(header.total()));
+        bytes.put_slice(&order.u32_bytes(header.payload_len()));
+        bytes.put_u8(header.kind() as u8);
+        bytes.put_slice(&order.u32_bytes(checksum));
+        bytes.put_slice(&order.u64_bytes(header.correlation_id()));
 
         bytes.extend_from_slice(value.payload());
 
         Ok(bytes.freeze())
     }
 
+    /// Splits a whole message into chunks that fit [`UDP_CHUNK_SIZE`] once
+    /// encoded.
+    ///
+    /// Chunk payloads are [`Bytes::slice`]s of `payload`, so they share its
+    /// buffer instead of copying it: a handler's `Bytes` response reaches
+    /// the encoder without being duplicated per chunk.
+    ///
+    /// # Errors
+    ///
+    /// - [`RpcError::MessageTooLarge`] if the payload exceeds
+    ///   [`MAX_MESSAGE_SIZE`], more than `u16::MAX` chunks
+    pub fn split(
+        &self,
+        call_id: CallId,
+        correlation_id: CallId,
+        kind: MessageKind,
+        payload: &Bytes,
+    ) -> Result<Vec<PackageChunk>, RpcError> {
+        cut_chunks(
+            call_id,
+            correlation_id,
+            kind,
+            payload,
+            UDP_CHUNK_SIZE - CHUNK_HEADER_SIZE,
+        )
+    }
+
+    /// Splits a whole message into encoded chunks of at most
+    /// [`UDP_CHUNK_SIZE`] bytes, ready to be sent one datagram each.
+    pub(crate) fn encode_message(
+        &self,
+        call_id: CallId,
+        correlation_id: CallId,
+        kind: MessageKind,
+        payload: &Bytes,
+    ) -> Result<Vec<Bytes>, RpcError> {
+        self.split(call_id, correlation_id, kind, payload)?
+            .into_iter()
+            .map(|chunk| self.encode(chunk))
+            .collect()
+    }
+
     pub fn decode(&self, bytes: &[u8]) -> Result<PackageChunk, RpcError> {
+        if self.compat_v0
+            && !bytes.starts_with(&CHUNK_MAGIC)
+            && let Some(chunk) = decode_v0(bytes)
+        {
+            return Ok(chunk);
+        }
+
         if bytes.len() < CHUNK_HEADER_SIZE {
             return Err(RpcError::ChunkHeaderSizeConstraintViolation);
         }
 
-        let len = bytes[12..16]
-            .try_into()
-            .map(u32::from_le_bytes)
-            .map_err(|_| RpcError::Decode)?;
+        if bytes[..2] != CHUNK_MAGIC {
+            return Err(RpcError::BadMagic);
+        }
+
+        if bytes[2] & !VERSION_FLAGS != PROTOCOL_VERSION {
+            return Err(RpcError::UnsupportedVersion);
+        }
+
+        let order = self.endianness;
+        if (bytes[2] & BIG_ENDIAN_FLAG != 0) != (order == Endianness::Big) {
+            return Err(RpcError::EndiannessMismatch);
+        }
+
+        let len = order.read_u32(&bytes[15..19])?;
 
         if bytes.len() < CHUNK_HEADER_SIZE + len as usize {
             return Err(RpcError::ChunkHeaderSizeConstraintViolation);
         }
 
-        let call_id = bytes[..8]
-            .try_into()
-            .map(u64::from_le_bytes)
-            .map_err(|_| RpcError::Decode)?;
+        let call_id = order.read_u64(&bytes[3..11])?;
 
-        let index = bytes[8..10]
-            .try_into()
-            .map(u16::from_le_bytes)
-            .map_err(|_| RpcError::Decode)?;
+        let index = order.read_u16(&bytes[11..13])?;
 
-        let total = bytes[10..12]
-            .try_into()
-            .map(u16::from_le_bytes)
-            .map_err(|_| RpcError::Decode)?;
+        let total = order.read_u16(&bytes[13..15])?;
 
-        let header = ChunkHeader::new(call_id, index, total, len);
+        let kind = MessageKind::try_from(bytes[19])?;
+
+        let correlation_id = order.read_u64(&bytes[24..32])?;
+
+        let header = ChunkHeader::try_new(call_id, index, total, len)?
+            .with_kind(kind)
+            .with_correlation_id(correlation_id);
 
         let payload_start = CHUNK_HEADER_SIZE;
         let payload_end = payload_start + len as usize;
-        let payload = Bytes::copy_from_slice(&bytes[payload_start..payload_end + len as usize]);
+        let payload = &bytes[payload_start..payload_end];
+
+        let checksum = order.read_u32(&bytes[20..24])?;
+
+        if bytes[2] & NO_CHECKSUM_FLAG == 0 && checksum != crc32fast::hash(payload) {
+            return Err(RpcError::ChecksumMismatch);
+        }
+
+        let payload = Bytes::copy_from_slice(payload);
 
         Ok(PackageChunk::new(header, payload))
     }
 }
 
+/// Decodes `bytes` as a version 0 request chunk, if they look like one. See
+/// [`PackageChunkCodec::with_compat_v0`] for what looks like one.
+fn decode_v0(bytes: &[u8]) -> Option<PackageChunk> {
+    static WARNED: AtomicBool = AtomicBool::new(false);
+
+    let order = Endianness::Little;
+    let header = bytes.get(..LEGACY_CHUNK_HEADER_SIZE)?;
+    let payload = &bytes[LEGACY_CHUNK_HEADER_SIZE..];
+    let call_id = order.read_u64(&header[0..8]).ok()?;
+    let index = order.read_u16(&header[8..10]).ok()?;
+    let total = order.read_u16(&header[10..12]).ok()?;
+    let len = order.read_u32(&header[12..16]).ok()?;
+
+    if len as usize != payload.len() {
+        return None;
+    }
+    if index == 0 {
+        let fn_len = order.read_u16(payload.get(..2)?).ok()? as usize;
+        if fn_len == 0 || fn_len > payload.len() - 2 {
+            return None;
+        }
+    }
+    let header = ChunkHeader::try_new(call_id, index, total, len).ok()?;
+
+    if !WARNED.swap(true, Ordering::Relaxed) {
+        tracing::warn!(
+            "Decoded a version 0 chunk of call {call_id}; version 0 is deprecated, upgrade the peer"
+        );
+    }
+
+    Some(PackageChunk::new(header, Bytes::copy_from_slice(payload)))
+}
+
+///
+/// Binary wire format for an RPC call envelope.
+///
+/// Layout:
+///
+/// ```text
+/// | fn_len | fn_name | arg_count | (arg_len | arg)*   | [header_count | (key_len | key | value_len | value)*] |
+/// | u16    | fn_len  | u16       | (u64     | bytes)* | [u16          | (u16     | key | u32       | value)*] |
+/// ```
+///
+/// `fn_name` must be valid UTF-8. It is validated once while decoding so the
+/// rest of the pipeline can treat it as a `&str` lookup key.
+///
+/// The headers section is optional: it is only written when the envelope
+/// carries at least one header, so envelopes without metadata keep the
+/// original layout.
+///
+/// Notes:
+///
+/// - All integer fields are encoded in **little-endian** order.
+/// - Headers are limited to `MAX_HEADERS_COUNT` entries and
+///   `MAX_HEADERS_SIZE` bytes of keys and values in total.
+///
 #[derive(Default, Clone)]
 pub struct EnvelopeCodec;
 
@@ -143,46 +588,45 @@ impl EnvelopeCodec {
     pub fn encode(&self, value: Envelope) -> Result<Bytes, RpcError> {
         let fn_name = value.fn_name();
         let args = value.parameters();
+        let headers = value.headers();
 
-        if fn_name.len() > MAX_FUNCTION_NAME_SIZE {
-            return Err(RpcError::MaxFunctionNameConstraintViolation);
-        }
-
-        if args.len() > MAX_ARGUMENTS_COUNT {
-            return Err(RpcError::MaxArgumentsConstraintViolation);
-        }
-
-        for arg in args {
-            if arg.len() > MAX_ARGUMENT_SIZE {
-                return Err(RpcError::MaxArgumentSizeConstraintViolation);
-            }
-        }
-
-        // fn name + fn len + args count
-        let mut capacity = 2 + fn_name.len() + 2;
-
-        // Allocation for each argument
-        for arg in args {
-            capacity += 8 + arg.len();
-        }
+        let capacity = encoded_envelope_len(fn_name.as_bytes(), args, headers)?;
 
         let mut buf = BytesMut::with_capacity(capacity);
 
-        buf.put_u16(fn_name.len() as u16);
+        buf.put_u16_le(fn_name.len() as u16);
 
-        buf.extend_from_slice(fn_name);
+        buf.extend_from_slice(fn_name.as_bytes());
 
-        buf.put_u16(args.len() as u16);
+        buf.put_u16_le(args.len() as u16);
 
         for arg in args {
-            buf.put_u64(arg.len() as u64);
+            buf.put_u64_le(arg.len() as u64);
             buf.extend_from_slice(arg);
         }
 
+        if !headers.is_empty() {
+            buf.put_u16_le(headers.len() as u16);
+
+            for (key, value) in headers {
+                buf.put_u16_le(key.len() as u16);
+                buf.extend_from_slice(key);
+                buf.put_u32_le(value.len() as u32);
+                buf.extend_from_slice(value);
+            }
+        }
+
         Ok(buf.freeze())
     }
 
     pub fn decode(&self, bytes: &[u8]) -> Result<Envelope, RpcError> {
+        self.decode_shared(&Bytes::copy_from_slice(bytes))
+    }
+
+    /// Like [`EnvelopeCodec::decode`], without copying: the decoded arguments
+    /// and headers are slices of `bytes` and keep its buffer alive for as
+    /// long as any of them is.
+    pub fn decode_shared(&self, bytes: &Bytes) -> Result<Envelope, RpcError> {
         let mut cursor = 0;
 
         // Function name length
@@ -206,7 +650,9 @@ impl EnvelopeCodec {
             return Err(RpcError::Decode);
         }
 
-        let fn_name = Bytes::copy_from_slice(&bytes[cursor..cursor + fn_len]);
+        let fn_name = std::str::from_utf8(&bytes[cursor..cursor + fn_len])
+            .map_err(|_| RpcError::InvalidFunctionName)?
+            .to_owned();
         cursor += fn_len;
 
         if bytes.len() < cursor + 2 {
@@ -225,7 +671,9 @@ impl EnvelopeCodec {
         }
 
         // Arguments
-        let mut parameters = Vec::with_capacity(arg_count);
+        // Each argument takes at least its 8 byte length prefix.
+        let mut parameters =
+            Vec::with_capacity(bounded_capacity(arg_count, bytes.len() - cursor, 8));
 
         for _ in 0..arg_count {
             if bytes.len() < cursor + 8 {
@@ -247,18 +695,673 @@ impl EnvelopeCodec {
                 return Err(RpcError::Decode);
             }
 
-            let arg = &bytes[cursor..cursor + arg_len];
+            let arg = bytes.slice(cursor..cursor + arg_len);
             cursor += arg_len;
 
-            parameters.push(Bytes::copy_from_slice(arg));
+            parameters.push(arg);
+        }
+
+        // Headers
+        let mut headers = Vec::new();
+
+        if cursor < bytes.len() {
+            if bytes.len() < cursor + 2 {
+                return Err(RpcError::Decode);
+            }
+
+            let header_count = bytes[cursor..cursor + 2]
+                .try_into()
+                .map(u16::from_le_bytes)
+                .map_err(|_| RpcError::Decode)? as usize;
+
+            cursor += 2;
+
+            if header_count > MAX_HEADERS_COUNT {
+                return Err(RpcError::MaxHeadersConstraintViolation);
+            }
+
+            let mut headers_size = 0;
+            // Each header takes at least its 2 and 4 byte length prefixes.
+            headers.reserve(bounded_capacity(header_count, bytes.len() - cursor, 6));
+
+            for _ in 0..header_count {
+                if bytes.len() < cursor + 2 {
+                    return Err(RpcError::Decode);
+                }
+
+                let key_len = bytes[cursor..cursor + 2]
+                    .try_into()
+                    .map(u16::from_le_bytes)
+                    .map_err(|_| RpcError::Decode)? as usize;
+
+                cursor += 2;
+
+                if bytes.len() < cursor + key_len + 4 {
+                    return Err(RpcError::Decode);
+                }
+
+                let key = bytes.slice(cursor..cursor + key_len);
+                cursor += key_len;
+
+                let value_len = bytes[cursor..cursor + 4]
+                    .try_into()
+                    .map(u32::from_le_bytes)
+                    .map_err(|_| RpcError::Decode)? as usize;
+
+                cursor += 4;
+
+                headers_size += key_len + value_len;
+                if headers_size > MAX_HEADERS_SIZE {
+                    return Err(RpcError::MaxHeadersSizeConstraintViolation);
+                }
+
+                if bytes.len() < cursor + value_len {
+                    return Err(RpcError::Decode);
+                }
+
+                let value = bytes.slice(cursor..cursor + value_len);
+                cursor += value_len;
+
+                headers.push((key, value));
+            }
         }
 
         if cursor != bytes.len() {
             return Err(RpcError::GarbageBytes);
         }
 
-        let envelope = Envelope::new(fn_name, parameters);
+        let envelope = Envelope::new(fn_name, parameters).with_headers(headers);
 
         Ok(envelope)
     }
 }
+
+/// Splits the payload of request `call_id` into chunks carrying at most
+/// `max_chunk` payload bytes each, without encoding or sending them.
+///
+/// Chunks are indexed sequentially from 0 and agree on `total`. An empty
+/// payload still yields one empty chunk, so the message arrives. Like
+/// [`PackageChunkCodec::split`], which cuts messages through the same code,
+/// chunk payloads are views of `payload`'s buffer.
+///
+/// # Errors
+///
+/// - [`RpcError::MaxChunkPayloadSizeConstraintViolation`] if `max_chunk` is
+///   zero or its chunks would not fit the [`UDP_CHUNK_SIZE`] datagrams
+///   clients and servers receive into
+/// - [`RpcError::MessageTooLarge`] if more than `u16::MAX` chunks are needed
+pub fn chunk_payload(
+    call_id: CallId,
+    payload: &Bytes,
+    max_chunk: usize,
+) -> Result<Vec<PackageChunk>, RpcError> {
+    // Receive buffers hold `UDP_CHUNK_SIZE` bytes, so a larger datagram
+    // would arrive truncated and fail its checksum.
+    if max_chunk == 0 || max_chunk > UDP_CHUNK_SIZE - CHUNK_HEADER_SIZE {
+        return Err(RpcError::MaxChunkPayloadSizeConstraintViolation);
+    }
+
+    cut_chunks(call_id, 0, MessageKind::Request, payload, max_chunk)
+}
+
+fn cut_chunks(
+    call_id: CallId,
+    correlation_id: CallId,
+    kind: MessageKind,
+    payload: &Bytes,
+    chunk_size: usize,
+) -> Result<Vec<PackageChunk>, RpcError> {
+    let total = payload.len().div_ceil(chunk_size).max(1);
+    let total = u16::try_from(total).map_err(|_| RpcError::MessageTooLarge)?;
+
+    Ok((0..total)
+        .map(|index| {
+            let start = (index as usize * chunk_size).min(payload.len());
+            let end = (start + chunk_size).min(payload.len());
+            let header = ChunkHeader::new(call_id, index, total, (end - start) as u32)
+                .with_kind(kind)
+                .with_correlation_id(correlation_id);
+            PackageChunk::new(header, payload.slice(start..end))
+        })
+        .collect())
+}
+
+/// Estimates the on-wire size of a call without encoding or sending it.
+///
+/// Returns the encoded envelope length in bytes and the number of chunks
+/// needed to carry it in datagrams of at most `mtu` bytes, chunk headers
+/// included. The same constraints as [`EnvelopeCodec::encode`] apply, so a
+/// call that would be rejected on encode is rejected here too.
+///
+/// # Errors
+///
+/// Returns [`RpcError::ChunkHeaderSizeConstraintViolation`] if `mtu` cannot
+/// fit a chunk header and at least one payload byte, or any constraint
+/// violation [`EnvelopeCodec::encode`] would report.
+pub fn estimate_wire_size(
+    fn_name: &str,
+    args: &[Bytes],
+    mtu: usize,
+) -> Result<(usize, usize), RpcError> {
+    if mtu <= CHUNK_HEADER_SIZE {
+        return Err(RpcError::ChunkHeaderSizeConstraintViolation);
+    }
+
+    let envelope_len = encoded_envelope_len(fn_name.as_bytes(), args, &[])?;
+    let chunk_payload_size = (mtu - CHUNK_HEADER_SIZE).min(MAX_CHUNK_PAYLOAD_SIZE);
+    let chunks = envelope_len.div_ceil(chunk_payload_size).max(1);
+
+    Ok((envelope_len, chunks))
+}
+
+/// Validates envelope parts against the codec limits and returns the exact
+/// encoded length.
+fn encoded_envelope_len(
+    fn_name: &[u8],
+    args: &[Bytes],
+    headers: &[(Bytes, Bytes)],
+) -> Result<usize, RpcError> {
+    if fn_name.len() > MAX_FUNCTION_NAME_SIZE {
+        return Err(RpcError::MaxFunctionNameConstraintViolation);
+    }
+
+    if args.len() > MAX_ARGUMENTS_COUNT {
+        return Err(RpcError::MaxArgumentsConstraintViolation);
+    }
+
+    for arg in args {
+        if arg.len() > MAX_ARGUMENT_SIZE {
+            return Err(RpcError::MaxArgumentSizeConstraintViolation);
+        }
+    }
+
+    validate_headers(headers)?;
+
+    // fn name + fn len + args count
+    let mut len = 2 + fn_name.len() + 2;
+
+    // Allocation for each argument
+    for arg in args {
+        len += 8 + arg.len();
+    }
+
+    // Allocation for headers count and each header
+    if !headers.is_empty() {
+        len += 2;
+        for (key, value) in headers {
+            len += 2 + key.len() + 4 + value.len();
+        }
+    }
+
+    Ok(len)
+}
+
+fn validate_headers(headers: &[(Bytes, Bytes)]) -> Result<(), RpcError> {
+    if headers.len() > MAX_HEADERS_COUNT {
+        return Err(RpcError::MaxHeadersConstraintViolation);
+    }
+
+    let mut headers_size = 0;
+
+    for (key, value) in headers {
+        headers_size += key.len() + value.len();
+    }
+
+    if headers_size > MAX_HEADERS_SIZE {
+        return Err(RpcError::MaxHeadersSizeConstraintViolation);
+    }
+
+    Ok(())
+}
+
+///
+/// Binary wire format for an error frame sent in place of a result.
+///
+/// Layout:
+///
+/// ```text
+/// | code | message_len | message           |
+/// | u16  | u32         | message_len bytes |
+/// ```
+///
+/// `code` is taken from the stable table in [`crate::protocol::codes`] and
+/// `message` is a UTF-8 description intended for logs, not for matching.
+/// Decoding always yields [`RpcError::Remote`], since the original variant
+/// lives on the peer.
+///
+#[derive(Default, Clone)]
+pub struct ErrorFrameCodec;
+
+impl ErrorFrameCodec {
+    pub fn encode(&self, error: &RpcError) -> Bytes {
+        let message = match error {
+            RpcError::Remote { message, .. } => message.clone(),
+            error => format!("{error:?}"),
+        };
+
+        let mut buf = BytesMut::with_capacity(2 + 4 + message.len());
+        buf.put_u16_le(error.code());
+        buf.put_u32_le(message.len() as u32);
+        buf.extend_from_slice(message.as_bytes());
+
+        buf.freeze()
+    }
+
+    pub fn decode(&self, bytes: &[u8]) -> Result<RpcError, RpcError> {
+        if bytes.len() < 6 {
+            return Err(RpcError::Decode);
+        }
+
+        let code = bytes[..2]
+            .try_into()
+            .map(u16::from_le_bytes)
+            .map_err(|_| RpcError::Decode)?;
+
+        let message_len = bytes[2..6]
+            .try_into()
+            .map(u32::from_le_bytes)
+            .map_err(|_| RpcError::Decode)? as usize;
+
+        if bytes.len() < 6 + message_len {
+            return Err(RpcError::Decode);
+        }
+
+        if bytes.len() != 6 + message_len {
+            return Err(RpcError::GarbageBytes);
+        }
+
+        let message = String::from_utf8(bytes[6..].to_vec()).map_err(|_| RpcError::Decode)?;
+
+        Ok(RpcError::Remote { code, message })
+    }
+}
+
+///
+/// Binary wire format of the error value a handler returned, carried in a
+/// [`FailureCodec`] frame.
+///
+/// Layout:
+///
+/// ```text
+/// | schema_id | error bytes       |
+/// | u64       | remaining bytes   |
+/// ```
+///
+/// `schema_id` identifies the function's declared error type, so a client
+/// can check it expects that type before decoding the error bytes with it.
+///
+#[derive(Default, Clone)]
+pub struct ApplicationErrorCodec;
+
+impl ApplicationErrorCodec {
+    /// Encodes an [`RpcError::Application`]. Any other error is rejected with
+    /// [`RpcError::Encode`].
+    pub fn encode(&self, error: &RpcError) -> Result<Bytes, RpcError> {
+        let RpcError::Application { schema_id, payload } = error else {
+            return Err(RpcError::Encode);
+        };
+
+        let mut buf = BytesMut::with_capacity(8 + payload.len());
+        buf.put_u64_le(*schema_id);
+        buf.extend_from_slice(payload);
+
+        Ok(buf.freeze())
+    }
+
+    pub fn decode(&self, bytes: &Bytes) -> Result<RpcError, RpcError> {
+        let schema_id = bytes
+            .get(..8)
+            .and_then(|schema_id| schema_id.try_into().ok())
+            .map(u64::from_le_bytes)
+            .ok_or(RpcError::Decode)?;
+
+        Ok(RpcError::Application {
+            schema_id,
+            payload: bytes.slice(8..),
+        })
+    }
+}
+
+///
+/// Binary wire format of a [`MessageKind::Failure`] response, reporting why
+/// a call failed.
+///
+/// Layout:
+///
+/// ```text
+/// | status | body            |
+/// | u8     | remaining bytes |
+/// ```
+///
+/// - status `0`: the call failed in the framework or the handler returned an
+///   [`RpcError`]. The body is an [`ErrorFrameCodec`] frame and decodes to
+///   [`RpcError::Remote`] with the code from [`crate::protocol::codes`].
+/// - status `1`: the handler returned its declared error type. The body is
+///   an [`ApplicationErrorCodec`] frame and decodes to
+///   [`RpcError::Application`].
+///
+#[derive(Default, Clone)]
+pub struct FailureCodec;
+
+/// FAILURE_STATUS_REMOTE indicates failure status byte of a failure described by an error frame
+const FAILURE_STATUS_REMOTE: u8 = 0;
+
+/// FAILURE_STATUS_APPLICATION indicates failure status byte of an error returned as a handler's
+/// declared error type
+const FAILURE_STATUS_APPLICATION: u8 = 1;
+
+impl FailureCodec {
+    pub fn encode(&self, error: &RpcError) -> Result<Bytes, RpcError> {
+        let (status, body) = match error {
+            RpcError::Application { .. } => (
+                FAILURE_STATUS_APPLICATION,
+                ApplicationErrorCodec.encode(error)?,
+            ),
+            error => (FAILURE_STATUS_REMOTE, ErrorFrameCodec.encode(error)),
+        };
+
+        let mut buf = BytesMut::with_capacity(1 + body.len());
+        buf.put_u8(status);
+        buf.extend_from_slice(&body);
+
+        Ok(buf.freeze())
+    }
+
+    pub fn decode(&self, bytes: &Bytes) -> Result<RpcError, RpcError> {
+        match bytes.first() {
+            Some(&FAILURE_STATUS_REMOTE) => ErrorFrameCodec.decode(&bytes[1..]),
+            Some(&FAILURE_STATUS_APPLICATION) => ApplicationErrorCodec.decode(&bytes.slice(1..)),
+            _ => Err(RpcError::Decode),
+        }
+    }
+}
+
+///
+/// Binary wire format for a response made of named fields.
+///
+/// Layout:
+///
+/// ```text
+/// | field_count | (name_len | name     | value_len | value)* |
+/// | u16         | (u16      | name_len | u64       | bytes)* |
+/// ```
+///
+/// Each value is encoded independently, so a client can decode only the
+/// fields it needs. Names must be valid UTF-8.
+///
+#[derive(Default, Clone)]
+pub struct ResponseFieldsCodec;
+
+impl ResponseFieldsCodec {
+    pub fn encode(&self, value: &ResponseFields) -> Result<Bytes, RpcError> {
+        let fields = value.fields();
+
+        if fields.len() > MAX_RESPONSE_FIELDS_COUNT {
+            return Err(RpcError::Encode);
+        }
+
+        let mut capacity = 2;
+        for (name, value) in fields {
+            if name.len() > u16::MAX as usize {
+                return Err(RpcError::Encode);
+            }
+            capacity += 2 + name.len() + 8 + value.len();
+        }
+
+        let mut buf = BytesMut::with_capacity(capacity);
+        buf.put_u16_le(fields.len() as u16);
+
+        for (name, value) in fields {
+            buf.put_u16_le(name.len() as u16);
+            buf.extend_from_slice(name.as_bytes());
+            buf.put_u64_le(value.len() as u64);
+            buf.extend_from_slice(value);
+        }
+
+        Ok(buf.freeze())
+    }
+
+    pub fn decode(&self, bytes: &[u8]) -> Result<ResponseFields, RpcError> {
+        let mut cursor = 0;
+
+        if bytes.len() < 2 {
+            return Err(RpcError::Decode);
+        }
+
+        let field_count = bytes[cursor..cursor + 2]
+            .try_into()
+            .map(u16::from_le_bytes)
+            .map_err(|_| RpcError::Decode)? as usize;
+
+        cursor += 2;
+
+        if field_count > MAX_RESPONSE_FIELDS_COUNT {
+            return Err(RpcError::Decode);
+        }
+
+        // Each field takes at least its 2 and 8 byte length prefixes.
+        let mut fields =
+            Vec::with_capacity(bounded_capacity(field_count, bytes.len() - cursor, 10));
+
+        for _ in 0..field_count {
+            if bytes.len() < cursor + 2 {
+                return Err(RpcError::Decode);
+            }
+
+            let name_len = bytes[cursor..cursor + 2]
+                .try_into()
+                .map(u16::from_le_bytes)
+                .map_err(|_| RpcError::Decode)? as usize;
+
+            cursor += 2;
+
+            if bytes.len() < cursor + name_len + 8 {
+                return Err(RpcError::Decode);
+            }
+
+            let name = std::str::from_utf8(&bytes[cursor..cursor + name_len])
+                .map_err(|_| RpcError::Decode)?
+                .to_owned();
+
+            cursor += name_len;
+
+            let value_len = bytes[cursor..cursor + 8]
+                .try_into()
+                .map(u64::from_le_bytes)
+                .map_err(|_| RpcError::Decode)? as usize;
+
+            cursor += 8;
+
+            if bytes.len() - cursor < value_len {
+                return Err(RpcError::Decode);
+            }
+
+            let value = Bytes::copy_from_slice(&bytes[cursor..cursor + value_len]);
+            cursor += value_len;
+
+            fields.push((name, value));
+        }
+
+        if cursor != bytes.len() {
+            return Err(RpcError::GarbageBytes);
+        }
+
+        Ok(ResponseFields::new(fields))
+    }
+}
+
+///
+/// Binary wire format for an exported container schema.
+///
+/// Layout:
+///
+/// ```text
+/// | fn_count | function*                                                           |
+/// | u16      |                                                                     |
+///
+/// function:
+/// | name_len | name     | has_return | [return_schema_id] | has_error | [error_schema_id] | param_count | param* |
+/// | u16      | name_len | u8         | [u64]              | u8        | [u64]             | u16         |        |
+///
+/// param:
+/// | name_len | name     | schema_id |
+/// | u16      | name_len | u64       |
+/// ```
+///
+/// `return_schema_id` is only present when `has_return` is `1`, and
+/// `error_schema_id` only when `has_error` is `1`.
+///
+#[derive(Default, Clone)]
+pub struct SchemaCodec;
+
+impl SchemaCodec {
+    pub fn encode(&self, descriptors: &[FunctionDescriptor]) -> Result<Bytes, RpcError> {
+        if descriptors.len() > u16::MAX as usize {
+            return Err(RpcError::Encode);
+        }
+
+        let mut buf = BytesMut::new();
+        buf.put_u16_le(descriptors.len() as u16);
+
+        for descriptor in descriptors {
+            put_schema_name(&mut buf, &descriptor.name)?;
+
+            for schema_id in [descriptor.return_schema_id, descriptor.error_schema_id] {
+                match schema_id {
+                    Some(schema_id) => {
+                        buf.put_u8(1);
+                        buf.put_u64_le(schema_id);
+                    }
+                    None => buf.put_u8(0),
+                }
+            }
+
+            if descriptor.params.len() > MAX_ARGUMENTS_COUNT {
+                return Err(RpcError::MaxArgumentsConstraintViolation);
+            }
+
+            buf.put_u16_le(descriptor.params.len() as u16);
+
+            for param in &descriptor.params {
+                put_schema_name(&mut buf, &param.name)?;
+                buf.put_u64_le(param.schema_id);
+            }
+        }
+
+        Ok(buf.freeze())
+    }
+
+    pub fn decode(&self, bytes: &[u8]) -> Result<Vec<FunctionDescriptor>, RpcError> {
+        let mut cursor = 0;
+
+        let fn_count = read_u16(bytes, &mut cursor)? as usize;
+        // Each descriptor takes at least a name length, two schema id tags
+        // and a parameter count.
+        let mut descriptors =
+            Vec::with_capacity(bounded_capacity(fn_count, bytes.len() - cursor, 7));
+
+        for _ in 0..fn_count {
+            let name = read_schema_name(bytes, &mut cursor)?;
+
+            let return_schema_id = read_optional_schema_id(bytes, &mut cursor)?;
+            let error_schema_id = read_optional_schema_id(bytes, &mut cursor)?;
+
+            let param_count = read_u16(bytes, &mut cursor)? as usize;
+
+            if param_count > MAX_ARGUMENTS_COUNT {
+                return Err(RpcError::MaxArgumentsConstraintViolation);
+            }
+
+            // Each parameter takes at least a name length and a schema id.
+            let mut params =
+                Vec::with_capacity(bounded_capacity(param_count, bytes.len() - cursor, 10));
+
+            for _ in 0..param_count {
+                let name = read_schema_name(bytes, &mut cursor)?;
+                let schema_id = read_u64(bytes, &mut cursor)?;
+                params.push(ParamDescriptor { name, schema_id });
+            }
+
+            descriptors.push(FunctionDescriptor {
+                name,
+                params,
+                return_schema_id,
+                error_schema_id,
+            });
+        }
+
+        if cursor != bytes.len() {
+            return Err(RpcError::GarbageBytes);
+        }
+
+        Ok(descriptors)
+    }
+}
+
+fn put_schema_name(buf: &mut BytesMut, name: &str) -> Result<(), RpcError> {
+    if name.len() > MAX_FUNCTION_NAME_SIZE {
+        return Err(RpcError::MaxFunctionNameConstraintViolation);
+    }
+
+    buf.put_u16_le(name.len() as u16);
+    buf.extend_from_slice(name.as_bytes());
+    Ok(())
+}
+
+fn read_optional_schema_id(bytes: &[u8], cursor: &mut usize) -> Result<Option<u64>, RpcError> {
+    match read_u8(bytes, cursor)? {
+        0 => Ok(None),
+        1 => read_u64(bytes, cursor).map(Some),
+        _ => Err(RpcError::Decode),
+    }
+}
+
+fn read_schema_name(bytes: &[u8], cursor: &mut usize) -> Result<String, RpcError> {
+    let len = read_u16(bytes, cursor)? as usize;
+
+    if bytes.len() < *cursor + len {
+        return Err(RpcError::Decode);
+    }
+
+    let name = std::str::from_utf8(&bytes[*cursor..*cursor + len])
+        .map_err(|_| RpcError::InvalidFunctionName)?
+        .to_owned();
+
+    *cursor += len;
+    Ok(name)
+}
+
+/// Bounds the capacity reserved for `count` items, each encoded in at least
+/// `min_size` bytes, by what the `remaining` input can hold. Counts come from
+/// the peer, so they must not size an allocation on their own.
+fn bounded_capacity(count: usize, remaining: usize, min_size: usize) -> usize {
+    count.min(remaining / min_size)
+}
+
+fn read_u8(bytes: &[u8], cursor: &mut usize) -> Result<u8, RpcError> {
+    let value = *bytes.get(*cursor).ok_or(RpcError::Decode)?;
+    *cursor += 1;
+    Ok(value)
+}
+
+fn read_u16(bytes: &[u8], cursor: &mut usize) -> Result<u16, RpcError> {
+    let value = bytes
+        .get(*cursor..*cursor + 2)
+        .ok_or(RpcError::Decode)?
+        .try_into()
+        .map(u16::from_le_bytes)
+        .map_err(|_| RpcError::Decode)?;
+    *cursor += 2;
+    Ok(value)
+}
+
+fn read_u64(bytes: &[u8], cursor: &mut usize) -> Result<u64, RpcError> {
+    let value = bytes
+        .get(*cursor..*cursor + 8)
+        .ok_or(RpcError::Decode)?
+        .try_into()
+        .map(u64::from_le_bytes)
+        .map_err(|_| RpcError::Decode)?;
+    *cursor += 8;
+    Ok(value)
+}
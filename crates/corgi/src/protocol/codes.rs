@@ -0,0 +1,130 @@
+//! Stable numeric error codes carried in error frames.
+//!
+//! Every [`RpcError`](crate::protocol::types::RpcError) variant maps to one
+//! code from this table. Codes are part of the wire protocol: they must never
+//! be renumbered or reused, so peers written in other languages can rely on
+//! them.
+//!
+//! | Code | Variant                                  |
+//! |------|------------------------------------------|
+//! | 1    | `Decode`                                 |
+//! | 2    | `Encode`                                 |
+//! | 3    | `MaxFunctionNameConstraintViolation`     |
+//! | 4    | `MaxArgumentsConstraintViolation`        |
+//! | 5    | `MaxArgumentSizeConstraintViolation`     |
+//! | 6    | `ChunkHeaderSizeConstraintViolation`     |
+//! | 7    | `MaxChunkPayloadSizeConstraintViolation` |
+//! | 8    | `InvalidChunkIndex`                      |
+//! | 9    | `MaxHeadersConstraintViolation`          |
+//! | 10   | `MaxHeadersSizeConstraintViolation`      |
+//! | 11   | `GarbageBytes`                           |
+//! | 12   | `ArgumentDecodeFailed`                   |
+//! | 13   | `SocketBinding`                          |
+//! | 14   | `LocalAddress`                           |
+//! | 15   | `InvalidFunctionName`                    |
+//! | 16   | `UnknownFunction`                        |
+//! | 17   | `DuplicateFunction`                      |
+//! | 18   | `Timeout`                                |
+//! | 19   | `ArityMismatch`                          |
+//! | 20   | `RateLimited`                            |
+//! | 21   | `ResponseTooLarge`                       |
+//! | 22   | `TooManyPartialMessages`                 |
+//! | 23   | `ChecksumMismatch`                       |
+//! | 24   | `BadMagic`                               |
+//! | 25   | `UnsupportedVersion`                     |
+//! | 26   | `FunctionNameTooLong`                    |
+//! | 27   | `Application`                            |
+//! | 28   | `HandlerPanicked`                        |
+//! | 29   | `EndiannessMismatch`                     |
+//! | 30   | `MessageTooLarge`                        |
+//! | 31   | `DeadlineExceeded`                       |
+//! | 32   | `Transport`                              |
+//! | 33   | `ConnectionClosed`                       |
+//! | 34   | `UnsupportedMessageKind`                 |
+//! | 35   | `SchemaMismatch`                         |
+//! | 36   | `FeatureDisabled`                        |
+//! | 37   | `ServerShuttingDown`                     |
+//! | 38   | `PeerUnreachable`                        |
+//! | 39   | `ClientBusy`                             |
+
+pub const DECODE: u16 = 1;
+pub const ENCODE: u16 = 2;
+pub const MAX_FUNCTION_NAME_CONSTRAINT_VIOLATION: u16 = 3;
+pub const MAX_ARGUMENTS_CONSTRAINT_VIOLATION: u16 = 4;
+pub const MAX_ARGUMENT_SIZE_CONSTRAINT_VIOLATION: u16 = 5;
+pub const CHUNK_HEADER_SIZE_CONSTRAINT_VIOLATION: u16 = 6;
+pub const MAX_CHUNK_PAYLOAD_SIZE_CONSTRAINT_VIOLATION: u16 = 7;
+pub const INVALID_CHUNK_INDEX: u16 = 8;
+pub const MAX_HEADERS_CONSTRAINT_VIOLATION: u16 = 9;
+pub const MAX_HEADERS_SIZE_CONSTRAINT_VIOLATION: u16 = 10;
+pub const GARBAGE_BYTES: u16 = 11;
+pub const ARGUMENT_DECODE_FAILED: u16 = 12;
+pub const SOCKET_BINDING: u16 = 13;
+pub const LOCAL_ADDRESS: u16 = 14;
+pub const INVALID_FUNCTION_NAME: u16 = 15;
+pub const UNKNOWN_FUNCTION: u16 = 16;
+pub const DUPLICATE_FUNCTION: u16 = 17;
+pub const TIMEOUT: u16 = 18;
+pub const ARITY_MISMATCH: u16 = 19;
+pub const RATE_LIMITED: u16 = 20;
+pub const RESPONSE_TOO_LARGE: u16 = 21;
+pub const TOO_MANY_PARTIAL_MESSAGES: u16 = 22;
+pub const CHECKSUM_MISMATCH: u16 = 23;
+pub const BAD_MAGIC: u16 = 24;
+pub const UNSUPPORTED_VERSION: u16 = 25;
+pub const FUNCTION_NAME_TOO_LONG: u16 = 26;
+pub const APPLICATION: u16 = 27;
+pub const HANDLER_PANICKED: u16 = 28;
+pub const ENDIANNESS_MISMATCH: u16 = 29;
+pub const MESSAGE_TOO_LARGE: u16 = 30;
+pub const DEADLINE_EXCEEDED: u16 = 31;
+pub const TRANSPORT: u16 = 32;
+pub const CONNECTION_CLOSED: u16 = 33;
+pub const UNSUPPORTED_MESSAGE_KIND: u16 = 34;
+pub const SCHEMA_MISMATCH: u16 = 35;
+pub const FEATURE_DISABLED: u16 = 36;
+pub const SERVER_SHUTTING_DOWN: u16 = 37;
+pub const PEER_UNREACHABLE: u16 = 38;
+pub const CLIENT_BUSY: u16 = 39;
+
+/// Returns a short, non-technical description of `code` suitable for
+/// showing to end users. Unknown codes get a generic message.
+pub fn describe(code: u16) -> &'static str {
+    match code {
+        DECODE
+        | CHUNK_HEADER_SIZE_CONSTRAINT_VIOLATION
+        | INVALID_CHUNK_INDEX
+        | GARBAGE_BYTES
+        | CHECKSUM_MISMATCH
+        | BAD_MAGIC => "A malformed message was received.",
+        ENCODE => "The request could not be prepared.",
+        MAX_FUNCTION_NAME_CONSTRAINT_VIOLATION
+        | FUNCTION_NAME_TOO_LONG
+        | MAX_ARGUMENTS_CONSTRAINT_VIOLATION
+        | MAX_ARGUMENT_SIZE_CONSTRAINT_VIOLATION
+        | MAX_CHUNK_PAYLOAD_SIZE_CONSTRAINT_VIOLATION
+        | MAX_HEADERS_CONSTRAINT_VIOLATION
+        | MAX_HEADERS_SIZE_CONSTRAINT_VIOLATION
+        | MESSAGE_TOO_LARGE => "The request is too large.",
+        ARGUMENT_DECODE_FAILED | ARITY_MISMATCH => "The request contained invalid arguments.",
+        SOCKET_BINDING | LOCAL_ADDRESS | TRANSPORT | CONNECTION_CLOSED | PEER_UNREACHABLE => {
+            "The network connection is unavailable."
+        }
+        INVALID_FUNCTION_NAME | UNKNOWN_FUNCTION | FEATURE_DISABLED => {
+            "The requested operation is not available."
+        }
+        DUPLICATE_FUNCTION => "The service is misconfigured.",
+        TIMEOUT | DEADLINE_EXCEEDED => "The service did not respond in time.",
+        UNSUPPORTED_VERSION | ENDIANNESS_MISMATCH | UNSUPPORTED_MESSAGE_KIND | SCHEMA_MISMATCH => {
+            "The client and service versions are incompatible."
+        }
+        RATE_LIMITED | CLIENT_BUSY | TOO_MANY_PARTIAL_MESSAGES => {
+            "Too many requests. Please try again later."
+        }
+        RESPONSE_TOO_LARGE => "The response is too large.",
+        APPLICATION => "The operation could not be completed.",
+        HANDLER_PANICKED => "The service failed to process the request.",
+        SERVER_SHUTTING_DOWN => "The service is restarting. Please try again shortly.",
+        _ => "An unexpected error occurred.",
+    }
+}
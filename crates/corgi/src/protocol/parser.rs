@@ -1,43 +1,228 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
 
 use bytes::{Bytes, BytesMut};
 
 use crate::protocol::{
     codec::{EnvelopeCodec, PackageChunkCodec},
-    types::{CallId, PackageChunk, RpcCall, RpcError},
+    types::{CallId, MessageKind, PackageChunk, RpcCall, RpcError},
 };
 
-#[derive(Default)]
+/// Identifies a reassembly. Call ids are chosen by each client
+/// independently, so two peers can legitimately use the same one.
+///
+/// The full `SocketAddr` is kept, including the scope id of link-local IPv6
+/// peers: the same link-local address on two interfaces is two peers.
+type ReassemblyKey = (SocketAddr, CallId);
+
+/// A reassembled message whose payload has not been interpreted yet.
+pub(crate) struct Message {
+    pub(crate) call_id: CallId,
+    pub(crate) correlation_id: CallId,
+    pub(crate) kind: MessageKind,
+    pub(crate) payload: Bytes,
+}
+
+/// MAX_PARTIAL_MESSAGES indicates default number of incomplete messages buffered at once
+pub(crate) const MAX_PARTIAL_MESSAGES: usize = 1024;
+
 pub(crate) struct Parser {
-    chunks: HashMap<CallId, Vec<PackageChunk>>,
+    chunks: HashMap<ReassemblyKey, Vec<PackageChunk>>,
+    /// When the first chunk of each incomplete message arrived.
+    started_at: HashMap<ReassemblyKey, Instant>,
+    /// Payload bytes of all buffered chunks.
+    buffered_bytes: usize,
+    max_partial_messages: usize,
     chunk_codec: PackageChunkCodec,
     envelope_codec: EnvelopeCodec,
 }
 
+impl Default for Parser {
+    fn default() -> Self {
+        Self::new(MAX_PARTIAL_MESSAGES)
+    }
+}
+
 impl Parser {
-    pub(crate) fn apply(&mut self, data: &[u8]) -> Result<Option<RpcCall>, RpcError> {
-        if let Some(call_id) = self.feed(data)? {
-            let bytes = self.build_package(call_id);
-            let envelope = self.envelope_codec.decode(&bytes)?;
-            let call = RpcCall::new(call_id, envelope);
-            return Ok(Some(call));
+    /// Creates a parser buffering at most `max_partial_messages` incomplete
+    /// messages, so a flood of distinct call ids can't exhaust memory.
+    pub(crate) fn new(max_partial_messages: usize) -> Self {
+        Self {
+            chunks: HashMap::new(),
+            started_at: HashMap::new(),
+            buffered_bytes: 0,
+            max_partial_messages,
+            chunk_codec: PackageChunkCodec::default(),
+            envelope_codec: EnvelopeCodec,
+        }
+    }
+
+    /// Decodes chunks with `chunk_codec` instead of the default one.
+    pub(crate) fn with_chunk_codec(mut self, chunk_codec: PackageChunkCodec) -> Self {
+        self.chunk_codec = chunk_codec;
+        self
+    }
+
+    /// Number of messages still waiting for chunks.
+    pub(crate) fn partial_messages(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// Payload bytes buffered for messages not taken yet.
+    pub(crate) fn buffered_bytes(&self) -> usize {
+        self.buffered_bytes
+    }
+
+    /// Periodic cleanup: drops incomplete messages whose first chunk arrived
+    /// more than `reassembly_timeout` before `now`, then gives memory left
+    /// over from bursts back. Returns the number of messages dropped.
+    pub(crate) fn maintenance_tick(&mut self, now: Instant, reassembly_timeout: Duration) -> usize {
+        let dropped = self.sweep_at(now, reassembly_timeout);
+        // Keep headroom, so a map is only reallocated once it is mostly
+        // empty instead of on every tick.
+        self.chunks.shrink_to(self.chunks.len() * 2);
+        self.started_at.shrink_to(self.started_at.len() * 2);
+        dropped
+    }
+
+    /// Drops incomplete messages whose first chunk arrived more than
+    /// `older_than` ago. Their remaining chunks were most likely lost.
+    fn sweep_at(&mut self, now: Instant, older_than: Duration) -> usize {
+        let (chunks, buffered_bytes) = (&mut self.chunks, &mut self.buffered_bytes);
+        let before = self.started_at.len();
+        self.started_at.retain(|key, started_at| {
+            let stale = now.saturating_duration_since(*started_at) > older_than;
+            if stale && let Some(dropped) = chunks.remove(key) {
+                *buffered_bytes -= payload_len(&dropped);
+            }
+            !stale
+        });
+        before - self.started_at.len()
+    }
+
+    /// Feeds a datagram and returns the call once every chunk of it has
+    /// arrived: [`Parser::feed`] and [`Parser::take`] in one step.
+    pub(crate) fn apply(
+        &mut self,
+        peer: SocketAddr,
+        data: &[u8],
+    ) -> Result<Option<RpcCall>, RpcError> {
+        match self.feed(peer, data)? {
+            Some(call_id) => self.take(peer, call_id),
+            None => Ok(None),
+        }
+    }
+
+    /// Whether every chunk of `call_id` from `peer` has arrived and the call
+    /// is waiting to be taken.
+    pub(crate) fn is_complete(&self, peer: SocketAddr, call_id: CallId) -> bool {
+        self.chunks.get(&(peer, call_id)).is_some_and(|chunks| {
+            chunks
+                .first()
+                .is_some_and(|first| first.header().total() as usize == chunks.len())
+        })
+    }
+
+    /// Removes the complete call `call_id` of `peer` and decodes it. Returns
+    /// `Ok(None)` while chunks are still missing, leaving them buffered.
+    ///
+    /// # Errors
+    ///
+    /// - [`RpcError::Decode`] if the message is not a request
+    /// - envelope violations reported by [`EnvelopeCodec::decode_shared`]
+    pub(crate) fn take(
+        &mut self,
+        peer: SocketAddr,
+        call_id: CallId,
+    ) -> Result<Option<RpcCall>, RpcError> {
+        if !self.is_complete(peer, call_id) {
+            return Ok(None);
+        }
+
+        let message = self.build_package((peer, call_id));
+        if message.kind != MessageKind::Request {
+            return Err(RpcError::Decode);
+        }
+        let envelope = self.envelope_codec.decode_shared(&message.payload)?;
+        Ok(Some(RpcCall::new(message.call_id, envelope)))
+    }
+
+    /// Feeds a datagram and returns the concatenated payload once every chunk
+    /// of its call has arrived, without interpreting it as an envelope.
+    pub(crate) fn reassemble(
+        &mut self,
+        peer: SocketAddr,
+        data: &[u8],
+    ) -> Result<Option<Message>, RpcError> {
+        if let Some(call_id) = self.feed(peer, data)? {
+            return Ok(Some(self.build_package((peer, call_id))));
         }
 
         Ok(None)
     }
 
-    fn feed(&mut self, data: &[u8]) -> Result<Option<CallId>, RpcError> {
+    /// Buffers a datagram and reports its call id once every chunk of the
+    /// call has arrived. The call stays buffered, counting against the
+    /// partial message limit and the reassembly timeout, until it is taken
+    /// with [`Parser::take`].
+    pub(crate) fn feed(
+        &mut self,
+        peer: SocketAddr,
+        data: &[u8],
+    ) -> Result<Option<CallId>, RpcError> {
+        // Decoding validates the header, so chunks with `total == 0` or
+        // `index >= total` are rejected here, before anything is buffered.
         let chunk = self.chunk_codec.decode(data)?;
+        self.insert(peer, chunk)
+    }
+
+    /// Buffers a decoded chunk and reports its call id once every chunk of
+    /// the call has arrived.
+    fn insert(
+        &mut self,
+        peer: SocketAddr,
+        chunk: PackageChunk,
+    ) -> Result<Option<CallId>, RpcError> {
         let total = chunk.header().total() as usize;
         let call_id = chunk.header().call_id();
+
+        // A chunk claiming a different `total` than the call it joins would
+        // let its index slip past the established range.
+        if let Some(buffered) = self.chunks.get(&(peer, call_id))
+            && buffered
+                .first()
+                .is_some_and(|first| first.header().total() as usize != total)
+        {
+            return Err(RpcError::InvalidChunkIndex);
+        }
+
+        let key = (peer, call_id);
+        if !self.chunks.contains_key(&key) {
+            if self.chunks.len() >= self.max_partial_messages {
+                return Err(RpcError::TooManyPartialMessages);
+            }
+            self.started_at.insert(key, Instant::now());
+        }
         let package_chunks = self
             .chunks
-            .entry(chunk.header().call_id())
-            .or_insert_with(|| {
-                let mut chunks = Vec::with_capacity(chunk.header().total() as usize);
-                chunks.push(chunk);
-                chunks
-            });
+            .entry(key)
+            .or_insert_with(|| Vec::with_capacity(total));
+
+        // UDP may deliver a chunk twice. Keeping only the first copy makes
+        // the buffered chunks a set of distinct indices, so reaching `total`
+        // means every index in `0..total` is present.
+        let index = chunk.header().index();
+        if package_chunks
+            .iter()
+            .any(|buffered| buffered.header().index() == index)
+        {
+            return Ok(None);
+        }
+        self.buffered_bytes += chunk.payload().len();
+        package_chunks.push(chunk);
 
         if total == package_chunks.len() {
             package_chunks.sort();
@@ -47,15 +232,591 @@ impl Parser {
         Ok(None)
     }
 
-    fn build_package(&mut self, call_id: CallId) -> Bytes {
-        let package_chunks = self.chunks.remove(&call_id).unwrap();
-        package_chunks
+    fn build_package(&mut self, key: ReassemblyKey) -> Message {
+        let package_chunks = self.chunks.remove(&key).unwrap();
+        self.started_at.remove(&key);
+        self.buffered_bytes -= payload_len(&package_chunks);
+        debug_assert_reassembly_invariants(&package_chunks);
+        let header = package_chunks[0].header();
+        let (kind, correlation_id) = (header.kind(), header.correlation_id());
+        let payload = package_chunks
             .iter()
             .map(|p| p.payload())
             .fold(BytesMut::new(), |mut acc, value| {
                 acc.extend_from_slice(value);
                 acc
             })
-            .freeze()
+            .freeze();
+        Message {
+            call_id: key.1,
+            correlation_id,
+            kind,
+            payload,
+        }
+    }
+}
+
+fn payload_len(chunks: &[PackageChunk]) -> usize {
+    chunks.iter().map(|chunk| chunk.payload().len()).sum()
+}
+
+/// Checks the invariants a completed reassembly must hold before its payloads
+/// are concatenated. Free in release builds.
+///
+/// - every chunk belongs to the same call and agrees on `total`
+/// - `total` matches the number of buffered chunks
+/// - chunks are sorted by index with no gaps or duplicates, i.e. chunk `i`
+///   has index `i`
+fn debug_assert_reassembly_invariants(chunks: &[PackageChunk]) {
+    if cfg!(debug_assertions)
+        && let Some(first) = chunks.first()
+    {
+        let call_id = first.header().call_id();
+        let total = first.header().total() as usize;
+
+        debug_assert_eq!(total, chunks.len(), "chunk count must match total");
+
+        for (position, chunk) in chunks.iter().enumerate() {
+            let header = chunk.header();
+            debug_assert_eq!(header.call_id(), call_id, "chunks must share call_id");
+            debug_assert_eq!(header.total() as usize, total, "chunks must agree on total");
+            debug_assert_eq!(
+                header.index() as usize,
+                position,
+                "chunks must be sorted without gaps"
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::{
+        codec::{CHUNK_HEADER_SIZE, UDP_CHUNK_SIZE, chunk_payload},
+        make_datagram as datagram,
+        types::{ChunkHeader, Envelope},
+    };
+
+    const PEER: &str = "127.0.0.1:4000";
+
+    fn peer(address: &str) -> SocketAddr {
+        address.parse().unwrap()
+    }
+
+    #[test]
+    fn parser_should_apply_single_chunk_call_built_by_make_datagram() {
+        let mut parser = Parser::default();
+        let envelope = Envelope::new("add".to_owned(), vec![Bytes::from_static(b"123456")]);
+        let payload = EnvelopeCodec.encode(envelope).unwrap();
+
+        let call = parser
+            .apply(peer(PEER), &datagram(7, 0, 1, &payload))
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(call.call_id(), 7);
+        assert_eq!(call.envelope().fn_name(), "add");
+        assert_eq!(call.envelope().parameters()[0].as_ref(), b"123456");
+        assert!(parser.chunks.is_empty());
+    }
+
+    /// Chunks `payload` with [`chunk_payload`] and feeds the encoded chunks
+    /// back to front, returning the chunk count and the reassembled bytes.
+    fn chunk_and_reassemble(payload: &[u8], max_chunk: usize) -> (usize, Bytes) {
+        let mut parser = Parser::default();
+        let chunks = chunk_payload(7, &Bytes::copy_from_slice(payload), max_chunk).unwrap();
+        let count = chunks.len();
+        for (index, chunk) in chunks.iter().enumerate() {
+            assert_eq!(chunk.header().index() as usize, index);
+            assert_eq!(chunk.header().total() as usize, count);
+        }
+
+        let mut message = None;
+        for chunk in chunks.into_iter().rev() {
+            let bytes = PackageChunkCodec::default().encode(chunk).unwrap();
+            assert!(message.is_none());
+            message = parser.reassemble(peer(PEER), &bytes).unwrap();
+        }
+        (count, message.unwrap().payload)
+    }
+
+    #[test]
+    fn chunk_payload_should_round_trip_through_parser() {
+        let payload: Vec<u8> = (0..=255).collect();
+
+        let (count, reassembled) = chunk_and_reassemble(&payload, 100);
+
+        assert_eq!(count, 3);
+        assert_eq!(reassembled.as_ref(), payload.as_slice());
+    }
+
+    #[test]
+    fn chunk_payload_should_send_empty_and_exactly_fitting_payloads_as_one_chunk() {
+        let (empty_count, empty) = chunk_and_reassemble(&[], 100);
+        let (fitting_count, fitting) = chunk_and_reassemble(&[1; 100], 100);
+
+        assert_eq!(empty_count, 1);
+        assert!(empty.is_empty());
+        assert_eq!(fitting_count, 1);
+        assert_eq!(fitting.as_ref(), [1; 100]);
+    }
+
+    #[test]
+    fn chunk_payload_should_cut_chunks_fitting_receive_buffers() {
+        let max_chunk = UDP_CHUNK_SIZE - CHUNK_HEADER_SIZE;
+        let payload = Bytes::from(vec![1; max_chunk * 2]);
+
+        let chunks = chunk_payload(7, &payload, max_chunk).unwrap();
+
+        assert_eq!(chunks.len(), 2);
+        for chunk in chunks {
+            let datagram = PackageChunkCodec::default().encode(chunk).unwrap();
+            assert_eq!(datagram.len(), UDP_CHUNK_SIZE);
+        }
+    }
+
+    #[test]
+    fn chunk_payload_should_reject_unusable_chunk_size() {
+        let payload = Bytes::from_static(b"payload");
+
+        let zero = chunk_payload(7, &payload, 0);
+        let oversized = chunk_payload(7, &payload, UDP_CHUNK_SIZE - CHUNK_HEADER_SIZE + 1);
+
+        assert!(matches!(
+            zero,
+            Err(RpcError::MaxChunkPayloadSizeConstraintViolation)
+        ));
+        assert!(matches!(
+            oversized,
+            Err(RpcError::MaxChunkPayloadSizeConstraintViolation)
+        ));
+    }
+
+    #[test]
+    fn parser_should_keep_complete_call_buffered_until_taken() {
+        let mut parser = Parser::default();
+        let datagrams = envelope_datagrams(7, 2);
+
+        assert!(matches!(parser.feed(peer(PEER), &datagrams[1]), Ok(None)));
+        assert!(!parser.is_complete(peer(PEER), 7));
+        assert!(matches!(parser.take(peer(PEER), 7), Ok(None)));
+        assert!(matches!(
+            parser.feed(peer(PEER), &datagrams[0]),
+            Ok(Some(7))
+        ));
+        assert!(parser.is_complete(peer(PEER), 7));
+        assert!(!parser.is_complete(peer("127.0.0.1:5000"), 7));
+        let call = parser.take(peer(PEER), 7).unwrap().unwrap();
+
+        assert_eq!(call.call_id(), 7);
+        assert_eq!(call.envelope().fn_name(), "add");
+        assert_eq!(call.envelope().parameters()[0].as_ref(), b"123456");
+        assert!(!parser.is_complete(peer(PEER), 7));
+        assert!(matches!(parser.take(peer(PEER), 7), Ok(None)));
+        assert!(parser.chunks.is_empty());
+    }
+
+    #[test]
+    fn parser_should_reject_chunk_with_zero_total_without_buffering() {
+        let mut parser = Parser::default();
+
+        let result = parser.apply(peer(PEER), &datagram(7, 0, 0, &[]));
+
+        assert!(matches!(result, Err(RpcError::InvalidChunkIndex)));
+        assert!(parser.chunks.is_empty());
+    }
+
+    #[test]
+    fn parser_should_reject_out_of_range_index_for_in_progress_call() {
+        let mut parser = Parser::default();
+        assert!(
+            parser
+                .apply(peer(PEER), &datagram(7, 0, 3, &[]))
+                .unwrap()
+                .is_none()
+        );
+
+        let out_of_range = parser.apply(peer(PEER), &datagram(7, 3, 3, &[]));
+        let inconsistent_total = parser.apply(peer(PEER), &datagram(7, 3, 4, &[]));
+
+        assert!(matches!(out_of_range, Err(RpcError::InvalidChunkIndex)));
+        assert!(matches!(
+            inconsistent_total,
+            Err(RpcError::InvalidChunkIndex)
+        ));
+        assert_eq!(parser.chunks[&(peer(PEER), 7)].len(), 1);
+    }
+
+    #[test]
+    fn parser_should_keep_same_call_id_from_different_peers_apart() {
+        let mut parser = Parser::default();
+        let first_peer = peer("127.0.0.1:4000");
+        let second_peer = peer("127.0.0.1:5000");
+
+        let first = parser.apply(first_peer, &datagram(7, 0, 3, &[]));
+        let second = parser.apply(second_peer, &datagram(7, 0, 2, &[]));
+
+        assert!(matches!(first, Ok(None)));
+        assert!(matches!(second, Ok(None)));
+        assert_eq!(parser.chunks[&(first_peer, 7)][0].header().total(), 3);
+        assert_eq!(parser.chunks[&(second_peer, 7)][0].header().total(), 2);
+    }
+
+    #[test]
+    fn parser_should_keep_link_local_peers_on_different_interfaces_apart() {
+        let mut parser = Parser::default();
+        let first_peer = peer("[fe80::1%1]:4000");
+        let second_peer = peer("[fe80::1%2]:4000");
+
+        let first = parser.apply(first_peer, &datagram(7, 0, 3, &[]));
+        let second = parser.apply(second_peer, &datagram(7, 0, 2, &[]));
+
+        assert!(matches!(first, Ok(None)));
+        assert!(matches!(second, Ok(None)));
+        let keys: Vec<_> = parser.chunks.keys().map(|(peer, _)| *peer).collect();
+        assert_eq!(keys.len(), 2);
+        assert!(
+            keys.iter()
+                .any(|peer| peer.to_string() == "[fe80::1%1]:4000")
+        );
+        assert!(
+            keys.iter()
+                .any(|peer| peer.to_string() == "[fe80::1%2]:4000")
+        );
+    }
+
+    #[test]
+    fn parser_should_not_complete_other_peers_message_with_same_call_id() {
+        let mut parser = Parser::default();
+        let first_peer = peer("127.0.0.1:4000");
+        let second_peer = peer("127.0.0.1:5000");
+        assert!(matches!(
+            parser.apply(first_peer, &datagram(7, 0, 2, &[])),
+            Ok(None)
+        ));
+
+        // The second peer's single-chunk message completes (and fails to
+        // decode, being empty) without touching the first peer's entry.
+        let second = parser.apply(second_peer, &datagram(7, 0, 1, &[]));
+
+        assert!(matches!(second, Err(RpcError::Decode)));
+        assert!(!parser.chunks.contains_key(&(second_peer, 7)));
+        assert_eq!(parser.chunks[&(first_peer, 7)].len(), 1);
+    }
+
+    #[test]
+    fn parser_should_apply_out_of_order_datagrams_into_call() {
+        let mut parser = Parser::default();
+        let datagrams = envelope_datagrams(7, 3);
+
+        assert!(matches!(parser.apply(peer(PEER), &datagrams[1]), Ok(None)));
+        assert!(matches!(parser.apply(peer(PEER), &datagrams[2]), Ok(None)));
+        let call = parser.apply(peer(PEER), &datagrams[0]).unwrap().unwrap();
+
+        assert_eq!(call.call_id(), 7);
+        assert_eq!(call.envelope().fn_name(), "add");
+        assert_eq!(call.envelope().parameters()[0].as_ref(), b"123456");
+    }
+
+    #[test]
+    fn parser_should_ignore_duplicated_chunk() {
+        let mut parser = Parser::default();
+        let datagrams = envelope_datagrams(7, 2);
+
+        assert!(matches!(parser.apply(peer(PEER), &datagrams[0]), Ok(None)));
+        assert!(matches!(parser.apply(peer(PEER), &datagrams[0]), Ok(None)));
+        assert_eq!(parser.chunks[&(peer(PEER), 7)].len(), 1);
+        let call = parser.apply(peer(PEER), &datagrams[1]).unwrap().unwrap();
+
+        assert_eq!(call.envelope().fn_name(), "add");
+        assert_eq!(call.envelope().parameters()[0].as_ref(), b"123456");
+        assert!(parser.chunks.is_empty());
+    }
+
+    #[test]
+    fn parser_should_demultiplex_interleaved_chunks_of_concurrent_calls() {
+        let mut parser = Parser::default();
+        // Distinct payloads per call, so a chunk landing in the wrong call
+        // shows up in the reassembled bytes.
+        let payloads: HashMap<CallId, Vec<u8>> = [(1, 30), (2, 40), (3, 20)]
+            .into_iter()
+            .map(|(call_id, len)| (call_id, (0..len).map(|byte| byte ^ call_id as u8).collect()))
+            .collect();
+        let datagrams: HashMap<CallId, Vec<Bytes>> = payloads
+            .iter()
+            .map(|(&call_id, payload)| {
+                let chunks = chunk_payload(call_id, &Bytes::copy_from_slice(payload), 10).unwrap();
+                let encoded = chunks
+                    .into_iter()
+                    .map(|chunk| PackageChunkCodec::default().encode(chunk).unwrap())
+                    .collect();
+                (call_id, encoded)
+            })
+            .collect();
+        // Calls 1, 2 and 3 have 3, 4 and 2 chunks. Call 1's chunk 0 is
+        // delivered a second time between call 2's chunks.
+        let order = [
+            (2, 3),
+            (1, 0),
+            (3, 1),
+            (2, 0),
+            (1, 0),
+            (2, 2),
+            (1, 2),
+            (3, 0),
+            (2, 1),
+            (1, 1),
+        ];
+
+        let mut completed: HashMap<CallId, Vec<Bytes>> = HashMap::new();
+        for (call_id, index) in order {
+            if let Some(message) = parser
+                .reassemble(peer(PEER), &datagrams[&call_id][index])
+                .unwrap()
+            {
+                assert_eq!(message.call_id, call_id);
+                completed.entry(call_id).or_default().push(message.payload);
+            }
+        }
+
+        assert_eq!(completed.len(), 3);
+        for (call_id, payload) in &payloads {
+            let messages = &completed[call_id];
+            assert_eq!(
+                messages.len(),
+                1,
+                "call {call_id} must complete exactly once"
+            );
+            assert_eq!(messages[0].as_ref(), payload.as_slice());
+        }
+        assert!(parser.chunks.is_empty());
+        assert!(parser.started_at.is_empty());
+    }
+
+    #[test]
+    fn parser_should_sweep_only_messages_older_than_cutoff() {
+        let mut parser = Parser::default();
+        let now = Instant::now();
+        parser
+            .apply(peer(PEER), &datagram(7, 0, 2, b"abc"))
+            .unwrap();
+        parser.apply(peer(PEER), &datagram(8, 0, 2, b"de")).unwrap();
+        parser
+            .started_at
+            .insert((peer(PEER), 7), now - Duration::from_secs(10));
+        parser.started_at.insert((peer(PEER), 8), now);
+
+        parser.sweep_at(now, Duration::from_secs(5));
+
+        assert!(!parser.chunks.contains_key(&(peer(PEER), 7)));
+        assert!(parser.chunks.contains_key(&(peer(PEER), 8)));
+        assert_eq!(parser.started_at.len(), 1);
+        assert_eq!(parser.buffered_bytes(), 2);
+    }
+
+    #[test]
+    fn parser_should_drop_expired_messages_cumulatively_across_maintenance_ticks() {
+        let mut parser = Parser::default();
+        let start = Instant::now();
+        let timeout = Duration::from_secs(10);
+        for call_id in 0..64 {
+            parser
+                .apply(peer(PEER), &datagram(call_id, 0, 2, &[]))
+                .unwrap();
+        }
+        for call_id in 0..64 {
+            let started_at = start + Duration::from_secs(call_id % 2 * 5);
+            parser.started_at.insert((peer(PEER), call_id), started_at);
+        }
+        let capacity = parser.chunks.capacity();
+
+        let dropped: Vec<_> = [6, 11, 16]
+            .into_iter()
+            .map(|secs| parser.maintenance_tick(start + Duration::from_secs(secs), timeout))
+            .collect();
+
+        assert_eq!(dropped, [0, 32, 32]);
+        assert!(parser.chunks.is_empty());
+        assert!(parser.started_at.is_empty());
+        assert!(parser.chunks.capacity() < capacity);
+    }
+
+    #[test]
+    fn parser_should_reject_new_message_beyond_max_partial_messages() {
+        let mut parser = Parser::new(2);
+        parser.apply(peer(PEER), &datagram(1, 0, 2, &[])).unwrap();
+        parser.apply(peer(PEER), &datagram(2, 0, 2, &[])).unwrap();
+
+        let third = parser.apply(peer(PEER), &datagram(3, 0, 2, &[]));
+        let in_progress = parser.apply(peer(PEER), &datagram(2, 1, 2, &[]));
+
+        assert!(matches!(third, Err(RpcError::TooManyPartialMessages)));
+        // Completing an in-progress message is still possible, and it fails
+        // only because the empty payload isn't an envelope.
+        assert!(matches!(in_progress, Err(RpcError::Decode)));
+        assert_eq!(parser.chunks.len(), 1);
+    }
+
+    /// Splits an encoded envelope into `total` chunks, returned by index.
+    fn envelope_chunks(call_id: CallId, total: u16) -> Vec<PackageChunk> {
+        let envelope = Envelope::new("add".to_owned(), vec![Bytes::from_static(b"123456")]);
+        let bytes = EnvelopeCodec.encode(envelope).unwrap();
+        let chunk_size = bytes.len().div_ceil(total as usize);
+
+        bytes
+            .chunks(chunk_size)
+            .enumerate()
+            .map(|(index, payload)| {
+                let header = ChunkHeader::new(call_id, index as u16, total, payload.len() as u32);
+                PackageChunk::new(header, Bytes::copy_from_slice(payload))
+            })
+            .collect()
+    }
+
+    /// Encodes [`envelope_chunks`] as datagrams, returned by index.
+    fn envelope_datagrams(call_id: CallId, total: u16) -> Vec<Bytes> {
+        envelope_chunks(call_id, total)
+            .iter()
+            .map(|chunk| {
+                let header = chunk.header();
+                datagram(call_id, header.index(), header.total(), chunk.payload())
+            })
+            .collect()
+    }
+
+    fn complete_call(parser: &mut Parser, call_id: CallId) -> RpcCall {
+        let bytes = parser.build_package((peer(PEER), call_id)).payload;
+        RpcCall::new(call_id, EnvelopeCodec.decode(&bytes).unwrap())
+    }
+
+    #[test]
+    fn parser_should_reassemble_two_chunks_received_out_of_order() {
+        let mut parser = Parser::default();
+        let mut chunks = envelope_chunks(7, 2);
+        let second = chunks.pop().unwrap();
+        let first = chunks.pop().unwrap();
+
+        assert!(matches!(parser.insert(peer(PEER), second), Ok(None)));
+        assert!(matches!(parser.insert(peer(PEER), first), Ok(Some(7))));
+
+        let call = complete_call(&mut parser, 7);
+        assert_eq!(call.envelope().fn_name(), "add");
+        assert_eq!(call.envelope().parameters()[0].as_ref(), b"123456");
+    }
+
+    #[test]
+    fn parser_should_reassemble_three_chunks_received_out_of_order() {
+        let mut parser = Parser::default();
+        let mut chunks = envelope_chunks(7, 3)
+            .into_iter()
+            .map(Some)
+            .collect::<Vec<_>>();
+
+        for index in [2, 0] {
+            let chunk = chunks[index].take().unwrap();
+            assert!(matches!(parser.insert(peer(PEER), chunk), Ok(None)));
+        }
+        let last = chunks[1].take().unwrap();
+        assert!(matches!(parser.insert(peer(PEER), last), Ok(Some(7))));
+
+        let call = complete_call(&mut parser, 7);
+        assert_eq!(call.envelope().fn_name(), "add");
+        assert_eq!(call.envelope().parameters()[0].as_ref(), b"123456");
+        assert!(parser.chunks.is_empty());
+    }
+
+    fn chunk(call_id: CallId, index: u16, total: u16) -> PackageChunk {
+        PackageChunk::new(
+            ChunkHeader::new(call_id, index, total, 1),
+            Bytes::from_static(b"x"),
+        )
+    }
+
+    /// Buffers `chunks` as call 7 of [`PEER`] without checking them.
+    fn buffer(parser: &mut Parser, chunks: Vec<PackageChunk>) {
+        parser.buffered_bytes += payload_len(&chunks);
+        parser.chunks.insert((peer(PEER), 7), chunks);
+    }
+
+    #[test]
+    fn parser_should_build_package_from_consistent_chunks() {
+        let mut parser = Parser::default();
+        buffer(
+            &mut parser,
+            vec![chunk(7, 0, 3), chunk(7, 1, 3), chunk(7, 2, 3)],
+        );
+
+        let message = parser.build_package((peer(PEER), 7));
+
+        assert_eq!(message.call_id, 7);
+        assert_eq!(message.kind, MessageKind::Request);
+        assert_eq!(message.payload.as_ref(), b"xxx");
+        assert_eq!(parser.buffered_bytes(), 0);
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "chunks must be sorted without gaps")]
+    fn parser_should_trip_invariant_on_gap_in_chunks() {
+        let mut parser = Parser::default();
+        buffer(
+            &mut parser,
+            vec![chunk(7, 0, 3), chunk(7, 2, 3), chunk(7, 2, 3)],
+        );
+
+        parser.build_package((peer(PEER), 7));
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "chunks must agree on total")]
+    fn parser_should_trip_invariant_on_inconsistent_total() {
+        let mut parser = Parser::default();
+        buffer(&mut parser, vec![chunk(7, 0, 2), chunk(7, 1, 3)]);
+
+        parser.build_package((peer(PEER), 7));
+    }
+
+    mod untrusted_input {
+        use proptest::prelude::*;
+
+        use super::*;
+
+        /// Datagrams with valid checksums but otherwise arbitrary headers,
+        /// over a small call id and index space so messages collide,
+        /// duplicate and complete.
+        fn datagrams() -> impl Strategy<Value = Vec<Bytes>> {
+            let datagram = (
+                0..4_u64,
+                0..4_u16,
+                0..4_u16,
+                proptest::collection::vec(any::<u8>(), 0..16),
+            )
+                .prop_map(|(call_id, index, total, payload)| {
+                    datagram(call_id, index, total, &payload)
+                });
+            proptest::collection::vec(datagram, 0..32)
+        }
+
+        proptest! {
+            #[test]
+            fn parser_should_never_panic_on_arbitrary_datagrams(
+                datagrams in prop::collection::vec(prop::collection::vec(any::<u8>(), 0..64), 0..16)
+            ) {
+                let mut parser = Parser::default();
+                for datagram in datagrams {
+                    let _ = parser.apply(peer(PEER), &datagram);
+                }
+            }
+
+            #[test]
+            fn parser_should_never_panic_on_colliding_chunk_sequences(datagrams in datagrams()) {
+                let mut parser = Parser::default();
+                for datagram in datagrams {
+                    let _ = parser.apply(peer(PEER), &datagram);
+                }
+            }
+        }
     }
 }
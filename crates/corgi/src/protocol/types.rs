@@ -1,27 +1,104 @@
 use core::fmt;
-use std::cmp;
+use std::{cmp, time::Duration};
 
 use bytes::Bytes;
+use prost::Message;
 use tokio::io;
 
+use crate::{
+    protocol::{
+        codec::{MAX_CHUNK_PAYLOAD_SIZE, PayloadCodec, ProtobufCodec},
+        codes,
+    },
+    schema,
+};
+
 pub type CallId = u64;
 
+/// Tells the chunks of a request apart from the chunks of the response
+/// answering it, which share the same [`CallId`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[repr(u8)]
+pub enum MessageKind {
+    #[default]
+    Request = 0,
+    Response = 1,
+    /// A response reporting that the call failed, see
+    /// [`FailureCodec`](crate::protocol::codec::FailureCodec).
+    Failure = 2,
+}
+
+impl TryFrom<u8> for MessageKind {
+    type Error = RpcError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(MessageKind::Request),
+            1 => Ok(MessageKind::Response),
+            2 => Ok(MessageKind::Failure),
+            _ => Err(RpcError::UnsupportedMessageKind),
+        }
+    }
+}
+
 #[derive(Debug, Eq)]
 pub struct ChunkHeader {
     call_id: CallId,
     index: u16,
     total: u16,
     len: u32,
+    kind: MessageKind,
+    correlation_id: CallId,
 }
 
 impl ChunkHeader {
+    /// Creates a header without validating its fields.
+    ///
+    /// Intended for trusted, internally produced values. Headers built from
+    /// untrusted input must go through [`ChunkHeader::try_new`].
     pub fn new(call_id: CallId, index: u16, total: u16, len: u32) -> Self {
         Self {
             call_id,
             index,
             total,
             len,
+            kind: MessageKind::Request,
+            correlation_id: 0,
+        }
+    }
+
+    /// Sets the call id of the request a response answers. Requests leave it
+    /// at `0`.
+    pub fn with_correlation_id(mut self, correlation_id: CallId) -> Self {
+        self.correlation_id = correlation_id;
+        self
+    }
+
+    /// Marks the chunk as part of a message of the given kind. Headers are
+    /// [`MessageKind::Request`] unless stated otherwise.
+    pub fn with_kind(mut self, kind: MessageKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    /// Creates a header, rejecting field combinations that can never describe
+    /// a valid chunk.
+    ///
+    /// # Errors
+    ///
+    /// - [`RpcError::InvalidChunkIndex`] if `total` is zero or `index >= total`
+    /// - [`RpcError::MaxChunkPayloadSizeConstraintViolation`] if `len` exceeds
+    ///   [`MAX_CHUNK_PAYLOAD_SIZE`]
+    pub fn try_new(call_id: CallId, index: u16, total: u16, len: u32) -> Result<Self, RpcError> {
+        if total == 0 || index >= total {
+            return Err(RpcError::InvalidChunkIndex);
+        }
+
+        if len as usize > MAX_CHUNK_PAYLOAD_SIZE {
+            return Err(RpcError::MaxChunkPayloadSizeConstraintViolation);
         }
+
+        Ok(Self::new(call_id, index, total, len))
     }
 
     pub fn call_id(&self) -> CallId {
@@ -39,6 +116,14 @@ impl ChunkHeader {
     pub fn payload_len(&self) -> u32 {
         self.len
     }
+
+    pub fn kind(&self) -> MessageKind {
+        self.kind
+    }
+
+    pub fn correlation_id(&self) -> CallId {
+        self.correlation_id
+    }
 }
 
 impl PartialEq for ChunkHeader {
@@ -63,8 +148,8 @@ impl fmt::Display for ChunkHeader {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "ChunkHeader(call_id={}, index={}, total={}, len={})",
-            self.call_id, self.index, self.total, self.len
+            "ChunkHeader(call_id={}, index={}, total={}, len={}, kind={:?}, correlation_id={})",
+            self.call_id, self.index, self.total, self.len, self.kind, self.correlation_id
         )
     }
 }
@@ -97,7 +182,7 @@ impl Ord for PackageChunk {
 
 impl PartialOrd for PackageChunk {
     fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
-        self.header().partial_cmp(&other.header())
+        Some(self.cmp(other))
     }
 }
 
@@ -118,40 +203,177 @@ impl fmt::Display for PackageChunk {
     }
 }
 
+/// Header carrying a call's deadline: the time, in ASCII decimal
+/// milliseconds, the server gives the handler once it starts executing it.
+///
+/// A budget rather than a point in time, so it doesn't depend on the peers'
+/// clocks agreeing.
+pub const DEADLINE_HEADER: &[u8] = b"corgi-deadline-ms";
+
+/// Header carrying the distributed trace a call belongs to: the 16-byte
+/// trace id followed by the 8-byte id of the caller's span, in binary.
+pub const TRACE_HEADER: &[u8] = b"corgi-trace";
+
+/// A call's place in a distributed trace, sent in the [`TRACE_HEADER`].
+///
+/// The server processes the call in a `rpc_call` span recording both ids as
+/// hex, `trace_id` and `parent_span_id`, for a tracing layer exporting spans
+/// to join it to the caller's.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TraceContext {
+    pub trace_id: [u8; 16],
+    /// The caller's span, which becomes the parent of the server's.
+    pub span_id: [u8; 8],
+}
+
+impl TraceContext {
+    pub(crate) fn encode(&self) -> Bytes {
+        Bytes::from([self.trace_id.as_slice(), &self.span_id].concat())
+    }
+
+    /// Reads a [`TRACE_HEADER`] value, or `None` if it is malformed.
+    pub(crate) fn decode(value: &[u8]) -> Option<Self> {
+        let (trace_id, span_id) = value.split_first_chunk::<16>()?;
+        Some(Self {
+            trace_id: *trace_id,
+            span_id: span_id.try_into().ok()?,
+        })
+    }
+}
+
 #[derive(Debug)]
 pub struct Envelope {
-    fn_name: Bytes,
+    /// Function names are lookup keys, so they are held as validated UTF-8
+    /// rather than raw bytes: a name is checked once on decode and every
+    /// later lookup borrows it as `&str` for free.
+    fn_name: String,
     parameters: Vec<Bytes>,
+    headers: Vec<(Bytes, Bytes)>,
 }
 
 impl Envelope {
-    pub fn new(fn_name: Bytes, parameters: Vec<Bytes>) -> Self {
+    pub fn new(fn_name: String, parameters: Vec<Bytes>) -> Self {
         Self {
             fn_name,
             parameters,
+            headers: Vec::new(),
         }
     }
 
-    pub fn fn_name(&self) -> &Bytes {
+    /// Attaches key-value metadata (auth tokens, trace ids, tenant, ...) to
+    /// the call. Headers are opaque to the framework, except for
+    /// [`DEADLINE_HEADER`] and [`TRACE_HEADER`].
+    pub fn with_headers(mut self, headers: Vec<(Bytes, Bytes)>) -> Self {
+        self.headers = headers;
+        self
+    }
+
+    /// Adds a [`DEADLINE_HEADER`] giving the handler `deadline` to finish,
+    /// rounded down to whole milliseconds.
+    pub fn with_deadline(mut self, deadline: Duration) -> Self {
+        self.headers.push((
+            Bytes::from_static(DEADLINE_HEADER),
+            Bytes::from(deadline.as_millis().to_string()),
+        ));
+        self
+    }
+
+    /// Adds a [`TRACE_HEADER`] making the call part of `trace`.
+    pub fn with_trace(mut self, trace: TraceContext) -> Self {
+        self.headers
+            .push((Bytes::from_static(TRACE_HEADER), trace.encode()));
+        self
+    }
+
+    /// Returns the trace set with [`Envelope::with_trace`]. A malformed
+    /// [`TRACE_HEADER`] is ignored.
+    pub fn trace(&self) -> Option<TraceContext> {
+        TraceContext::decode(self.header(TRACE_HEADER)?)
+    }
+
+    /// Returns the deadline set with [`Envelope::with_deadline`]. A
+    /// malformed [`DEADLINE_HEADER`] is ignored.
+    pub fn deadline(&self) -> Option<Duration> {
+        let value = self.header(DEADLINE_HEADER)?;
+        let millis = std::str::from_utf8(value).ok()?.parse().ok()?;
+        Some(Duration::from_millis(millis))
+    }
+
+    pub fn fn_name(&self) -> &str {
         &self.fn_name
     }
 
     pub fn parameters(&self) -> &Vec<Bytes> {
         &self.parameters
     }
+
+    pub fn headers(&self) -> &Vec<(Bytes, Bytes)> {
+        &self.headers
+    }
+
+    /// Returns the value of the first header matching `key`.
+    pub fn header(&self, key: &[u8]) -> Option<&Bytes> {
+        self.headers
+            .iter()
+            .find(|(header_key, _)| header_key.as_ref() == key)
+            .map(|(_, value)| value)
+    }
 }
 
 impl fmt::Display for Envelope {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "Envelope(fn_name=Byyes[{}], parameters={})",
-            self.fn_name.len(),
+            "Envelope(fn_name={}, parameters={}, headers={})",
+            self.fn_name,
             self.parameters().len(),
+            self.headers().len(),
         )
     }
 }
 
+/// Portable description of a registered function, as exported by
+/// `Container::export_schema`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionDescriptor {
+    pub name: String,
+    pub params: Vec<ParamDescriptor>,
+    pub return_schema_id: Option<u64>,
+    pub error_schema_id: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParamDescriptor {
+    pub name: String,
+    pub schema_id: u64,
+}
+
+/// A response made of named, individually encoded fields.
+///
+/// Produced by handlers returning a type that derives `RpcResponse`.
+#[derive(Debug, Default)]
+pub struct ResponseFields {
+    fields: Vec<(String, Bytes)>,
+}
+
+impl ResponseFields {
+    pub fn new(fields: Vec<(String, Bytes)>) -> Self {
+        Self { fields }
+    }
+
+    pub fn fields(&self) -> &Vec<(String, Bytes)> {
+        &self.fields
+    }
+
+    /// Returns the encoded value of the field called `name`.
+    pub fn get(&self, name: &str) -> Option<&Bytes> {
+        self.fields
+            .iter()
+            .find(|(field_name, _)| field_name == name)
+            .map(|(_, value)| value)
+    }
+}
+
 #[derive(Debug)]
 pub struct RpcCall {
     call_id: CallId,
@@ -162,6 +384,14 @@ impl RpcCall {
     pub fn new(call_id: CallId, envelope: Envelope) -> Self {
         RpcCall { call_id, envelope }
     }
+
+    pub fn call_id(&self) -> CallId {
+        self.call_id
+    }
+
+    pub fn envelope(&self) -> &Envelope {
+        &self.envelope
+    }
 }
 
 impl fmt::Display for RpcCall {
@@ -182,7 +412,268 @@ pub enum RpcError {
     MaxArgumentsConstraintViolation,
     MaxArgumentSizeConstraintViolation,
     ChunkHeaderSizeConstraintViolation,
+    MaxChunkPayloadSizeConstraintViolation,
+    InvalidChunkIndex,
+    MaxHeadersConstraintViolation,
+    MaxHeadersSizeConstraintViolation,
     GarbageBytes,
+    InvalidFunctionName,
+    UnknownFunction,
+    DuplicateFunction,
+    Timeout,
+    ArityMismatch,
+    RateLimited,
+    ResponseTooLarge,
+    TooManyPartialMessages,
+    ChecksumMismatch,
+    BadMagic,
+    UnsupportedVersion,
+    /// The chunk header's byte order differs from the one this end is
+    /// configured for.
+    EndiannessMismatch,
+    FunctionNameTooLong,
+    /// The handler panicked instead of returning. The panic is caught and
+    /// logged; the server keeps serving.
+    HandlerPanicked,
+    /// The message needs more chunks than a chunk header can count.
+    MessageTooLarge,
+    /// The handler did not finish within the deadline the caller set with
+    /// [`Envelope::with_deadline`]. It is cancelled.
+    DeadlineExceeded,
+    ArgumentDecodeFailed,
     SocketBinding(io::Error),
     LocalAddress(io::Error),
+    /// Sending a datagram failed at the socket.
+    Transport(io::Error),
+    /// The client's receive task is gone, so no reply can arrive anymore.
+    ConnectionClosed,
+    /// The chunk header names a message kind this end does not know, most
+    /// likely one introduced by a newer peer.
+    UnsupportedMessageKind,
+    /// The schema id of an [`RpcError::Application`] payload differs from
+    /// that of the type it is decoded into. Schema ids are built from Rust
+    /// type names, see [`schema_id`](crate::schema_id).
+    SchemaMismatch,
+    /// The call names a built-in function, see [`builtins`](crate::builtins),
+    /// but the server does not answer them.
+    FeatureDisabled,
+    /// The server is draining before shutdown and takes no new calls, see
+    /// [`RpcServer::enter_lame_duck`](crate::RpcServer::enter_lame_duck).
+    /// Another instance can take the call.
+    ServerShuttingDown,
+    /// The socket reported the server as gone, e.g. after an ICMP port
+    /// unreachable. Every call in flight to it fails with this at once instead
+    /// of timing out.
+    PeerUnreachable,
+    /// The calls in flight exhausted the client's
+    /// [`ClientConfig::memory_budget`](crate::ClientConfig::memory_budget),
+    /// and the call was refused rather than made to wait.
+    ClientBusy,
+    /// An error value returned by a handler, encoded as the function's
+    /// declared error type, whose schema id it carries. Decode it with
+    /// [`RpcError::application_error`].
+    Application {
+        schema_id: u64,
+        payload: Bytes,
+    },
+    /// An error reported by the remote peer, identified by its stable code.
+    Remote {
+        code: u16,
+        message: String,
+    },
+}
+
+/// Technical description for logs and developers; use
+/// [`RpcError::user_message`] for end users.
+impl fmt::Display for RpcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RpcError::Decode => write!(f, "failed to decode message"),
+            RpcError::Encode => write!(f, "failed to encode message"),
+            RpcError::MaxFunctionNameConstraintViolation => {
+                write!(f, "function name exceeds the maximum size")
+            }
+            RpcError::MaxArgumentsConstraintViolation => write!(f, "too many arguments"),
+            RpcError::MaxArgumentSizeConstraintViolation => {
+                write!(f, "argument exceeds the maximum size")
+            }
+            RpcError::ChunkHeaderSizeConstraintViolation => {
+                write!(f, "chunk is shorter than its header")
+            }
+            RpcError::MaxChunkPayloadSizeConstraintViolation => {
+                write!(f, "chunk payload exceeds the maximum size")
+            }
+            RpcError::InvalidChunkIndex => write!(f, "chunk index is out of range"),
+            RpcError::MaxHeadersConstraintViolation => write!(f, "too many envelope headers"),
+            RpcError::MaxHeadersSizeConstraintViolation => {
+                write!(f, "envelope headers exceed the maximum size")
+            }
+            RpcError::GarbageBytes => write!(f, "unexpected trailing bytes"),
+            RpcError::InvalidFunctionName => write!(f, "function name is not valid UTF-8"),
+            RpcError::UnknownFunction => write!(f, "no function registered under this name"),
+            RpcError::DuplicateFunction => write!(f, "function is already registered"),
+            RpcError::Timeout => write!(f, "call timed out"),
+            RpcError::ArityMismatch => write!(f, "wrong number of arguments"),
+            RpcError::RateLimited => write!(f, "peer exceeded its rate limit"),
+            RpcError::ResponseTooLarge => write!(f, "response exceeds the maximum size"),
+            RpcError::TooManyPartialMessages => {
+                write!(f, "too many partially received messages")
+            }
+            RpcError::ChecksumMismatch => write!(f, "chunk checksum mismatch"),
+            RpcError::BadMagic => write!(f, "chunk does not start with the protocol magic"),
+            RpcError::UnsupportedVersion => write!(f, "unsupported protocol version"),
+            RpcError::EndiannessMismatch => write!(f, "chunk header byte order mismatch"),
+            RpcError::FunctionNameTooLong => write!(f, "function name exceeds the limit"),
+            RpcError::HandlerPanicked => write!(f, "handler panicked"),
+            RpcError::MessageTooLarge => write!(f, "message needs too many chunks"),
+            RpcError::DeadlineExceeded => write!(f, "call deadline exceeded"),
+            RpcError::ArgumentDecodeFailed => write!(f, "failed to decode argument"),
+            RpcError::SocketBinding(error) => write!(f, "failed to bind socket: {error}"),
+            RpcError::LocalAddress(error) => {
+                write!(f, "failed to resolve local address: {error}")
+            }
+            RpcError::Transport(error) => write!(f, "failed to send datagram: {error}"),
+            RpcError::ConnectionClosed => write!(f, "connection closed"),
+            RpcError::UnsupportedMessageKind => write!(f, "unsupported message kind"),
+            RpcError::SchemaMismatch => write!(f, "schema id mismatch"),
+            RpcError::FeatureDisabled => write!(f, "built-in functions are disabled"),
+            RpcError::ServerShuttingDown => write!(f, "server is shutting down"),
+            RpcError::PeerUnreachable => write!(f, "peer unreachable"),
+            RpcError::ClientBusy => write!(f, "client memory budget exhausted"),
+            RpcError::Application { schema_id, .. } => {
+                write!(f, "handler returned an error (schema id {schema_id:#018x})")
+            }
+            RpcError::Remote { code, message } => {
+                write!(f, "remote error {code}: {message}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for RpcError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RpcError::SocketBinding(error)
+            | RpcError::LocalAddress(error)
+            | RpcError::Transport(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl RpcError {
+    /// Returns the stable wire code of this error, see [`codes`].
+    pub fn code(&self) -> u16 {
+        match self {
+            RpcError::Decode => codes::DECODE,
+            RpcError::Encode => codes::ENCODE,
+            RpcError::MaxFunctionNameConstraintViolation => {
+                codes::MAX_FUNCTION_NAME_CONSTRAINT_VIOLATION
+            }
+            RpcError::MaxArgumentsConstraintViolation => codes::MAX_ARGUMENTS_CONSTRAINT_VIOLATION,
+            RpcError::MaxArgumentSizeConstraintViolation => {
+                codes::MAX_ARGUMENT_SIZE_CONSTRAINT_VIOLATION
+            }
+            RpcError::ChunkHeaderSizeConstraintViolation => {
+                codes::CHUNK_HEADER_SIZE_CONSTRAINT_VIOLATION
+            }
+            RpcError::MaxChunkPayloadSizeConstraintViolation => {
+                codes::MAX_CHUNK_PAYLOAD_SIZE_CONSTRAINT_VIOLATION
+            }
+            RpcError::InvalidChunkIndex => codes::INVALID_CHUNK_INDEX,
+            RpcError::MaxHeadersConstraintViolation => codes::MAX_HEADERS_CONSTRAINT_VIOLATION,
+            RpcError::MaxHeadersSizeConstraintViolation => {
+                codes::MAX_HEADERS_SIZE_CONSTRAINT_VIOLATION
+            }
+            RpcError::GarbageBytes => codes::GARBAGE_BYTES,
+            RpcError::InvalidFunctionName => codes::INVALID_FUNCTION_NAME,
+            RpcError::UnknownFunction => codes::UNKNOWN_FUNCTION,
+            RpcError::DuplicateFunction => codes::DUPLICATE_FUNCTION,
+            RpcError::Timeout => codes::TIMEOUT,
+            RpcError::ArityMismatch => codes::ARITY_MISMATCH,
+            RpcError::RateLimited => codes::RATE_LIMITED,
+            RpcError::ResponseTooLarge => codes::RESPONSE_TOO_LARGE,
+            RpcError::TooManyPartialMessages => codes::TOO_MANY_PARTIAL_MESSAGES,
+            RpcError::ChecksumMismatch => codes::CHECKSUM_MISMATCH,
+            RpcError::BadMagic => codes::BAD_MAGIC,
+            RpcError::UnsupportedVersion => codes::UNSUPPORTED_VERSION,
+            RpcError::EndiannessMismatch => codes::ENDIANNESS_MISMATCH,
+            RpcError::FunctionNameTooLong => codes::FUNCTION_NAME_TOO_LONG,
+            RpcError::HandlerPanicked => codes::HANDLER_PANICKED,
+            RpcError::MessageTooLarge => codes::MESSAGE_TOO_LARGE,
+            RpcError::DeadlineExceeded => codes::DEADLINE_EXCEEDED,
+            RpcError::ArgumentDecodeFailed => codes::ARGUMENT_DECODE_FAILED,
+            RpcError::SocketBinding(_) => codes::SOCKET_BINDING,
+            RpcError::LocalAddress(_) => codes::LOCAL_ADDRESS,
+            RpcError::Transport(_) => codes::TRANSPORT,
+            RpcError::ConnectionClosed => codes::CONNECTION_CLOSED,
+            RpcError::UnsupportedMessageKind => codes::UNSUPPORTED_MESSAGE_KIND,
+            RpcError::SchemaMismatch => codes::SCHEMA_MISMATCH,
+            RpcError::FeatureDisabled => codes::FEATURE_DISABLED,
+            RpcError::ServerShuttingDown => codes::SERVER_SHUTTING_DOWN,
+            RpcError::PeerUnreachable => codes::PEER_UNREACHABLE,
+            RpcError::ClientBusy => codes::CLIENT_BUSY,
+            RpcError::Application { .. } => codes::APPLICATION,
+            RpcError::Remote { code, .. } => *code,
+        }
+    }
+
+    /// Decodes an [`RpcError::Application`] into the handler's error type
+    /// `E`.
+    ///
+    /// Returns `None` for any other error, or when the error was declared
+    /// with another type than `E`; use [`RpcError::try_application_error`]
+    /// to tell these apart.
+    pub fn application_error<E: Message + Default>(&self) -> Option<E> {
+        self.application_error_with(&ProtobufCodec)
+    }
+
+    /// Like [`RpcError::application_error`], for functions declared with
+    /// another [`PayloadCodec`] than [`ProtobufCodec`].
+    pub fn application_error_with<E, C: PayloadCodec<E>>(&self, codec: &C) -> Option<E> {
+        self.try_application_error_with(codec).ok().flatten()
+    }
+
+    /// Like [`RpcError::application_error`], reporting why an application
+    /// error could not be decoded into `E`.
+    ///
+    /// Returns `Ok(None)` for any other error.
+    ///
+    /// # Errors
+    ///
+    /// - [`RpcError::SchemaMismatch`] if the error was declared with another
+    ///   type than `E`. Schema ids hash Rust type names, so this is also
+    ///   what peers built from differently named types, or by compilers
+    ///   naming types differently, get for the same payload.
+    /// - [`RpcError::Decode`] if the payload does not decode as `E`
+    pub fn try_application_error<E: Message + Default>(&self) -> Result<Option<E>, RpcError> {
+        self.try_application_error_with(&ProtobufCodec)
+    }
+
+    /// Like [`RpcError::try_application_error`], for functions declared
+    /// with another [`PayloadCodec`] than [`ProtobufCodec`].
+    pub fn try_application_error_with<E, C: PayloadCodec<E>>(
+        &self,
+        codec: &C,
+    ) -> Result<Option<E>, RpcError> {
+        match self {
+            RpcError::Application { schema_id, payload } => {
+                if *schema_id != schema::schema_id::<E>() {
+                    return Err(RpcError::SchemaMismatch);
+                }
+                codec.decode(payload.as_ref()).map(Some)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Renders the error for end users.
+    ///
+    /// Local and remote errors are rendered the same way, through the code
+    /// table, so a remote `Timeout` reads exactly like a local one.
+    /// Technical details such as remote messages or I/O errors are left
+    /// out; use `Debug` for logs.
+    pub fn user_message(&self) -> String {
+        codes::describe(self.code()).to_owned()
+    }
 }
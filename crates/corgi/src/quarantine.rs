@@ -0,0 +1,196 @@
+//! Temporary blocking of misbehaving peers.
+//!
+//! Every malformed datagram or rate limit violation counts against the peer
+//! that sent it. A peer reaching `max_violations` within `window` is
+//! quarantined for `duration`: its datagrams are dropped as soon as they are
+//! received, before any parsing, so a broken or hostile peer stops costing
+//! CPU.
+
+use std::{
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
+
+use crate::peer_table::PeerTable;
+
+/// When a peer gets quarantined, and for how long.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuarantinePolicy {
+    /// Violations within `window` that quarantine a peer.
+    pub max_violations: u32,
+    /// Period violations are counted over, starting at a peer's first one.
+    pub window: Duration,
+    /// How long a quarantined peer's datagrams are dropped.
+    pub duration: Duration,
+}
+
+#[derive(Debug)]
+struct Reputation {
+    violations: u32,
+    window_started_at: Instant,
+    quarantined_until: Option<Instant>,
+}
+
+impl Reputation {
+    fn is_stale(&self, policy: &QuarantinePolicy, now: Instant) -> bool {
+        let quarantined = self.quarantined_until.is_some_and(|until| now < until);
+        let counting = now.saturating_duration_since(self.window_started_at) < policy.window;
+        !quarantined && !counting
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct PeerReputation {
+    policy: QuarantinePolicy,
+    peers: PeerTable<Reputation>,
+}
+
+impl PeerReputation {
+    pub(crate) fn new(policy: QuarantinePolicy) -> Self {
+        Self {
+            policy,
+            peers: PeerTable::default(),
+        }
+    }
+
+    /// Whether datagrams from `peer` are to be dropped at `now`.
+    pub(crate) fn is_quarantined(&self, peer: SocketAddr, now: Instant) -> bool {
+        self.peers
+            .get(&peer)
+            .and_then(|reputation| reputation.quarantined_until)
+            .is_some_and(|until| now < until)
+    }
+
+    /// Counts a violation against `peer`. Returns `true` when it got
+    /// quarantined by this one.
+    ///
+    /// While the peer table is full and none of its peers is stale,
+    /// violations of new peers are not counted, so a flood of spoofed source
+    /// addresses can't grow the table.
+    pub(crate) fn record_violation(&mut self, peer: SocketAddr, now: Instant) -> bool {
+        let policy = self.policy;
+        let Some(reputation) = self.peers.get_or_insert_with(
+            peer,
+            now,
+            |reputation| reputation.is_stale(&policy, now),
+            || Reputation {
+                violations: 0,
+                window_started_at: now,
+                quarantined_until: None,
+            },
+        ) else {
+            return false;
+        };
+
+        if now.saturating_duration_since(reputation.window_started_at) >= policy.window {
+            reputation.violations = 0;
+            reputation.window_started_at = now;
+        }
+
+        reputation.violations += 1;
+        if reputation.violations < policy.max_violations {
+            return false;
+        }
+
+        reputation.violations = 0;
+        reputation.window_started_at = now;
+        reputation.quarantined_until = Some(now + policy.duration);
+        true
+    }
+
+    /// Periodic cleanup: forgets peers that are neither quarantined nor
+    /// within a violation window, then gives memory left over from bursts
+    /// back.
+    pub(crate) fn maintenance_tick(&mut self, now: Instant) {
+        let policy = self.policy;
+        self.peers
+            .maintenance_tick(|reputation| reputation.is_stale(&policy, now));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::peer_table::MAX_TRACKED_PEERS;
+
+    const POLICY: QuarantinePolicy = QuarantinePolicy {
+        max_violations: 3,
+        window: Duration::from_secs(10),
+        duration: Duration::from_secs(60),
+    };
+
+    fn peer(address: &str) -> SocketAddr {
+        address.parse().unwrap()
+    }
+
+    #[test]
+    fn peer_reputation_should_quarantine_after_max_violations_within_window() {
+        let mut reputation = PeerReputation::new(POLICY);
+        let noisy = peer("127.0.0.1:4000");
+        let quiet = peer("127.0.0.1:5000");
+        let now = Instant::now();
+
+        let quarantined: Vec<_> = (0..3)
+            .map(|_| reputation.record_violation(noisy, now))
+            .collect();
+
+        assert_eq!(quarantined, [false, false, true]);
+        assert!(reputation.is_quarantined(noisy, now));
+        assert!(!reputation.is_quarantined(quiet, now));
+        assert!(!reputation.is_quarantined(noisy, now + POLICY.duration));
+    }
+
+    #[test]
+    fn peer_reputation_should_forget_violations_outside_of_window() {
+        let mut reputation = PeerReputation::new(POLICY);
+        let noisy = peer("127.0.0.1:4000");
+        let now = Instant::now();
+        reputation.record_violation(noisy, now);
+        reputation.record_violation(noisy, now);
+
+        let quarantined = reputation.record_violation(noisy, now + POLICY.window);
+
+        assert!(!quarantined);
+        assert!(!reputation.is_quarantined(noisy, now + POLICY.window));
+    }
+
+    #[test]
+    fn peer_reputation_should_not_track_new_peers_while_table_is_full_of_active_ones() {
+        let mut reputation = PeerReputation::new(POLICY);
+        let now = Instant::now();
+        for port in 0..MAX_TRACKED_PEERS as u16 {
+            reputation.record_violation(SocketAddr::from(([127, 0, 0, 1], port)), now);
+        }
+
+        let quarantined = (0..100_u16)
+            .filter(|port| {
+                let newcomer = SocketAddr::from(([127, 0, 0, 2], *port));
+                (0..3).any(|_| reputation.record_violation(newcomer, now))
+            })
+            .count();
+        let tracked = (0..2)
+            .map(|_| reputation.record_violation(SocketAddr::from(([127, 0, 0, 1], 0)), now))
+            .collect::<Vec<_>>();
+
+        assert_eq!(quarantined, 0);
+        assert!(reputation.peers.len() <= MAX_TRACKED_PEERS);
+        assert_eq!(tracked, [false, true]);
+    }
+
+    #[test]
+    fn peer_reputation_should_forget_stale_peers_on_maintenance_tick() {
+        let mut reputation = PeerReputation::new(POLICY);
+        let now = Instant::now();
+        reputation.record_violation(peer("127.0.0.1:4000"), now);
+        for _ in 0..3 {
+            reputation.record_violation(peer("127.0.0.1:5000"), now);
+        }
+
+        reputation.maintenance_tick(now + POLICY.window);
+        let after_first_tick = reputation.peers.len();
+        reputation.maintenance_tick(now + POLICY.duration);
+
+        assert_eq!(after_first_tick, 1);
+        assert!(reputation.peers.is_empty());
+    }
+}
@@ -0,0 +1,220 @@
+//! Per-peer bandwidth limiting.
+//!
+//! Each peer gets a token bucket measured in bytes: it holds up to `burst`
+//! bytes and refills at `bytes_per_second`. Datagrams that don't fit in the
+//! bucket are rejected, so a few large calls from one peer can't saturate
+//! the link for everybody else.
+
+use std::{
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
+
+use crate::{peer_table::PeerTable, protocol::types::RpcError};
+
+/// Bandwidth budget applied to every peer independently.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ByteRateLimit {
+    /// Sustained rate in bytes per second.
+    pub bytes_per_second: u64,
+    /// Largest number of bytes a peer may send at once after being idle.
+    pub burst: u64,
+}
+
+impl ByteRateLimit {
+    /// Bytes regained after being idle for `elapsed`.
+    fn refill(&self, elapsed: Duration) -> f64 {
+        elapsed.as_secs_f64() * self.bytes_per_second as f64
+    }
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl TokenBucket {
+    /// Whether the bucket would be full by `now`: forgetting it is
+    /// indistinguishable from keeping it.
+    fn is_idle(&self, limit: &ByteRateLimit, now: Instant) -> bool {
+        let elapsed = now.saturating_duration_since(self.updated_at);
+        self.tokens + limit.refill(elapsed) >= limit.burst as f64
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct PeerRateLimiter {
+    limit: ByteRateLimit,
+    buckets: PeerTable<TokenBucket>,
+}
+
+impl PeerRateLimiter {
+    pub(crate) fn new(limit: ByteRateLimit) -> Self {
+        Self {
+            limit,
+            buckets: PeerTable::default(),
+        }
+    }
+
+    /// Takes `bytes` from `peer`'s budget, or rejects the datagram with
+    /// [`RpcError::RateLimited`] when the budget is exhausted.
+    ///
+    /// A new peer is rejected the same way while the peer table is full and
+    /// none of its buckets is idle, so a flood of spoofed source addresses
+    /// can't grow the table.
+    pub(crate) fn check(
+        &mut self,
+        peer: SocketAddr,
+        bytes: usize,
+        now: Instant,
+    ) -> Result<(), RpcError> {
+        let limit = self.limit;
+        let burst = limit.burst as f64;
+        let Some(bucket) = self.buckets.get_or_insert_with(
+            peer,
+            now,
+            |bucket| bucket.is_idle(&limit, now),
+            || TokenBucket {
+                tokens: burst,
+                updated_at: now,
+            },
+        ) else {
+            return Err(RpcError::RateLimited);
+        };
+
+        let elapsed = now.saturating_duration_since(bucket.updated_at);
+        bucket.tokens = (bucket.tokens + limit.refill(elapsed)).min(burst);
+        bucket.updated_at = now;
+
+        let bytes = bytes as f64;
+        if bucket.tokens < bytes {
+            return Err(RpcError::RateLimited);
+        }
+
+        bucket.tokens -= bytes;
+        Ok(())
+    }
+
+    /// Periodic cleanup: forgets peers whose budget has fully refilled, so
+    /// they would be treated the same when seen next, then gives memory
+    /// left over from bursts back.
+    pub(crate) fn maintenance_tick(&mut self, now: Instant) {
+        let limit = self.limit;
+        self.buckets
+            .maintenance_tick(|bucket| bucket.is_idle(&limit, now));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::peer_table::MAX_TRACKED_PEERS;
+
+    const LIMIT: ByteRateLimit = ByteRateLimit {
+        bytes_per_second: 1000,
+        burst: 3000,
+    };
+
+    fn peer(address: &str) -> SocketAddr {
+        address.parse().unwrap()
+    }
+
+    #[test]
+    fn peer_rate_limiter_should_throttle_burst_while_other_peer_is_unaffected() {
+        let mut limiter = PeerRateLimiter::new(LIMIT);
+        let noisy = peer("127.0.0.1:4000");
+        let quiet = peer("127.0.0.1:5000");
+        let now = Instant::now();
+
+        let accepted = (0..10)
+            .filter(|_| limiter.check(noisy, 1200, now).is_ok())
+            .count();
+
+        assert_eq!(accepted, 2);
+        assert!(matches!(
+            limiter.check(noisy, 1200, now),
+            Err(RpcError::RateLimited)
+        ));
+        assert!(limiter.check(quiet, 1200, now).is_ok());
+    }
+
+    #[test]
+    fn peer_rate_limiter_should_refill_budget_over_time() {
+        let mut limiter = PeerRateLimiter::new(LIMIT);
+        let noisy = peer("127.0.0.1:4000");
+        let now = Instant::now();
+        limiter.check(noisy, 3000, now).unwrap();
+
+        assert!(limiter.check(noisy, 1000, now).is_err());
+        assert!(
+            limiter
+                .check(noisy, 1000, now + Duration::from_secs(1))
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn peer_rate_limiter_should_evict_idle_peers_when_tracking_too_many() {
+        let mut limiter = PeerRateLimiter::new(LIMIT);
+        let now = Instant::now();
+        for port in 0..MAX_TRACKED_PEERS as u16 {
+            limiter
+                .check(SocketAddr::from(([127, 0, 0, 1], port)), 3000, now)
+                .unwrap();
+        }
+
+        limiter
+            .check(peer("127.0.0.2:4000"), 1, now + Duration::from_secs(3))
+            .unwrap();
+
+        assert_eq!(limiter.buckets.len(), 1);
+    }
+
+    #[test]
+    fn peer_rate_limiter_should_reject_new_peers_while_table_is_full_of_active_ones() {
+        let mut limiter = PeerRateLimiter::new(LIMIT);
+        let now = Instant::now();
+        for port in 0..MAX_TRACKED_PEERS as u16 {
+            limiter
+                .check(SocketAddr::from(([127, 0, 0, 1], port)), 3000, now)
+                .unwrap();
+        }
+
+        let rejected = (0..100_u16)
+            .filter(|port| {
+                let newcomer = SocketAddr::from(([127, 0, 0, 2], *port));
+                matches!(
+                    limiter.check(newcomer, 1, now + Duration::from_millis(500)),
+                    Err(RpcError::RateLimited)
+                )
+            })
+            .count();
+        let tracked = limiter.check(
+            SocketAddr::from(([127, 0, 0, 1], 0)),
+            1000,
+            now + Duration::from_secs(1),
+        );
+
+        assert_eq!(rejected, 100);
+        assert!(limiter.buckets.len() <= MAX_TRACKED_PEERS);
+        assert!(tracked.is_ok());
+    }
+
+    #[test]
+    fn peer_rate_limiter_should_forget_refilled_peers_on_maintenance_tick() {
+        let mut limiter = PeerRateLimiter::new(LIMIT);
+        let now = Instant::now();
+        limiter.check(peer("127.0.0.1:4000"), 3000, now).unwrap();
+        limiter
+            .check(peer("127.0.0.1:5000"), 3000, now + Duration::from_secs(2))
+            .unwrap();
+
+        limiter.maintenance_tick(now + Duration::from_secs(3));
+        let after_first_tick = limiter.buckets.len();
+        limiter.maintenance_tick(now + Duration::from_secs(5));
+
+        assert_eq!(after_first_tick, 1);
+        assert!(limiter.buckets.is_empty());
+    }
+}
@@ -0,0 +1,210 @@
+//! Responses of recently completed calls.
+//!
+//! A client that doesn't hear back retransmits its call with the same call
+//! id. Answering the retransmit from this cache instead of running the
+//! handler again makes calls idempotent for as long as their response is
+//! cached.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
+
+use bytes::Bytes;
+
+use crate::protocol::types::{CallId, MessageKind};
+
+type CallKey = (SocketAddr, CallId);
+
+#[derive(Debug)]
+struct CachedResponse {
+    kind: MessageKind,
+    response: Bytes,
+    completed_at: Instant,
+}
+
+#[derive(Debug)]
+pub(crate) struct ResponseCache {
+    capacity: usize,
+    ttl: Duration,
+    responses: HashMap<CallKey, CachedResponse>,
+    /// Keys in completion order, oldest first. May hold keys that have been
+    /// completed again since, which are skipped on eviction.
+    completions: VecDeque<(CallKey, Instant)>,
+}
+
+impl ResponseCache {
+    pub(crate) fn new(capacity: usize, ttl: Duration) -> Self {
+        Self {
+            capacity,
+            ttl,
+            responses: HashMap::new(),
+            completions: VecDeque::new(),
+        }
+    }
+
+    /// Returns the response of `call_id` from `peer`, and whether it
+    /// reported a failure, if it completed less than the TTL ago.
+    pub(crate) fn get(
+        &self,
+        peer: SocketAddr,
+        call_id: CallId,
+        now: Instant,
+    ) -> Option<(MessageKind, Bytes)> {
+        self.responses
+            .get(&(peer, call_id))
+            .filter(|cached| now.saturating_duration_since(cached.completed_at) < self.ttl)
+            .map(|cached| (cached.kind, cached.response.clone()))
+    }
+
+    /// Remembers the response of a completed call, evicting expired entries
+    /// and, when full, the oldest one.
+    pub(crate) fn insert(
+        &mut self,
+        peer: SocketAddr,
+        call_id: CallId,
+        kind: MessageKind,
+        response: Bytes,
+        now: Instant,
+    ) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        self.evict(now, self.capacity - 1);
+
+        let key = (peer, call_id);
+        self.responses.insert(
+            key,
+            CachedResponse {
+                kind,
+                response,
+                completed_at: now,
+            },
+        );
+        self.completions.push_back((key, now));
+    }
+
+    /// Periodic cleanup: evicts expired responses even while no call
+    /// completes, then gives memory left over from bursts back.
+    pub(crate) fn maintenance_tick(&mut self, now: Instant) {
+        self.evict(now, self.capacity);
+        self.responses.shrink_to(self.responses.len() * 2);
+        self.completions.shrink_to(self.completions.len() * 2);
+    }
+
+    /// Evicts expired responses, and the oldest ones while more than `keep`
+    /// remain.
+    fn evict(&mut self, now: Instant, keep: usize) {
+        while let Some(&(key, completed_at)) = self.completions.front() {
+            let expired = now.saturating_duration_since(completed_at) >= self.ttl;
+            if !expired && self.responses.len() <= keep {
+                break;
+            }
+            self.completions.pop_front();
+            if self
+                .responses
+                .get(&key)
+                .is_some_and(|cached| cached.completed_at == completed_at)
+            {
+                self.responses.remove(&key);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TTL: Duration = Duration::from_secs(10);
+
+    fn peer(address: &str) -> SocketAddr {
+        address.parse().unwrap()
+    }
+
+    #[test]
+    fn response_cache_should_return_response_until_ttl_elapses() {
+        let mut cache = ResponseCache::new(16, TTL);
+        let now = Instant::now();
+        cache.insert(
+            peer("127.0.0.1:4000"),
+            7,
+            MessageKind::Response,
+            Bytes::from_static(b"ok"),
+            now,
+        );
+
+        let cached = cache.get(peer("127.0.0.1:4000"), 7, now + TTL / 2);
+        let other_peer = cache.get(peer("127.0.0.1:5000"), 7, now);
+        let expired = cache.get(peer("127.0.0.1:4000"), 7, now + TTL);
+
+        assert_eq!(
+            cached,
+            Some((MessageKind::Response, Bytes::from_static(b"ok")))
+        );
+        assert!(other_peer.is_none());
+        assert!(expired.is_none());
+    }
+
+    #[test]
+    fn response_cache_should_evict_oldest_response_when_full() {
+        let mut cache = ResponseCache::new(2, TTL);
+        let now = Instant::now();
+        for call_id in 1..=3 {
+            cache.insert(
+                peer("127.0.0.1:4000"),
+                call_id,
+                MessageKind::Response,
+                Bytes::new(),
+                now,
+            );
+        }
+
+        assert!(cache.get(peer("127.0.0.1:4000"), 1, now).is_none());
+        assert!(cache.get(peer("127.0.0.1:4000"), 2, now).is_some());
+        assert!(cache.get(peer("127.0.0.1:4000"), 3, now).is_some());
+        assert_eq!(cache.responses.len(), 2);
+    }
+
+    #[test]
+    fn response_cache_should_keep_nothing_when_capacity_is_zero() {
+        let mut cache = ResponseCache::new(0, TTL);
+        let now = Instant::now();
+
+        cache.insert(
+            peer("127.0.0.1:4000"),
+            7,
+            MessageKind::Response,
+            Bytes::new(),
+            now,
+        );
+
+        assert!(cache.get(peer("127.0.0.1:4000"), 7, now).is_none());
+    }
+
+    #[test]
+    fn response_cache_should_evict_expired_responses_on_maintenance_tick() {
+        let mut cache = ResponseCache::new(16, TTL);
+        let now = Instant::now();
+        for call_id in 0..3 {
+            let completed_at = now + TTL / 2 * call_id as u32;
+            cache.insert(
+                peer("127.0.0.1:4000"),
+                call_id,
+                MessageKind::Response,
+                Bytes::new(),
+                completed_at,
+            );
+        }
+
+        cache.maintenance_tick(now + TTL);
+        let after_first_tick = cache.responses.len();
+        cache.maintenance_tick(now + TTL * 2);
+
+        assert_eq!(after_first_tick, 2);
+        assert!(cache.responses.is_empty());
+        assert!(cache.completions.is_empty());
+    }
+}
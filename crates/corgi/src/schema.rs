@@ -0,0 +1,38 @@
+//! Stable schema identifiers for RPC argument and return types.
+//!
+//! A schema id lets both peers cheaply check that they agree on the type
+//! carried by an argument before attempting to decode it.
+
+/// FNV_OFFSET_BASIS indicates 64-bit FNV-1a offset basis
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+
+/// FNV_PRIME indicates 64-bit FNV-1a prime
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Computes a deterministic schema id for `T`.
+///
+/// The id is the 64-bit FNV-1a hash of the fully-qualified type name, so it is
+/// stable across invocations and processes built by the same compiler. Unlike
+/// [`std::hash::DefaultHasher`], FNV-1a is a fixed algorithm that does
+/// not change between Rust releases.
+///
+/// The type name itself is not guaranteed to be: [`std::any::type_name`]
+/// may render the same type differently across compiler versions, and it
+/// includes the module path, so moving or renaming a type changes its id.
+/// Peers must therefore share the type definition and be built by the same
+/// compiler for their ids to agree. A disagreement is reported as
+/// [`RpcError::SchemaMismatch`](crate::protocol::types::RpcError::SchemaMismatch)
+/// by [`RpcError::try_application_error`](crate::protocol::types::RpcError::try_application_error).
+///
+/// # Example
+/// ```rust
+/// assert_eq!(corgi::schema_id::<String>(), corgi::schema_id::<String>());
+/// assert_ne!(corgi::schema_id::<String>(), corgi::schema_id::<i32>());
+/// ```
+pub fn schema_id<T: ?Sized>() -> u64 {
+    std::any::type_name::<T>()
+        .bytes()
+        .fold(FNV_OFFSET_BASIS, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+        })
+}
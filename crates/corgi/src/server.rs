@@ -1,17 +1,32 @@
 use core::fmt;
-use std::net::SocketAddr;
+use std::{
+    fmt::Write,
+    net::{Ipv4Addr, SocketAddr},
+    sync::atomic::{AtomicBool, AtomicU64, Ordering},
+    time::{Duration, Instant},
+};
 
-use bytes::BytesMut;
+use bytes::{Bytes, BytesMut};
+use futures::{StreamExt, future, stream::FuturesUnordered};
+use socket2::Socket;
 use tokio::net::UdpSocket;
-
-const UDP_CHUNK_SIZE: usize = 1200;
+use tower_service::Service;
+use tracing::Instrument;
 
 use crate::{
-    Container,
+    Container, RpcService,
+    builtins::{self, Builtins},
+    metrics::{ServerCounters, ServerMetrics},
+    outbox::{Busy, Outbox},
     protocol::{
-        parser::Parser,
-        types::{RpcCall, RpcError},
+        codec::{Endianness, FailureCodec, PackageChunkCodec, UDP_CHUNK_SIZE},
+        parser::{MAX_PARTIAL_MESSAGES, Parser},
+        types::{CallId, MessageKind, RpcCall, RpcError},
     },
+    quarantine::{PeerReputation, QuarantinePolicy},
+    rate_limit::{ByteRateLimit, PeerRateLimiter},
+    response_cache::ResponseCache,
+    service,
 };
 
 #[derive(Debug)]
@@ -41,9 +56,150 @@ impl fmt::Display for RpcCallContext {
     }
 }
 
+/// A call that was answered, successfully or with a failure.
+struct CompletedCall {
+    peer_address: SocketAddr,
+    call_id: CallId,
+    kind: MessageKind,
+    response: Bytes,
+}
+
+/// A call that [`RpcServer::debug_inject`] ran, with its handler's result.
+#[derive(Debug)]
+pub struct InjectedCall {
+    pub call: RpcCall,
+    pub result: Result<Bytes, RpcError>,
+}
+
+/// How an [`RpcServer`] picks the call id of the response chunks it sends.
+///
+/// Either way, every response carries the id of the call it answers in its
+/// `correlation_id` header field, which is what clients match on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ResponseIds {
+    /// The response reuses the call id of the request. Needs no state, but a
+    /// request and its response share one id, which is ambiguous to a peer
+    /// that both issues and serves calls on the same socket and reassembles
+    /// both by id.
+    #[default]
+    ReuseCallId,
+    /// Every response gets a fresh id from a counter owned by the server, so
+    /// response ids never depend on the ids clients choose. Costs an atomic
+    /// increment per response, and a retransmitted call is answered under a
+    /// new id each time.
+    Allocated,
+}
+
+/// What an [`RpcServer`] does with chunks of a [`MessageKind`] it doesn't
+/// know, as sent by peers speaking a newer protocol revision.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UnknownMessageKinds {
+    /// The chunk is logged and dropped. The peer is not held responsible,
+    /// so newer peers can probe for features without risking quarantine.
+    #[default]
+    Ignore,
+    /// The chunk is rejected with [`RpcError::UnsupportedMessageKind`] like
+    /// any malformed datagram: dropped and counted as a violation of the
+    /// peer towards its quarantine.
+    Reject,
+}
+
+/// Tunables of an [`RpcServer`].
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    /// Per-peer bandwidth budget. Datagrams from a peer exceeding it are
+    /// dropped. Disabled by default.
+    pub peer_rate_limit: Option<ByteRateLimit>,
+    /// Quarantines peers that keep sending malformed datagrams or exceeding
+    /// their rate limit, dropping everything they send for a while. Disabled
+    /// by default.
+    pub quarantine: Option<QuarantinePolicy>,
+    /// Number of completed calls whose responses are kept to answer
+    /// retransmits. `0` disables deduplication. Defaults to 1024.
+    pub dedup_cache_capacity: usize,
+    /// How long a completed call's response is kept. A retransmit of the same
+    /// call id from the same peer within this window is answered from the
+    /// cache without running the handler again; later ones execute again.
+    /// Calls are only deduplicated once they completed, so a retransmit
+    /// arriving while the handler still runs executes it a second time.
+    /// Defaults to 30 seconds.
+    pub dedup_cache_ttl: Duration,
+    /// How long an incomplete message waits for its remaining chunks before
+    /// it is dropped by the next maintenance tick. Defaults to 10 seconds.
+    pub reassembly_timeout: Duration,
+    /// How often the server runs its periodic cleanup: dropping timed out
+    /// reassemblies, expired cached responses and rate limit state of idle
+    /// peers, and shrinking the maps holding them after bursts. Defaults to
+    /// 1 second.
+    pub maintenance_interval: Duration,
+    /// Number of incomplete messages buffered at once. Chunks starting a new
+    /// message beyond it are dropped. Defaults to 1024.
+    pub max_partial_messages: usize,
+    /// How response chunks are identified. Defaults to
+    /// [`ResponseIds::ReuseCallId`].
+    pub response_ids: ResponseIds,
+    /// Byte order of chunk headers, which clients must be configured for
+    /// too. Chunks in the other order are dropped. Defaults to
+    /// [`Endianness::Little`].
+    pub endianness: Endianness,
+    /// How chunks of unknown message kinds are treated. Defaults to
+    /// [`UnknownMessageKinds::Ignore`].
+    pub unknown_message_kinds: UnknownMessageKinds,
+    /// Whether the server answers the functions in
+    /// [`builtins`](crate::builtins), such as
+    /// [`builtins::REFLECT`](crate::builtins::REFLECT). They expose server
+    /// internals, so they are disabled by default, and calls to them fail
+    /// with [`RpcError::FeatureDisabled`].
+    pub builtin_functions: bool,
+    /// How long a multi-chunk response waits for others to share its batch
+    /// of sends. The chunks of one response always go out in one batch,
+    /// which on Linux is a single `sendmmsg`, and single-chunk responses
+    /// never wait. Defaults to zero, so nothing waits.
+    pub coalesce_window: Duration,
+    /// Whether request chunks of version 0 peers, which predate the chunk
+    /// magic and version, are accepted, see
+    /// [`PackageChunkCodec::with_compat_v0`]. Their responses are sent in
+    /// the current version. Defaults to false.
+    pub compat_v0: bool,
+    /// Whether response chunks carry a payload checksum. Request chunks are
+    /// validated whenever their sender included one. Defaults to true.
+    pub checksums: bool,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            peer_rate_limit: None,
+            quarantine: None,
+            dedup_cache_capacity: 1024,
+            dedup_cache_ttl: Duration::from_secs(30),
+            reassembly_timeout: Duration::from_secs(10),
+            maintenance_interval: Duration::from_secs(1),
+            max_partial_messages: MAX_PARTIAL_MESSAGES,
+            response_ids: ResponseIds::default(),
+            endianness: Endianness::default(),
+            unknown_message_kinds: UnknownMessageKinds::default(),
+            builtin_functions: false,
+            coalesce_window: Duration::ZERO,
+            compat_v0: false,
+            checksums: true,
+        }
+    }
+}
+
 pub struct RpcServer<'a, T> {
     container: &'a Container,
     connection: T,
+    local_address: SocketAddr,
+    config: ServerConfig,
+    /// Last id handed out under [`ResponseIds::Allocated`].
+    response_id: AtomicU64,
+    counters: ServerCounters,
+    /// Set by [`RpcServer::enter_lame_duck`].
+    lame_duck: AtomicBool,
+    outbox: Outbox,
+    chunk_codec: PackageChunkCodec,
+    error_codec: FailureCodec,
 }
 
 impl<'a> RpcServer<'a, UdpSocket> {
@@ -55,34 +211,275 @@ impl<'a> RpcServer<'a, UdpSocket> {
         let socket = UdpSocket::bind(address)
             .await
             .map_err(RpcError::SocketBinding)?;
-        let instance = Self {
+        let instance = Self::from_socket(container, socket)?;
+        tracing::debug!(
+            "Successfully established UDP socket binding on address {}.",
+            instance.local_address
+        );
+        Ok(instance)
+    }
+
+    /// Creates a server on a caller-configured socket.
+    ///
+    /// Use this when the socket needs options [`UdpSocket::bind`] does not
+    /// expose, such as buffer sizes or TOS/DSCP marking for
+    /// latency-sensitive traffic. The socket must already be bound; it is
+    /// switched to non-blocking mode before being handed to tokio.
+    pub fn create_udp_with(container: &'a Container, socket: Socket) -> Result<Self, RpcError> {
+        tracing::trace!("Creating RpcServer from a pre-configured UDP socket");
+        socket
+            .set_nonblocking(true)
+            .map_err(RpcError::SocketBinding)?;
+        let socket = UdpSocket::from_std(socket.into()).map_err(RpcError::SocketBinding)?;
+        let instance = Self::from_socket(container, socket)?;
+        tracing::debug!(
+            "Successfully created RpcServer from a pre-configured UDP socket on address {}.",
+            instance.local_address
+        );
+        Ok(instance)
+    }
+
+    fn from_socket(container: &'a Container, socket: UdpSocket) -> Result<Self, RpcError> {
+        let local_address = socket.local_addr().map_err(RpcError::LocalAddress)?;
+
+        Ok(Self {
             container,
             connection: socket,
-        };
-        tracing::debug!("Successfully established UDP socket binding on address {address}.");
-        Ok(instance)
+            local_address,
+            config: ServerConfig::default(),
+            response_id: AtomicU64::new(0),
+            counters: ServerCounters::new(container),
+            lame_duck: AtomicBool::new(false),
+            outbox: Outbox::new(Duration::ZERO),
+            chunk_codec: PackageChunkCodec::default(),
+            error_codec: FailureCodec,
+        })
+    }
+}
+
+impl<'a> RpcServer<'a, ()> {
+    /// Creates a server without a socket, for pushing datagrams through
+    /// [`RpcServer::debug_inject`]. Its local address is unspecified.
+    pub fn detached(container: &'a Container) -> Self {
+        Self {
+            container,
+            connection: (),
+            local_address: SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0)),
+            config: ServerConfig::default(),
+            response_id: AtomicU64::new(0),
+            counters: ServerCounters::new(container),
+            lame_duck: AtomicBool::new(false),
+            outbox: Outbox::new(Duration::ZERO),
+            chunk_codec: PackageChunkCodec::default(),
+            error_codec: FailureCodec,
+        }
     }
+}
 
-    pub fn local_address(&self) -> Result<SocketAddr, RpcError> {
-        let address = self
-            .connection
-            .local_addr()
-            .map_err(RpcError::LocalAddress)?;
+impl<T> RpcServer<'_, T> {
+    pub fn with_config(mut self, config: ServerConfig) -> Self {
+        self.chunk_codec =
+            PackageChunkCodec::new(config.endianness).with_compat_v0(config.compat_v0);
+        self.outbox = Outbox::new(config.coalesce_window);
+        self.config = config;
+        self
+    }
+
+    /// Returns a [`Service`] dispatching calls to this server's container,
+    /// for wrapping in `tower` layers and passing to [`RpcServer::serve`].
+    pub fn service(&self) -> RpcService<'_> {
+        RpcService::new(self.container).with_builtins(self.builtins())
+    }
+
+    /// Returns a snapshot of the server's counters.
+    pub fn metrics(&self) -> ServerMetrics {
+        self.counters.snapshot()
+    }
+
+    /// Renders [`RpcServer::metrics`] in Prometheus text exposition format,
+    /// for serving from a scrape endpoint.
+    pub fn metrics_prometheus(&self) -> String {
+        self.metrics().to_prometheus()
+    }
 
-        Ok(address)
+    /// Stops taking new calls ahead of a shutdown, such as during a rolling
+    /// deploy.
+    ///
+    /// From then on the server keeps receiving, but answers every new call
+    /// with [`RpcError::ServerShuttingDown`] instead of running it, so
+    /// clients can move on to another instance. Calls already executing run
+    /// to completion and are answered as usual, and retransmits of answered
+    /// calls still get their cached response. There is no way back; shut the
+    /// server down with [`RpcServer::start_with_shutdown`] once the calls in
+    /// flight drained.
+    pub fn enter_lame_duck(&self) {
+        tracing::info!(
+            "RPC server on {} entering lame-duck mode",
+            self.local_address
+        );
+        self.lame_duck.store(true, Ordering::Relaxed);
     }
 
+    /// Waits until every call received so far has been answered and the
+    /// chunks of its response were handed to the socket, including those
+    /// held back by [`ServerConfig::coalesce_window`].
+    ///
+    /// Meant for tests and clean shutdowns, so they don't race the send
+    /// path. Calls keep being received while it waits and are waited for
+    /// too; enter [lame-duck mode](RpcServer::enter_lame_duck) first for it
+    /// to return under load.
+    pub async fn flush(&self) {
+        self.outbox.idle().await;
+    }
+
+    fn builtins(&self) -> Builtins<'_> {
+        Builtins {
+            container: self.container,
+            config: &self.config,
+            counters: &self.counters,
+        }
+    }
+
+    /// Returns the address the socket is bound to.
+    ///
+    /// The address is resolved once right after binding, so when binding to
+    /// port 0 this is the concrete port the OS assigned.
+    pub fn local_address(&self) -> SocketAddr {
+        self.local_address
+    }
+
+    /// Pushes a single datagram from `peer` through parsing, validation and
+    /// the handler, and returns what happened, without touching any socket.
+    ///
+    /// Meant for reproducing a captured bad packet in a test. Nothing is
+    /// sent back, and the rate limiter and response cache are bypassed.
+    /// Returns `Ok(None)` when the datagram is one chunk of a larger message,
+    /// and the parser's error when it is rejected.
+    pub async fn debug_inject(
+        &self,
+        peer: SocketAddr,
+        datagram: &[u8],
+    ) -> Result<Option<InjectedCall>, RpcError> {
+        let mut parser =
+            Parser::new(self.config.max_partial_messages).with_chunk_codec(self.chunk_codec);
+        let Some(call) = parser.apply(peer, datagram)? else {
+            return Ok(None);
+        };
+
+        let result = match builtins::call(Some(self.builtins()), call.envelope()) {
+            Some(result) => result,
+            None => service::execute(self.container, call.envelope()).await,
+        };
+        Ok(Some(InjectedCall { call, result }))
+    }
+}
+
+impl RpcServer<'_, UdpSocket> {
+    /// Receives calls, dispatches them to the container and sends each
+    /// handler's return value back to the caller.
+    ///
+    /// Calls are executed concurrently with receiving, so a slow handler does
+    /// not hold up the chunks of other calls. Calls that fail validation or
+    /// whose handler returns an error are answered with a
+    /// [`MessageKind::Failure`] response, so the caller learns why.
+    /// Datagrams that don't form a call are logged and dropped.
+    ///
+    /// Runs until the task is dropped; see [`RpcServer::start_with_shutdown`]
+    /// to stop it cleanly.
     pub async fn start(&self) -> Result<(), RpcError> {
+        self.serve(self.service()).await
+    }
+
+    /// Like [`RpcServer::start`], returning `Ok(())` once `shutdown`
+    /// completes.
+    ///
+    /// From then on no datagram is received anymore, but calls already
+    /// executing run to completion and are answered before it returns. Pass
+    /// `tokio::signal::ctrl_c()` or a cancellation token's `cancelled()` to
+    /// stop on a signal.
+    pub async fn start_with_shutdown(
+        &self,
+        shutdown: impl Future<Output = ()>,
+    ) -> Result<(), RpcError> {
+        self.serve_with_shutdown(self.service(), shutdown).await
+    }
+
+    /// Like [`RpcServer::start`], running every call through `service`
+    /// instead of straight through the container.
+    ///
+    /// Build `service` by wrapping [`RpcServer::service`] in `tower` layers.
+    /// Each call is dispatched on its own clone of `service` once it reports
+    /// ready; an error from `poll_ready` fails the call like a handler error.
+    pub async fn serve<'s, S>(&'s self, service: S) -> Result<(), RpcError>
+    where
+        S: Service<RpcCall, Response = Bytes, Error = RpcError> + Clone + 's,
+    {
+        self.serve_with_shutdown(service, future::pending()).await
+    }
+
+    /// Combines [`RpcServer::serve`] and [`RpcServer::start_with_shutdown`]:
+    /// runs every call through `service` until `shutdown` completes.
+    pub async fn serve_with_shutdown<'s, S>(
+        &'s self,
+        service: S,
+        shutdown: impl Future<Output = ()>,
+    ) -> Result<(), RpcError>
+    where
+        S: Service<RpcCall, Response = Bytes, Error = RpcError> + Clone + 's,
+    {
         let mut buf = BytesMut::with_capacity(UDP_CHUNK_SIZE);
-        let mut parser = Parser::default();
-        let local_address = self.local_address()?;
+        let mut parser =
+            Parser::new(self.config.max_partial_messages).with_chunk_codec(self.chunk_codec);
+        // `interval` panics on a zero period.
+        let mut maintenance = tokio::time::interval(
+            self.config
+                .maintenance_interval
+                .max(Duration::from_millis(1)),
+        );
+        let mut rate_limiter = self.config.peer_rate_limit.map(PeerRateLimiter::new);
+        let mut reputation = self.config.quarantine.map(PeerReputation::new);
+        let mut responses = ResponseCache::new(
+            self.config.dedup_cache_capacity,
+            self.config.dedup_cache_ttl,
+        );
+        let mut in_flight = FuturesUnordered::new();
+        let local_address = self.local_address;
+        let mut shutdown = std::pin::pin!(shutdown);
 
         loop {
             tracing::trace!("Waiting for accepting RPC call for address {local_address}");
 
             buf.clear();
             buf.resize(UDP_CHUNK_SIZE, 0);
-            let (len, peer_address) = match self.connection.recv_from(&mut buf).await {
+            let received = tokio::select! {
+                () = &mut shutdown => break,
+                received = self.connection.recv_from(&mut buf) => received,
+                Some(completed) = in_flight.next(), if !in_flight.is_empty() => {
+                    if let Some(CompletedCall { peer_address, call_id, kind, response }) = completed {
+                        responses.insert(peer_address, call_id, kind, response, Instant::now());
+                    }
+                    continue;
+                }
+                _ = maintenance.tick() => {
+                    let now = Instant::now();
+                    let timed_out = parser.maintenance_tick(now, self.config.reassembly_timeout);
+                    self.counters
+                        .reassemblies_timed_out
+                        .fetch_add(timed_out as u64, Ordering::Relaxed);
+                    self.counters
+                        .partial_messages
+                        .store(parser.partial_messages() as u64, Ordering::Relaxed);
+                    responses.maintenance_tick(now);
+                    if let Some(rate_limiter) = rate_limiter.as_mut() {
+                        rate_limiter.maintenance_tick(now);
+                    }
+                    if let Some(reputation) = reputation.as_mut() {
+                        reputation.maintenance_tick(now);
+                    }
+                    continue;
+                }
+            };
+            let (len, peer_address) = match received {
                 Ok(data) => data,
                 Err(error) => {
                     tracing::error!("Failed to receive from socket connection. Error: {error}");
@@ -90,11 +487,241 @@ impl<'a> RpcServer<'a, UdpSocket> {
                 }
             };
             buf.truncate(len);
+            self.counters
+                .datagrams_received
+                .fetch_add(1, Ordering::Relaxed);
+
+            if let Some(reputation) = reputation.as_ref()
+                && reputation.is_quarantined(peer_address, Instant::now())
+            {
+                self.counters
+                    .quarantined_datagrams
+                    .fetch_add(1, Ordering::Relaxed);
+                continue;
+            }
+
+            if let Some(rate_limiter) = rate_limiter.as_mut()
+                && let Err(error) = rate_limiter.check(peer_address, len, Instant::now())
+            {
+                tracing::debug!("Dropping datagram from {peer_address}. Error: {error:?}");
+                self.record_violation(reputation.as_mut(), peer_address);
+                continue;
+            }
+
+            let applied = parser.apply(peer_address, &buf);
+            self.counters
+                .partial_messages
+                .store(parser.partial_messages() as u64, Ordering::Relaxed);
+            match applied {
+                Ok(Some(call)) => {
+                    let cached = responses.get(peer_address, call.call_id(), Instant::now());
+                    let context = RpcCallContext::new(local_address, peer_address, call);
+                    tracing::trace!("Received RpcCallContext {context}");
+                    let owed = self.outbox.busy();
+                    in_flight.push(self.dispatch(service.clone(), context, cached, owed));
+                }
+                Ok(None) => {}
+                // A full reassembly table is not the fault of the peer whose
+                // chunk happened to overflow it.
+                Err(error @ RpcError::TooManyPartialMessages) => {
+                    tracing::debug!("Dropping datagram from {peer_address}. Error: {error:?}");
+                }
+                Err(error @ RpcError::UnsupportedMessageKind)
+                    if self.config.unknown_message_kinds == UnknownMessageKinds::Ignore =>
+                {
+                    tracing::debug!("Ignoring datagram from {peer_address}. Error: {error:?}");
+                }
+                Err(error) => {
+                    tracing::debug!("Dropping datagram from {peer_address}. Error: {error:?}");
+                    self.record_violation(reputation.as_mut(), peer_address);
+                }
+            }
+        }
+
+        tracing::info!(
+            "Shutting down RPC server on {local_address}, waiting for {} calls in flight",
+            in_flight.len()
+        );
+        while in_flight.next().await.is_some() {}
+        Ok(())
+    }
+
+    /// Counts a violation against `peer_address`, quarantining it once it
+    /// reaches the configured limit.
+    fn record_violation(&self, reputation: Option<&mut PeerReputation>, peer_address: SocketAddr) {
+        let Some(reputation) = reputation else {
+            return;
+        };
+
+        if reputation.record_violation(peer_address, Instant::now()) {
+            self.counters
+                .peers_quarantined
+                .fetch_add(1, Ordering::Relaxed);
+            tracing::warn!("Quarantining misbehaving peer {peer_address}");
+        }
+    }
 
-            if let Some(call) = parser.apply(&buf)? {
-                let context = RpcCallContext::new(local_address, peer_address, call);
-                tracing::trace!("Received RpcCallContext {context}");
+    /// Runs the call and sends its response back, or resends `cached` when
+    /// the call is a retransmit of one that already completed.
+    async fn dispatch<S>(
+        &self,
+        mut service: S,
+        context: RpcCallContext,
+        cached: Option<(MessageKind, Bytes)>,
+        _owed: Busy<'_>,
+    ) -> Option<CompletedCall>
+    where
+        S: Service<RpcCall, Response = Bytes, Error = RpcError>,
+    {
+        let call_id = context.package.call_id();
+        let peer_address = context.peer_address;
+
+        if let Some((kind, response)) = cached {
+            tracing::debug!("Answering retransmitted {context} from the response cache");
+            let response_id = self.response_id(call_id);
+            if let Some(datagrams) = self.encode_response(response_id, call_id, kind, &response) {
+                self.send_datagrams(peer_address, datagrams).await;
             }
+            return None;
         }
+
+        let result = if self.lame_duck.load(Ordering::Relaxed) {
+            Err(RpcError::ServerShuttingDown)
+        } else {
+            let function = self
+                .container
+                .find(context.package.envelope().fn_name())
+                .map(|function| function.name);
+            let span = call_span(&context);
+            let result = match future::poll_fn(|cx| service.poll_ready(cx)).await {
+                Ok(()) => service.call(context.package).instrument(span).await,
+                Err(error) => Err(error),
+            };
+            if let Some(function) = function {
+                self.counters.record_call(function, result.is_err());
+            }
+            result
+        };
+        let (kind, response) = match result {
+            Ok(response) => (MessageKind::Response, response),
+            Err(error) => (
+                MessageKind::Failure,
+                self.encode_failure(peer_address, call_id, &error)?,
+            ),
+        };
+
+        let response_id = self.response_id(call_id);
+        let (kind, response, datagrams) =
+            match self
+                .chunk_codec
+                .encode_message(response_id, call_id, kind, &response)
+            {
+                Ok(datagrams) => (kind, response, datagrams),
+                // A response too large to send is answered with a failure saying
+                // so, which is also what the cache keeps for retransmits.
+                Err(error @ (RpcError::MessageTooLarge | RpcError::ResponseTooLarge)) => {
+                    let response = self.encode_failure(peer_address, call_id, &error)?;
+                    let datagrams = self.encode_response(
+                        response_id,
+                        call_id,
+                        MessageKind::Failure,
+                        &response,
+                    )?;
+                    (MessageKind::Failure, response, datagrams)
+                }
+                Err(error) => {
+                    tracing::error!(
+                        "Failed to encode response of call {call_id}. Error: {error:?}"
+                    );
+                    return None;
+                }
+            };
+
+        self.send_datagrams(peer_address, datagrams).await;
+        Some(CompletedCall {
+            peer_address,
+            call_id,
+            kind,
+            response,
+        })
     }
+
+    /// Picks the call id of the chunks answering `call_id`.
+    fn response_id(&self, call_id: CallId) -> CallId {
+        match self.config.response_ids {
+            ResponseIds::ReuseCallId => call_id,
+            ResponseIds::Allocated => self.response_id.fetch_add(1, Ordering::Relaxed) + 1,
+        }
+    }
+
+    /// Encodes the failure payload reporting `error` to the caller.
+    fn encode_failure(
+        &self,
+        peer_address: SocketAddr,
+        call_id: CallId,
+        error: &RpcError,
+    ) -> Option<Bytes> {
+        tracing::debug!("Call {call_id} from {peer_address} failed. Error: {error:?}");
+        self.error_codec
+            .encode(error)
+            .inspect_err(|error| {
+                tracing::error!(
+                    "Failed to encode error of call {call_id} from {peer_address}. Error: {error:?}"
+                );
+            })
+            .ok()
+    }
+
+    fn encode_response(
+        &self,
+        response_id: CallId,
+        call_id: CallId,
+        kind: MessageKind,
+        response: &Bytes,
+    ) -> Option<Vec<Bytes>> {
+        self.chunk_codec
+            .encode_message(response_id, call_id, kind, response)
+            .inspect_err(|error| {
+                tracing::error!("Failed to encode response of call {call_id}. Error: {error:?}");
+            })
+            .ok()
+    }
+
+    async fn send_datagrams(&self, peer_address: SocketAddr, datagrams: Vec<Bytes>) {
+        if let Err(error) = self
+            .outbox
+            .send(&self.connection, peer_address, datagrams)
+            .await
+        {
+            tracing::error!("Failed to send response to {peer_address}. Error: {error}");
+        }
+    }
+}
+
+/// The span a call is processed in, joined to the caller's trace when it
+/// sent one, see [`TraceContext`](crate::protocol::types::TraceContext).
+fn call_span(context: &RpcCallContext) -> tracing::Span {
+    let envelope = context.package.envelope();
+    let span = tracing::info_span!(
+        "rpc_call",
+        fn_name = envelope.fn_name(),
+        call_id = context.package.call_id(),
+        peer = %context.peer_address,
+        trace_id = tracing::field::Empty,
+        parent_span_id = tracing::field::Empty,
+    );
+    if let Some(trace) = envelope.trace() {
+        span.record("trace_id", hex(&trace.trace_id));
+        span.record("parent_span_id", hex(&trace.span_id));
+    }
+    span
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .fold(String::with_capacity(bytes.len() * 2), |mut out, byte| {
+            let _ = write!(out, "{byte:02x}");
+            out
+        })
 }
@@ -0,0 +1,110 @@
+//! [`tower_service::Service`] adapter over a container's dispatch.
+//!
+//! [`RpcService`] validates a call and runs its handler, which is exactly
+//! what an [`RpcServer`](crate::RpcServer) does for every call it receives.
+//! Wrapping it in `tower` layers (timeouts, load shedding, concurrency
+//! limits, ...) and handing the result to
+//! [`RpcServer::serve`](crate::RpcServer::serve) puts those layers in front
+//! of every handler.
+
+use std::{
+    panic::AssertUnwindSafe,
+    task::{Context, Poll},
+};
+
+use bytes::Bytes;
+use futures::{FutureExt, future::BoxFuture};
+use tower_service::Service;
+
+use crate::{
+    Container, RpcContext,
+    builtins::{self, Builtins},
+    protocol::types::{Envelope, RpcCall, RpcError},
+};
+
+/// Dispatches [`RpcCall`]s to the functions registered on a [`Container`].
+///
+/// Always ready; a call fails with the error [`Container::validate`] or the
+/// handler returned.
+#[derive(Clone, Copy)]
+pub struct RpcService<'a> {
+    container: &'a Container,
+    /// Set for the services of a server, which answer its built-in functions.
+    builtins: Option<Builtins<'a>>,
+}
+
+impl<'a> RpcService<'a> {
+    /// Creates a service answering calls from `container` alone: calls to
+    /// [`builtins`](crate::builtins) fail with [`RpcError::FeatureDisabled`].
+    pub fn new(container: &'a Container) -> Self {
+        Self {
+            container,
+            builtins: None,
+        }
+    }
+
+    pub(crate) fn with_builtins(mut self, builtins: Builtins<'a>) -> Self {
+        self.builtins = Some(builtins);
+        self
+    }
+}
+
+impl<'a> Service<RpcCall> for RpcService<'a> {
+    type Response = Bytes;
+    type Error = RpcError;
+    type Future = BoxFuture<'a, Result<Bytes, RpcError>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), RpcError>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, call: RpcCall) -> Self::Future {
+        let (container, builtins) = (self.container, self.builtins);
+        Box::pin(async move {
+            match builtins::call(builtins, call.envelope()) {
+                Some(result) => result,
+                None => execute(container, call.envelope()).await,
+            }
+        })
+    }
+}
+
+/// Validates the call and runs its handler with the call context installed.
+///
+/// A panicking handler fails the call with [`RpcError::HandlerPanicked`]
+/// instead of unwinding into the server. A handler still running when the
+/// call's [deadline](Envelope::deadline) passes is dropped and the call fails
+/// with [`RpcError::DeadlineExceeded`].
+pub(crate) async fn execute(container: &Container, envelope: &Envelope) -> Result<Bytes, RpcError> {
+    let function = container.validate(envelope)?;
+    let run = async {
+        let handler = (function.handler)(envelope.parameters().clone());
+        RpcContext::new(container.extensions(), envelope.headers().clone())
+            .scope(handler)
+            .await
+    };
+    let run = AssertUnwindSafe(run).catch_unwind();
+    let outcome = match envelope.deadline() {
+        Some(deadline) => tokio::time::timeout(deadline, run).await.map_err(|_| {
+            tracing::debug!(
+                "Handler of {} exceeded its deadline of {deadline:?}",
+                function.name
+            );
+            RpcError::DeadlineExceeded
+        })?,
+        None => run.await,
+    };
+
+    match outcome {
+        Ok(result) => result,
+        Err(panic) => {
+            let message = panic
+                .downcast_ref::<&str>()
+                .copied()
+                .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+                .unwrap_or("<non-string panic payload>");
+            tracing::error!("Handler of {} panicked: {message}", function.name);
+            Err(RpcError::HandlerPanicked)
+        }
+    }
+}
@@ -0,0 +1,157 @@
+use std::{net::SocketAddr, time::Duration};
+
+use bytes::Bytes;
+use corgi::{
+    Container, RpcClient, RpcServer, ServerConfig, builtins,
+    protocol::{
+        codec::{ProtobufCodec, ResponseFieldsCodec, SchemaCodec},
+        codes,
+        types::RpcError,
+    },
+    rpc_fn,
+};
+use prometheus_parse::{Scrape, Value};
+
+#[rpc_fn]
+async fn add(a: i32, b: i32) -> i32 {
+    a + b
+}
+
+fn test_container() -> &'static Container {
+    let mut container = Container::default();
+    container.register(&__CORGI_RPC_add);
+    Box::leak(Box::new(container))
+}
+
+/// Starts a server with the test functions registered and returns its address.
+async fn spawn_server_with(config: ServerConfig) -> SocketAddr {
+    let address: SocketAddr = "127.0.0.1:0".parse().unwrap();
+    let server = RpcServer::create_udp(test_container(), address)
+        .await
+        .unwrap()
+        .with_config(config);
+    let local_address = server.local_address();
+    tokio::spawn(async move { server.start().await });
+
+    local_address
+}
+
+fn enabled() -> ServerConfig {
+    ServerConfig {
+        builtin_functions: true,
+        ..ServerConfig::default()
+    }
+}
+
+#[tokio::test]
+async fn reflect_should_return_schema_of_registered_functions() {
+    let address = spawn_server_with(enabled()).await;
+    let client = RpcClient::connect_udp(address).await.unwrap();
+
+    let reply = client.call(builtins::REFLECT, Vec::new()).await.unwrap();
+
+    let descriptors = SchemaCodec.decode(&reply).unwrap();
+    assert_eq!(descriptors, test_container().descriptors());
+}
+
+#[tokio::test]
+async fn config_should_report_effective_configuration() {
+    let address = spawn_server_with(ServerConfig {
+        max_partial_messages: 64,
+        reassembly_timeout: Duration::from_millis(1500),
+        ..enabled()
+    })
+    .await;
+    let client = RpcClient::connect_udp(address).await.unwrap();
+    let codec = ProtobufCodec;
+
+    let reply = client.call(builtins::CONFIG, Vec::new()).await.unwrap();
+
+    let fields = ResponseFieldsCodec.decode(&reply).unwrap();
+    let number = |name| codec.decode::<u64>(fields.get(name).unwrap()).unwrap();
+    assert_eq!(number("udp_chunk_size"), 1200);
+    assert_eq!(number("max_partial_messages"), 64);
+    assert_eq!(number("reassembly_timeout_ms"), 1500);
+    assert!(fields.get("peer_rate_limit_burst").is_none());
+    let endianness: String = codec.decode(fields.get("endianness").unwrap()).unwrap();
+    assert_eq!(endianness, "little");
+}
+
+/// Calls `fn_name`, a metrics built-in, and returns the `add` call count and
+/// the datagrams received it reports.
+async fn read_metrics(client: &RpcClient, fn_name: &str) -> (f64, f64) {
+    let reply = client.call(fn_name, Vec::new()).await.unwrap();
+    let text: String = ProtobufCodec.decode(&reply).unwrap();
+    let scrape = Scrape::parse(text.lines().map(|line| Ok(line.to_owned()))).unwrap();
+    let value = |metric: &str| {
+        let sample = scrape
+            .samples
+            .iter()
+            .find(|sample| sample.metric == metric)
+            .unwrap();
+        match sample.value {
+            Value::Counter(value) => value,
+            ref other => panic!("expected counter, got {other:?}"),
+        }
+    };
+
+    (
+        value("corgi_server_calls_total"),
+        value("corgi_server_datagrams_received_total"),
+    )
+}
+
+#[tokio::test]
+async fn metrics_should_report_and_reset_counters() {
+    let address = spawn_server_with(enabled()).await;
+    let client = RpcClient::connect_udp(address).await.unwrap();
+    let codec = ProtobufCodec;
+    let args = vec![codec.encode(&1_i32).unwrap(), codec.encode(&2_i32).unwrap()];
+
+    // Each built-in call is a datagram itself, counted before it is answered.
+    assert_eq!(read_metrics(&client, builtins::METRICS).await, (0.0, 1.0));
+    client.call("add", args.clone()).await.unwrap();
+    client.call("add", args).await.unwrap();
+    assert_eq!(read_metrics(&client, builtins::METRICS).await, (2.0, 4.0));
+
+    assert_eq!(
+        read_metrics(&client, builtins::METRICS_RESET).await,
+        (2.0, 5.0)
+    );
+    assert_eq!(read_metrics(&client, builtins::METRICS).await, (0.0, 1.0));
+}
+
+#[tokio::test]
+async fn builtins_should_reject_arguments() {
+    let address = spawn_server_with(enabled()).await;
+    let client = RpcClient::connect_udp(address).await.unwrap();
+
+    let result = client
+        .call(builtins::REFLECT, vec![Bytes::from_static(b"x")])
+        .await;
+
+    assert!(matches!(
+        result,
+        Err(RpcError::Remote { code, .. }) if code == codes::ARITY_MISMATCH
+    ));
+}
+
+#[tokio::test]
+async fn builtins_should_be_disabled_by_default() {
+    let address = spawn_server_with(ServerConfig::default()).await;
+    let client = RpcClient::connect_udp(address).await.unwrap();
+
+    for name in [
+        builtins::REFLECT,
+        builtins::CONFIG,
+        builtins::METRICS,
+        builtins::METRICS_RESET,
+    ] {
+        let result = client.call(name, Vec::new()).await;
+
+        assert!(matches!(
+            result,
+            Err(RpcError::Remote { code, .. }) if code == codes::FEATURE_DISABLED
+        ));
+    }
+}
@@ -0,0 +1,340 @@
+use bytes::Bytes;
+use corgi::protocol::{
+    codec::{
+        BIG_ENDIAN_FLAG, CHUNK_MAGIC, Endianness, MAX_CHUNK_PAYLOAD_SIZE, MAX_MESSAGE_SIZE,
+        NO_CHECKSUM_FLAG, PROTOCOL_VERSION, PackageChunkCodec,
+    },
+    types::{ChunkHeader, MessageKind, PackageChunk, RpcError},
+};
+
+#[test]
+fn chunk_header_should_be_created_with_valid_fields() {
+    let header = ChunkHeader::try_new(42, 1, 2, 128).unwrap();
+
+    assert_eq!(header.call_id(), 42);
+    assert_eq!(header.index(), 1);
+    assert_eq!(header.total(), 2);
+    assert_eq!(header.payload_len(), 128);
+}
+
+#[test]
+fn chunk_header_should_reject_zero_total() {
+    let result = ChunkHeader::try_new(42, 0, 0, 128);
+
+    assert!(matches!(result, Err(RpcError::InvalidChunkIndex)));
+}
+
+#[test]
+fn chunk_header_should_reject_index_equal_to_total() {
+    let result = ChunkHeader::try_new(42, 2, 2, 128);
+
+    assert!(matches!(result, Err(RpcError::InvalidChunkIndex)));
+}
+
+#[test]
+fn chunk_header_should_reject_payload_len_above_max() {
+    let result = ChunkHeader::try_new(42, 0, 1, MAX_CHUNK_PAYLOAD_SIZE as u32 + 1);
+
+    assert!(matches!(
+        result,
+        Err(RpcError::MaxChunkPayloadSizeConstraintViolation)
+    ));
+}
+
+#[test]
+fn package_chunk_codec_should_reject_header_with_index_out_of_range() {
+    let mut bytes = Vec::with_capacity(32);
+    bytes.extend_from_slice(&CHUNK_MAGIC);
+    bytes.push(PROTOCOL_VERSION);
+    bytes.extend_from_slice(&42_u64.to_le_bytes());
+    bytes.extend_from_slice(&3_u16.to_le_bytes());
+    bytes.extend_from_slice(&3_u16.to_le_bytes());
+    bytes.extend_from_slice(&0_u32.to_le_bytes());
+    bytes.push(MessageKind::Request as u8);
+    bytes.extend_from_slice(&0_u32.to_le_bytes());
+    bytes.extend_from_slice(&0_u64.to_le_bytes());
+
+    let result = PackageChunkCodec::default().decode(&bytes);
+
+    assert!(matches!(result, Err(RpcError::InvalidChunkIndex)));
+}
+
+#[test]
+fn package_chunk_codec_should_reject_unknown_message_kind() {
+    let mut bytes = Vec::with_capacity(32);
+    bytes.extend_from_slice(&CHUNK_MAGIC);
+    bytes.push(PROTOCOL_VERSION);
+    bytes.extend_from_slice(&42_u64.to_le_bytes());
+    bytes.extend_from_slice(&0_u16.to_le_bytes());
+    bytes.extend_from_slice(&1_u16.to_le_bytes());
+    bytes.extend_from_slice(&0_u32.to_le_bytes());
+    bytes.push(0xff);
+    bytes.extend_from_slice(&0_u32.to_le_bytes());
+    bytes.extend_from_slice(&0_u64.to_le_bytes());
+
+    let result = PackageChunkCodec::default().decode(&bytes);
+
+    assert!(matches!(result, Err(RpcError::UnsupportedMessageKind)));
+}
+
+#[test]
+fn package_chunk_codec_should_round_trip_chunk_with_payload() {
+    let codec = PackageChunkCodec::default();
+    let header = ChunkHeader::new(42, 1, 3, 5).with_kind(MessageKind::Response);
+    let chunk = PackageChunk::new(header, Bytes::from_static(b"hello"));
+
+    let bytes = codec.encode(chunk).unwrap();
+    let decoded = codec.decode(&bytes).unwrap();
+
+    assert_eq!(decoded.header().call_id(), 42);
+    assert_eq!(decoded.header().index(), 1);
+    assert_eq!(decoded.header().total(), 3);
+    assert_eq!(decoded.header().payload_len(), 5);
+    assert_eq!(decoded.header().kind(), MessageKind::Response);
+    assert_eq!(decoded.payload().as_ref(), b"hello");
+}
+
+#[test]
+fn package_chunk_codec_should_reject_every_truncation_without_panicking() {
+    let codec = PackageChunkCodec::default();
+    let chunk = PackageChunk::new(ChunkHeader::new(42, 0, 1, 5), Bytes::from_static(b"hello"));
+    let bytes = codec.encode(chunk).unwrap();
+
+    for len in 0..bytes.len() {
+        let result = codec.decode(&bytes[..len]);
+
+        assert!(
+            matches!(result, Err(RpcError::ChunkHeaderSizeConstraintViolation)),
+            "unexpected result for {len} bytes: {result:?}"
+        );
+    }
+}
+
+#[test]
+fn package_chunk_codec_should_reject_chunk_with_corrupted_payload() {
+    let codec = PackageChunkCodec::default();
+    let chunk = PackageChunk::new(ChunkHeader::new(42, 0, 1, 5), Bytes::from_static(b"hello"));
+    let mut bytes = codec.encode(chunk).unwrap().to_vec();
+
+    let last = bytes.len() - 1;
+    bytes[last] ^= 0x01;
+    let result = codec.decode(&bytes);
+
+    assert!(matches!(result, Err(RpcError::ChecksumMismatch)));
+}
+
+#[test]
+fn package_chunk_codec_should_reject_datagram_without_magic() {
+    let chunk = PackageChunk::new(ChunkHeader::new(42, 0, 1, 5), Bytes::from_static(b"hello"));
+    let mut bytes = PackageChunkCodec::default().encode(chunk).unwrap().to_vec();
+    bytes[..2].copy_from_slice(b"XX");
+
+    let result = PackageChunkCodec::default().decode(&bytes);
+
+    assert!(matches!(result, Err(RpcError::BadMagic)));
+}
+
+#[test]
+fn package_chunk_codec_should_reject_unsupported_protocol_version() {
+    let chunk = PackageChunk::new(ChunkHeader::new(42, 0, 1, 5), Bytes::from_static(b"hello"));
+    let mut bytes = PackageChunkCodec::default().encode(chunk).unwrap().to_vec();
+    bytes[2] = PROTOCOL_VERSION + 1;
+
+    let result = PackageChunkCodec::default().decode(&bytes);
+
+    assert!(matches!(result, Err(RpcError::UnsupportedVersion)));
+}
+
+#[test]
+fn package_chunk_codec_should_split_payload_into_views_of_the_same_buffer() {
+    let payload = Bytes::from(vec![7_u8; 5000]);
+    let buffer = payload.as_ptr_range();
+
+    let chunks = PackageChunkCodec::default()
+        .split(42, 42, MessageKind::Response, &payload)
+        .unwrap();
+
+    assert_eq!(chunks.len(), 5);
+    let mut expected_start = buffer.start;
+    for (index, chunk) in chunks.iter().enumerate() {
+        let range = chunk.payload().as_ptr_range();
+        assert_eq!(chunk.header().index() as usize, index);
+        assert_eq!(chunk.header().total(), 5);
+        assert_eq!(chunk.header().kind(), MessageKind::Response);
+        assert_eq!(range.start, expected_start);
+        assert!(range.end <= buffer.end);
+        expected_start = range.end;
+    }
+    assert_eq!(expected_start, buffer.end);
+}
+
+#[test]
+fn package_chunk_codec_should_reject_message_needing_more_chunks_than_header_counts() {
+    let codec = PackageChunkCodec::default();
+    let largest = Bytes::from(vec![0_u8; MAX_MESSAGE_SIZE]);
+    let too_large = Bytes::from(vec![0_u8; MAX_MESSAGE_SIZE + 1]);
+
+    let chunks = codec.split(42, 0, MessageKind::Request, &largest).unwrap();
+    let result = codec.split(42, 0, MessageKind::Request, &too_large);
+
+    assert_eq!(chunks.len(), u16::MAX as usize);
+    assert_eq!(chunks.last().unwrap().header().index(), u16::MAX - 1);
+    assert!(matches!(result, Err(RpcError::MessageTooLarge)));
+}
+
+fn sample_chunk() -> PackageChunk {
+    let header = ChunkHeader::new(0x0102_0304_0506_0708, 1, 3, 4)
+        .with_kind(MessageKind::Response)
+        .with_correlation_id(99);
+    PackageChunk::new(header, Bytes::from_static(b"data"))
+}
+
+#[test]
+fn package_chunk_codec_should_round_trip_under_both_endiannesses() {
+    for endianness in [Endianness::Little, Endianness::Big] {
+        let codec = PackageChunkCodec::new(endianness);
+
+        let decoded = codec
+            .decode(&codec.encode(sample_chunk()).unwrap())
+            .unwrap();
+
+        assert_eq!(decoded.header().call_id(), 0x0102_0304_0506_0708);
+        assert_eq!(decoded.header().index(), 1);
+        assert_eq!(decoded.header().total(), 3);
+        assert_eq!(decoded.header().kind(), MessageKind::Response);
+        assert_eq!(decoded.header().correlation_id(), 99);
+        assert_eq!(decoded.payload().as_ref(), b"data");
+    }
+}
+
+#[test]
+fn package_chunk_codec_should_flag_big_endian_headers_in_version_byte() {
+    let little = PackageChunkCodec::new(Endianness::Little)
+        .encode(sample_chunk())
+        .unwrap();
+    let big = PackageChunkCodec::new(Endianness::Big)
+        .encode(sample_chunk())
+        .unwrap();
+
+    assert_eq!(little[2], PROTOCOL_VERSION);
+    assert_eq!(big[2], PROTOCOL_VERSION | BIG_ENDIAN_FLAG);
+    assert_eq!(&little[3..11], &0x0102_0304_0506_0708_u64.to_le_bytes());
+    assert_eq!(&big[3..11], &0x0102_0304_0506_0708_u64.to_be_bytes());
+    assert_eq!(&little[32..], &big[32..]);
+}
+
+#[test]
+fn package_chunk_codec_should_reject_chunk_of_other_endianness() {
+    let little = PackageChunkCodec::new(Endianness::Little);
+    let big = PackageChunkCodec::new(Endianness::Big);
+
+    let big_as_little = little.decode(&big.encode(sample_chunk()).unwrap());
+    let little_as_big = big.decode(&little.encode(sample_chunk()).unwrap());
+
+    assert!(matches!(big_as_little, Err(RpcError::EndiannessMismatch)));
+    assert!(matches!(little_as_big, Err(RpcError::EndiannessMismatch)));
+}
+
+/// Builds a version 0 chunk: a 16-byte little-endian header without magic,
+/// version, kind or checksum.
+fn v0_chunk(call_id: u64, index: u16, total: u16, payload: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(16 + payload.len());
+    bytes.extend_from_slice(&call_id.to_le_bytes());
+    bytes.extend_from_slice(&index.to_le_bytes());
+    bytes.extend_from_slice(&total.to_le_bytes());
+    bytes.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(payload);
+    bytes
+}
+
+/// Version 0 envelope calling `legacy.ping.function` without arguments.
+const V0_PING: &[u8] = b"\x14\x00legacy.ping.function\x00\x00";
+
+#[test]
+fn package_chunk_codec_should_decode_v0_and_versioned_chunks_with_compat_v0() {
+    let codec = PackageChunkCodec::default().with_compat_v0(true);
+
+    let legacy = codec.decode(&v0_chunk(7, 0, 1, V0_PING)).unwrap();
+    let current = codec
+        .decode(&codec.encode(sample_chunk()).unwrap())
+        .unwrap();
+
+    assert_eq!(legacy.header().call_id(), 7);
+    assert_eq!(legacy.header().index(), 0);
+    assert_eq!(legacy.header().total(), 1);
+    assert_eq!(legacy.header().kind(), MessageKind::Request);
+    assert_eq!(legacy.header().correlation_id(), 0);
+    assert_eq!(legacy.payload().as_ref(), V0_PING);
+    assert_eq!(current.header().call_id(), 0x0102_0304_0506_0708);
+    assert_eq!(current.header().kind(), MessageKind::Response);
+    assert_eq!(current.payload().as_ref(), b"data");
+}
+
+#[test]
+fn package_chunk_codec_should_decode_later_v0_chunk_without_function_name() {
+    let codec = PackageChunkCodec::default().with_compat_v0(true);
+
+    let chunk = codec.decode(&v0_chunk(7, 1, 2, b"\xff\xff")).unwrap();
+
+    assert_eq!(chunk.header().index(), 1);
+    assert_eq!(chunk.payload().as_ref(), b"\xff\xff");
+}
+
+#[test]
+fn package_chunk_codec_should_reject_v0_chunk_without_compat_v0() {
+    let result = PackageChunkCodec::default().decode(&v0_chunk(7, 0, 1, V0_PING));
+
+    assert!(matches!(result, Err(RpcError::BadMagic)));
+}
+
+#[test]
+fn package_chunk_codec_should_reject_implausible_v0_chunk_with_compat_v0() {
+    let codec = PackageChunkCodec::default().with_compat_v0(true);
+    let mut wrong_len = v0_chunk(7, 0, 1, V0_PING);
+    wrong_len.push(0);
+    let name_past_payload = v0_chunk(7, 0, 1, b"\x40\x00legacy.ping.function\x00\x00");
+    let index_past_total = v0_chunk(7, 1, 1, V0_PING);
+
+    for bytes in [wrong_len, name_past_payload, index_past_total] {
+        let result = codec.decode(&bytes);
+
+        assert!(matches!(result, Err(RpcError::BadMagic)));
+    }
+}
+
+#[test]
+fn package_chunk_codec_should_flag_chunks_sent_without_checksum_in_version_byte() {
+    let bytes = PackageChunkCodec::new(Endianness::Big)
+        .with_checksums(false)
+        .encode(sample_chunk())
+        .unwrap();
+
+    assert_eq!(
+        bytes[2],
+        PROTOCOL_VERSION | BIG_ENDIAN_FLAG | NO_CHECKSUM_FLAG
+    );
+    assert_eq!(&bytes[20..24], &[0; 4]);
+}
+
+#[test]
+fn package_chunk_codec_should_validate_checksum_as_flagged_by_sender() {
+    for sender_checksums in [true, false] {
+        for receiver_checksums in [true, false] {
+            let sender = PackageChunkCodec::default().with_checksums(sender_checksums);
+            let receiver = PackageChunkCodec::default().with_checksums(receiver_checksums);
+            let bytes = sender.encode(sample_chunk()).unwrap().to_vec();
+            let mut corrupted = bytes.clone();
+            *corrupted.last_mut().unwrap() ^= 0x01;
+
+            let intact = receiver.decode(&bytes);
+            let corrupted = receiver.decode(&corrupted);
+
+            assert_eq!(intact.unwrap().payload().as_ref(), b"data");
+            if sender_checksums {
+                assert!(matches!(corrupted, Err(RpcError::ChecksumMismatch)));
+            } else {
+                assert_eq!(corrupted.unwrap().payload().as_ref(), b"dat`");
+            }
+        }
+    }
+}
@@ -0,0 +1,382 @@
+use std::{
+    collections::HashSet,
+    time::{Duration, Instant},
+};
+
+use bytes::Bytes;
+use corgi::{
+    RpcClient,
+    client::{
+        BudgetExhausted, CallIdGenerator, ChunkWindow, ClientConfig, MemoryBudget,
+        MonotonicCallIds, RandomCallIds,
+    },
+    protocol::{
+        codec::{CHUNK_MAGIC, EnvelopeCodec, PROTOCOL_VERSION},
+        make_datagram,
+        types::{MessageKind, RpcError},
+    },
+};
+use tokio::net::UdpSocket;
+
+/// Builds a single-chunk empty response to `call_id`.
+fn empty_reply(call_id: u64) -> Vec<u8> {
+    let mut reply = Vec::with_capacity(32);
+    reply.extend_from_slice(&CHUNK_MAGIC);
+    reply.push(PROTOCOL_VERSION);
+    reply.extend_from_slice(&call_id.to_le_bytes());
+    reply.extend_from_slice(&0_u16.to_le_bytes());
+    reply.extend_from_slice(&1_u16.to_le_bytes());
+    reply.extend_from_slice(&0_u32.to_le_bytes());
+    reply.push(MessageKind::Response as u8);
+    reply.extend_from_slice(&crc32fast::hash(&[]).to_le_bytes());
+    reply.extend_from_slice(&call_id.to_le_bytes());
+    reply
+}
+
+#[test]
+fn monotonic_call_ids_should_count_up_from_one() {
+    let call_ids = MonotonicCallIds::default();
+
+    let ids: Vec<_> = (0..3).map(|_| call_ids.next_call_id()).collect();
+
+    assert_eq!(ids, [1, 2, 3]);
+}
+
+#[test]
+fn random_call_ids_should_not_repeat() {
+    let call_ids = RandomCallIds::default();
+
+    let ids: HashSet<_> = (0..1000).map(|_| call_ids.next_call_id()).collect();
+
+    assert_eq!(ids.len(), 1000);
+}
+
+#[tokio::test]
+async fn rpc_client_should_chunk_call_and_resolve_on_matching_reply() {
+    let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let client = RpcClient::connect_udp(server.local_addr().unwrap())
+        .await
+        .unwrap();
+    let argument = Bytes::from(vec![7_u8; 3000]);
+
+    let call = tokio::spawn({
+        let argument = argument.clone();
+        async move { client.call("upload", vec![argument]).await }
+    });
+
+    // 3000 bytes of argument do not fit into a single 1200 byte datagram.
+    let mut payload = Vec::new();
+    let mut buf = [0_u8; 2048];
+    let (len, client_address) = server.recv_from(&mut buf).await.unwrap();
+    let call_id = u64::from_le_bytes(buf[3..11].try_into().unwrap());
+    let total = u16::from_le_bytes(buf[13..15].try_into().unwrap());
+    payload.extend_from_slice(&buf[32..len]);
+    assert_eq!(total, 3);
+
+    for expected_index in 1..total {
+        let (len, _) = server.recv_from(&mut buf).await.unwrap();
+        assert!(len <= 1200);
+        assert_eq!(u64::from_le_bytes(buf[3..11].try_into().unwrap()), call_id);
+        assert_eq!(
+            u16::from_le_bytes(buf[11..13].try_into().unwrap()),
+            expected_index
+        );
+        payload.extend_from_slice(&buf[32..len]);
+    }
+
+    let envelope = EnvelopeCodec.decode(&payload).unwrap();
+    assert_eq!(envelope.fn_name(), "upload");
+    assert_eq!(envelope.parameters(), &vec![argument]);
+
+    // A reply to some other call is ignored; the matching one resolves it.
+    for reply_call_id in [call_id + 1, call_id] {
+        server
+            .send_to(&empty_reply(reply_call_id), client_address)
+            .await
+            .unwrap();
+    }
+
+    let result = call.await.unwrap().unwrap();
+    assert!(result.is_empty());
+}
+
+#[tokio::test]
+async fn rpc_client_should_time_out_at_deadline_while_retries_remain() {
+    let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let client = RpcClient::connect_udp(server.local_addr().unwrap())
+        .await
+        .unwrap()
+        .with_config(ClientConfig {
+            timeout: Duration::from_millis(300),
+            retries: 10,
+            deadline: Some(Duration::from_secs(1)),
+            ..ClientConfig::default()
+        });
+    let started_at = Instant::now();
+
+    let result = client.call("silent", vec![]).await;
+
+    let elapsed = started_at.elapsed();
+    let mut attempts = 0;
+    let mut buf = [0_u8; 2048];
+    while let Ok(Ok(_)) =
+        tokio::time::timeout(Duration::from_millis(50), server.recv_from(&mut buf)).await
+    {
+        attempts += 1;
+    }
+    assert!(matches!(result, Err(RpcError::Timeout)));
+    // Attempts start at 0, 300, 600 and 900ms; the 11 allowed would take
+    // 3.3 seconds.
+    assert_eq!(attempts, 4);
+    assert!(elapsed >= Duration::from_secs(1));
+    assert!(elapsed < Duration::from_millis(1200));
+}
+
+#[tokio::test]
+async fn rpc_client_should_time_out_after_configured_attempts_to_unresponsive_server() {
+    let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let client = RpcClient::connect_udp(server.local_addr().unwrap())
+        .await
+        .unwrap()
+        .with_config(ClientConfig {
+            timeout: Duration::from_millis(50),
+            retries: 2,
+            ..ClientConfig::default()
+        });
+
+    let result = client.call("silent", vec![]).await;
+
+    let mut call_ids = Vec::new();
+    let mut buf = [0_u8; 2048];
+    while let Ok(Ok((_, _))) =
+        tokio::time::timeout(Duration::from_millis(50), server.recv_from(&mut buf)).await
+    {
+        call_ids.push(u64::from_le_bytes(buf[3..11].try_into().unwrap()));
+    }
+    assert!(matches!(result, Err(RpcError::Timeout)));
+    assert_eq!(call_ids.len(), 3);
+    assert!(call_ids.iter().all(|call_id| *call_id == call_ids[0]));
+}
+
+// Linux reports the ICMP port unreachable answering a datagram to a closed
+// port on the connected socket; other platforms may drop it silently.
+#[cfg(target_os = "linux")]
+#[tokio::test]
+async fn rpc_client_should_fail_all_pending_calls_fast_once_peer_is_unreachable() {
+    let closed = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let address = closed.local_addr().unwrap();
+    drop(closed);
+    let client = RpcClient::connect_udp(address)
+        .await
+        .unwrap()
+        .with_config(ClientConfig {
+            timeout: Duration::from_secs(5),
+            retries: 0,
+            ..ClientConfig::default()
+        });
+    let started_at = Instant::now();
+
+    let results = tokio::join!(
+        client.call("first", vec![]),
+        client.call("second", vec![]),
+        client.call("third", vec![]),
+    );
+
+    let elapsed = started_at.elapsed();
+    for result in [results.0, results.1, results.2] {
+        assert!(
+            matches!(result, Err(RpcError::PeerUnreachable)),
+            "{result:?}"
+        );
+    }
+    assert!(elapsed < Duration::from_secs(1));
+}
+
+#[tokio::test]
+async fn rpc_client_should_keep_outstanding_chunks_within_window() {
+    const WINDOW: ChunkWindow = ChunkWindow {
+        chunks: 3,
+        interval: Duration::from_millis(60),
+    };
+    let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let client = RpcClient::connect_udp(server.local_addr().unwrap())
+        .await
+        .unwrap()
+        .with_config(ClientConfig {
+            timeout: Duration::from_millis(50),
+            retries: 0,
+            chunk_window: Some(WINDOW),
+            ..ClientConfig::default()
+        });
+    let args = vec![Bytes::from(vec![7_u8; 10_000])];
+
+    let (_, arrivals) = tokio::join!(client.call("upload", args), async {
+        let mut arrivals = Vec::new();
+        let mut buf = [0_u8; 2048];
+        loop {
+            server.recv_from(&mut buf).await.unwrap();
+            arrivals.push(Instant::now());
+            let total = u16::from_le_bytes(buf[13..15].try_into().unwrap());
+            if arrivals.len() == usize::from(total) {
+                return arrivals;
+            }
+        }
+    });
+
+    assert!(arrivals.len() > 2 * WINDOW.chunks);
+    // A chunk is only sent once the one `chunks` before it expired; allow
+    // for timer granularity.
+    for (earlier, later) in arrivals.iter().zip(&arrivals[WINDOW.chunks..]) {
+        assert!(*later - *earlier >= WINDOW.interval - Duration::from_millis(5));
+    }
+}
+
+/// Connects a client with a memory budget fitting one call of [`upload_args`]
+/// to `server`.
+async fn budgeted_client(server: &UdpSocket, when_exhausted: BudgetExhausted) -> RpcClient {
+    RpcClient::connect_udp(server.local_addr().unwrap())
+        .await
+        .unwrap()
+        .with_config(ClientConfig {
+            timeout: Duration::from_millis(100),
+            retries: 0,
+            memory_budget: Some(MemoryBudget {
+                bytes: 3000,
+                when_exhausted,
+            }),
+            ..ClientConfig::default()
+        })
+}
+
+/// Arguments of a two-chunk call, about 2100 bytes on the wire.
+fn upload_args() -> Vec<Bytes> {
+    vec![Bytes::from(vec![7_u8; 2000])]
+}
+
+#[tokio::test]
+async fn rpc_client_should_refuse_calls_beyond_memory_budget() {
+    let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let client = budgeted_client(&server, BudgetExhausted::Fail).await;
+
+    let (first, second) = tokio::join!(client.call("upload", upload_args()), async {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        client.call("upload", upload_args()).await
+    });
+    let after = client.call("upload", upload_args()).await;
+
+    assert!(matches!(first, Err(RpcError::Timeout)));
+    assert!(matches!(second, Err(RpcError::ClientBusy)));
+    // The first call released its bytes once it timed out.
+    assert!(matches!(after, Err(RpcError::Timeout)));
+}
+
+#[tokio::test]
+async fn rpc_client_should_hold_calls_beyond_memory_budget_until_room_frees() {
+    let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let client = budgeted_client(&server, BudgetExhausted::Wait).await;
+    let started_at = Instant::now();
+
+    let (first, second, arrivals) = tokio::join!(
+        client.call("upload", upload_args()),
+        async {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            client.call("upload", upload_args()).await
+        },
+        async {
+            let mut arrivals = Vec::new();
+            let mut buf = [0_u8; 2048];
+            for _ in 0..4 {
+                server.recv_from(&mut buf).await.unwrap();
+                arrivals.push(started_at.elapsed());
+            }
+            arrivals
+        },
+    );
+
+    assert!(matches!(first, Err(RpcError::Timeout)));
+    assert!(matches!(second, Err(RpcError::Timeout)));
+    // The second call's chunks only go out once the first call timed out.
+    assert!(arrivals[1] < Duration::from_millis(50));
+    assert!(arrivals[2] >= Duration::from_millis(100));
+}
+
+#[tokio::test]
+async fn rpc_client_should_accept_late_reply_to_original_after_retransmit() {
+    let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let client = RpcClient::connect_udp(server.local_addr().unwrap())
+        .await
+        .unwrap()
+        .with_config(ClientConfig {
+            timeout: Duration::from_millis(100),
+            retries: 3,
+            ..ClientConfig::default()
+        });
+
+    let call = tokio::spawn(async move { client.call("late", vec![]).await });
+
+    let mut buf = [0_u8; 2048];
+    let (_, client_address) = server.recv_from(&mut buf).await.unwrap();
+    let original_call_id = u64::from_le_bytes(buf[3..11].try_into().unwrap());
+    server.recv_from(&mut buf).await.unwrap();
+    let retransmit_call_id = u64::from_le_bytes(buf[3..11].try_into().unwrap());
+    server
+        .send_to(&empty_reply(original_call_id), client_address)
+        .await
+        .unwrap();
+
+    assert_eq!(retransmit_call_id, original_call_id);
+    assert!(call.await.unwrap().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn rpc_client_should_drop_incomplete_replies_and_keep_answering_calls() {
+    let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let client = RpcClient::connect_udp(server.local_addr().unwrap())
+        .await
+        .unwrap()
+        .with_config(ClientConfig {
+            reassembly_timeout: Duration::from_millis(50),
+            ..ClientConfig::default()
+        });
+    let client_address = {
+        let call = client.call("probe", vec![]);
+        let answer = async {
+            let mut buf = [0_u8; 2048];
+            let (_, client_address) = server.recv_from(&mut buf).await.unwrap();
+            let call_id = u64::from_le_bytes(buf[3..11].try_into().unwrap());
+            server
+                .send_to(&empty_reply(call_id), client_address)
+                .await
+                .unwrap();
+            client_address
+        };
+        let (reply, client_address) = tokio::join!(call, answer);
+        reply.unwrap();
+        client_address
+    };
+
+    // First chunks of replies whose second chunk is lost, more than the
+    // client's reassembly table holds.
+    for batch in 0..20_u64 {
+        for call_id in batch * 100..(batch + 1) * 100 {
+            let partial = make_datagram(10_000 + call_id, 0, 2, b"x");
+            server.send_to(&partial, client_address).await.unwrap();
+        }
+        tokio::time::sleep(Duration::from_millis(1)).await;
+    }
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    let call = client.call("probe", vec![]);
+    let answer = async {
+        let mut buf = [0_u8; 2048];
+        server.recv_from(&mut buf).await.unwrap();
+        let call_id = u64::from_le_bytes(buf[3..11].try_into().unwrap());
+        server
+            .send_to(&empty_reply(call_id), client_address)
+            .await
+            .unwrap();
+    };
+    let (reply, ()) = tokio::join!(call, answer);
+
+    assert!(reply.is_ok());
+}
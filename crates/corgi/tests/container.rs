@@ -0,0 +1,500 @@
+use bytes::Bytes;
+use corgi::{
+    Container,
+    container::{ArityPolicy, DEFAULT_FUNCTION_NAME_LIMIT},
+    protocol::{
+        codec::{ProtobufCodec, SchemaCodec},
+        types::{Envelope, FunctionDescriptor, ParamDescriptor, RpcError},
+    },
+};
+
+mod v1 {
+    use corgi::rpc_fn;
+
+    #[rpc_fn]
+    pub async fn add(a: i32, b: i32) -> i32 {
+        a + b
+    }
+}
+
+mod v2 {
+    use corgi::rpc_fn;
+
+    #[rpc_fn(version = 2)]
+    pub async fn add(a: i32, b: i32) -> i32 {
+        (a + b) * 2
+    }
+}
+
+mod colliding {
+    use corgi::rpc_fn;
+
+    #[rpc_fn(name = "add")]
+    pub async fn subtract(a: i32, b: i32) -> i32 {
+        a - b
+    }
+}
+
+async fn call(container: &Container, name: &str, a: i32, b: i32) -> i32 {
+    let codec = ProtobufCodec;
+    let function = container.find(name).unwrap();
+    let args = vec![codec.encode(&a).unwrap(), codec.encode(&b).unwrap()];
+    let result = (function.handler)(args).await.unwrap();
+    codec.decode(&result).unwrap()
+}
+
+#[tokio::test]
+async fn container_should_route_calls_by_function_version() {
+    let mut container = Container::default();
+    container.register(&v1::__CORGI_RPC_add);
+    container.register(&v2::__CORGI_RPC_add);
+
+    assert_eq!(v1::__CORGI_RPC_add.name, "add");
+    assert_eq!(v2::__CORGI_RPC_add.name, "add@2");
+    assert_eq!(v2::__CORGI_RPC_add.version, 2);
+
+    assert_eq!(call(&container, "add", 1, 2).await, 3);
+    assert_eq!(call(&container, "add@1", 1, 2).await, 3);
+    assert_eq!(call(&container, "add@2", 1, 2).await, 6);
+}
+
+#[tokio::test]
+async fn container_should_register_functions_through_chained_builder() {
+    let container = Container::default()
+        .with(&v1::__CORGI_RPC_add)
+        .with(&v2::__CORGI_RPC_add);
+
+    assert_eq!(call(&container, "add", 1, 2).await, 3);
+    assert_eq!(call(&container, "add@2", 1, 2).await, 6);
+}
+
+#[tokio::test]
+async fn container_should_try_register_function_under_free_name() {
+    let mut container = Container::default();
+
+    let first = container.try_register(&v1::__CORGI_RPC_add);
+    let again = container.try_register(&v1::__CORGI_RPC_add);
+
+    assert!(first.is_ok());
+    assert!(again.is_ok());
+    assert_eq!(call(&container, "add", 1, 2).await, 3);
+}
+
+#[tokio::test]
+async fn container_should_reject_try_register_of_other_function_under_taken_name() {
+    let mut container = Container::default();
+    container.try_register(&v1::__CORGI_RPC_add).unwrap();
+
+    let result = container.try_register(&colliding::__CORGI_RPC_subtract);
+
+    assert!(matches!(result, Err(RpcError::DuplicateFunction)));
+    assert_eq!(call(&container, "add", 1, 2).await, 3);
+}
+
+#[tokio::test]
+async fn container_should_keep_first_function_when_register_collides() {
+    let mut container = Container::default();
+
+    container.register(&v1::__CORGI_RPC_add);
+    container.register(&colliding::__CORGI_RPC_subtract);
+
+    assert_eq!(call(&container, "add", 1, 2).await, 3);
+}
+
+#[test]
+fn container_should_not_resolve_unregistered_version() {
+    let mut container = Container::default();
+    container.register(&v1::__CORGI_RPC_add);
+
+    assert!(container.find("add@3").is_none());
+}
+
+#[tokio::test]
+async fn container_should_dispatch_alias_to_the_same_handler() {
+    let mut container = Container::default();
+    container.register(&v1::__CORGI_RPC_add);
+
+    container.register_alias("add", "sum").unwrap();
+
+    assert!(std::ptr::eq(
+        container.find("add").unwrap(),
+        container.find("sum").unwrap()
+    ));
+    assert_eq!(call(&container, "sum", 2, 3).await, 5);
+    assert_eq!(call(&container, "add", 2, 3).await, 5);
+}
+
+#[test]
+fn container_should_reject_alias_of_unknown_function() {
+    let mut container = Container::default();
+
+    let result = container.register_alias("add", "sum");
+
+    assert!(matches!(result, Err(RpcError::UnknownFunction)));
+}
+
+#[test]
+fn container_should_reject_alias_colliding_with_registered_name() {
+    let mut container = Container::default();
+    container.register(&v1::__CORGI_RPC_add);
+    container.register(&v2::__CORGI_RPC_add);
+
+    let result = container.register_alias("add", "add@2");
+
+    assert!(matches!(result, Err(RpcError::DuplicateFunction)));
+    assert_eq!(container.find("add@2").unwrap().version, 2);
+}
+
+#[test]
+fn container_should_enumerate_registered_functions_and_names() {
+    let empty = Container::default();
+    let mut container = Container::default()
+        .with(&v1::__CORGI_RPC_add)
+        .with(&v2::__CORGI_RPC_add);
+    container.register_alias("add", "sum").unwrap();
+
+    let mut names: Vec<_> = container.names().collect();
+    names.sort_unstable();
+    let mut functions: Vec<_> = container
+        .iter()
+        .map(|function| (function.name, function.version))
+        .collect();
+    functions.sort_unstable();
+
+    assert_eq!(names, ["add", "add@2", "sum"]);
+    assert_eq!(functions, [("add", 1), ("add@2", 2)]);
+    assert_eq!(container.len(), 2);
+    assert!(!container.is_empty());
+    assert_eq!(empty.len(), 0);
+    assert!(empty.is_empty());
+}
+
+#[test]
+fn container_should_validate_known_call_with_matching_arity() {
+    let mut container = Container::default();
+    container.register(&v1::__CORGI_RPC_add);
+    let envelope = Envelope::new("add".to_owned(), vec![Bytes::new(), Bytes::new()]);
+
+    let function = container.validate(&envelope).unwrap();
+
+    assert_eq!(function.name, "add");
+}
+
+#[test]
+fn container_should_reject_validation_of_unknown_function() {
+    let container = Container::default();
+    let envelope = Envelope::new("add".to_owned(), vec![]);
+
+    let result = container.validate(&envelope);
+
+    assert!(matches!(result, Err(RpcError::UnknownFunction)));
+}
+
+#[test]
+fn container_should_reject_validation_on_arity_mismatch() {
+    let mut container = Container::default();
+    container.register(&v1::__CORGI_RPC_add);
+    let envelope = Envelope::new("add".to_owned(), vec![Bytes::new()]);
+
+    let result = container.validate(&envelope);
+
+    assert!(matches!(result, Err(RpcError::ArityMismatch)));
+}
+
+#[tokio::test]
+async fn container_should_ignore_extra_trailing_arguments_only_under_lenient_arity() {
+    let codec = ProtobufCodec;
+    let envelope = Envelope::new(
+        "add".to_owned(),
+        vec![
+            codec.encode(&2_i32).unwrap(),
+            codec.encode(&3_i32).unwrap(),
+            codec.encode(&4_i32).unwrap(),
+        ],
+    );
+    let mut strict = Container::default();
+    strict.register(&v1::__CORGI_RPC_add);
+    let mut lenient = Container::default();
+    lenient.register(&v1::__CORGI_RPC_add);
+    lenient.set_arity_policy(ArityPolicy::IgnoreExtra);
+
+    let rejected = strict.validate(&envelope);
+    let function = lenient.validate(&envelope).unwrap();
+    let result = (function.handler)(envelope.parameters().clone())
+        .await
+        .unwrap();
+
+    assert!(matches!(rejected, Err(RpcError::ArityMismatch)));
+    assert_eq!(codec.decode::<i32>(&result).unwrap(), 5);
+}
+
+#[test]
+fn container_should_still_validate_size_of_ignored_arguments() {
+    let mut container = Container::default();
+    container.register(&v1::__CORGI_RPC_add);
+    container.set_arity_policy(ArityPolicy::IgnoreExtra);
+    let envelope = Envelope::new(
+        "add".to_owned(),
+        vec![
+            Bytes::new(),
+            Bytes::new(),
+            Bytes::from(vec![0; 16 * 1024 * 1024 + 1]),
+        ],
+    );
+
+    let result = container.validate(&envelope);
+
+    assert!(matches!(
+        result,
+        Err(RpcError::MaxArgumentSizeConstraintViolation)
+    ));
+}
+
+#[test]
+fn container_should_accept_function_name_at_soft_limit() {
+    let mut container = Container::default();
+    container.set_function_name_limit(3);
+    container.register(&v1::__CORGI_RPC_add);
+    let envelope = Envelope::new("add".to_owned(), vec![Bytes::new(), Bytes::new()]);
+
+    let result = container.validate(&envelope);
+
+    assert!(result.is_ok());
+}
+
+#[test]
+fn container_should_reject_function_name_beyond_soft_limit_before_lookup() {
+    let mut container = Container::default();
+    container.set_function_name_limit(3);
+    container.register(&v1::__CORGI_RPC_add);
+    let envelope = Envelope::new("add@1".to_owned(), vec![Bytes::new(), Bytes::new()]);
+
+    let result = container.validate(&envelope);
+
+    assert!(matches!(result, Err(RpcError::FunctionNameTooLong)));
+}
+
+#[test]
+fn container_should_apply_default_soft_limit_below_hard_limit() {
+    let container = Container::default();
+    let at_limit = Envelope::new("a".repeat(DEFAULT_FUNCTION_NAME_LIMIT), vec![]);
+    let beyond_limit = Envelope::new("a".repeat(DEFAULT_FUNCTION_NAME_LIMIT + 1), vec![]);
+    let beyond_hard_limit = Envelope::new("a".repeat(u16::MAX as usize + 1), vec![]);
+
+    assert!(matches!(
+        container.validate(&at_limit),
+        Err(RpcError::UnknownFunction)
+    ));
+    assert!(matches!(
+        container.validate(&beyond_limit),
+        Err(RpcError::FunctionNameTooLong)
+    ));
+    assert!(matches!(
+        container.validate(&beyond_hard_limit),
+        Err(RpcError::MaxFunctionNameConstraintViolation)
+    ));
+}
+
+mod reports {
+    use corgi::rpc_fn;
+
+    #[rpc_fn]
+    pub async fn publish(title: String) {
+        println!("{title}");
+    }
+}
+
+#[test]
+fn container_should_export_schema_that_parses_into_the_same_descriptors() {
+    let mut container = Container::default();
+    container.register(&v1::__CORGI_RPC_add);
+    container.register(&reports::__CORGI_RPC_publish);
+
+    let schema = container.export_schema().unwrap();
+    let mut descriptors = SchemaCodec.decode(&schema).unwrap();
+    descriptors.sort_by(|a, b| a.name.cmp(&b.name));
+
+    assert_eq!(
+        descriptors,
+        vec![
+            FunctionDescriptor {
+                name: "add".to_owned(),
+                params: vec![
+                    ParamDescriptor {
+                        name: "a".to_owned(),
+                        schema_id: corgi::schema_id::<i32>(),
+                    },
+                    ParamDescriptor {
+                        name: "b".to_owned(),
+                        schema_id: corgi::schema_id::<i32>(),
+                    },
+                ],
+                return_schema_id: Some(corgi::schema_id::<i32>()),
+                error_schema_id: None,
+            },
+            FunctionDescriptor {
+                name: "publish".to_owned(),
+                params: vec![ParamDescriptor {
+                    name: "title".to_owned(),
+                    schema_id: corgi::schema_id::<String>(),
+                }],
+                return_schema_id: None,
+                error_schema_id: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn container_should_export_identical_schema_regardless_of_registration_order() {
+    let mut first = Container::default();
+    first.register(&v1::__CORGI_RPC_add);
+    first.register(&reports::__CORGI_RPC_publish);
+    first.register_alias("add", "sum").unwrap();
+    let mut second = Container::default();
+    second.register(&reports::__CORGI_RPC_publish);
+    second.register(&v1::__CORGI_RPC_add);
+    second.register_alias("add", "sum").unwrap();
+
+    let names: Vec<_> = first
+        .descriptors()
+        .into_iter()
+        .map(|descriptor| descriptor.name)
+        .collect();
+
+    assert_eq!(names, ["add", "publish", "sum"]);
+    assert_eq!(first.descriptors(), second.descriptors());
+    assert_eq!(
+        first.export_schema().unwrap(),
+        first.export_schema().unwrap()
+    );
+    assert_eq!(
+        first.export_schema().unwrap(),
+        second.export_schema().unwrap()
+    );
+}
+
+#[test]
+fn schema_codec_should_reject_truncated_schema() {
+    let mut container = Container::default();
+    container.register(&v1::__CORGI_RPC_add);
+    let schema = container.export_schema().unwrap();
+
+    let result = SchemaCodec.decode(&schema[..schema.len() - 1]);
+
+    assert!(matches!(result, Err(RpcError::Decode)));
+}
+
+#[test]
+fn schema_codec_should_reject_function_count_beyond_input() {
+    // Claims u16::MAX functions but carries none. The count must not size
+    // an allocation before the input runs out.
+    let result = SchemaCodec.decode(&u16::MAX.to_le_bytes());
+
+    assert!(matches!(result, Err(RpcError::Decode)));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn container_should_have_exactly_one_winner_per_name_under_concurrent_registration() {
+    use std::sync::{Arc, RwLock};
+
+    const ALIASES: [&str; 4] = ["sum", "plus", "total", "accumulate"];
+    const TASKS_PER_ALIAS: usize = 16;
+
+    let mut container = Container::default();
+    container.register(&v1::__CORGI_RPC_add);
+    let container = Arc::new(RwLock::new(container));
+
+    let tasks: Vec<_> = (0..TASKS_PER_ALIAS)
+        .flat_map(|_| ALIASES)
+        .map(|alias| {
+            let container = container.clone();
+            tokio::spawn(async move { container.write().unwrap().register_alias("add", alias) })
+        })
+        .collect();
+
+    let mut wins = 0;
+    let mut duplicates = 0;
+    for task in tasks {
+        match task.await.unwrap() {
+            Ok(()) => wins += 1,
+            Err(RpcError::DuplicateFunction) => duplicates += 1,
+            Err(error) => panic!("unexpected error {error:?}"),
+        }
+    }
+
+    assert_eq!(wins, ALIASES.len());
+    assert_eq!(duplicates, ALIASES.len() * (TASKS_PER_ALIAS - 1));
+    let container = container.read().unwrap();
+    for alias in ALIASES {
+        assert_eq!(container.find(alias).unwrap().name, "add");
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn container_should_resolve_colliding_try_registrations_while_being_looked_up() {
+    use std::sync::{Arc, RwLock};
+
+    use corgi::container::RpcFunction;
+
+    const TASKS_PER_FUNCTION: usize = 16;
+    const READERS: usize = 4;
+
+    let candidates: [&'static RpcFunction; 3] = [
+        &v1::__CORGI_RPC_add,
+        &colliding::__CORGI_RPC_subtract,
+        &v2::__CORGI_RPC_add,
+    ];
+    let container = Arc::new(RwLock::new(Container::default()));
+
+    let readers: Vec<_> = (0..READERS)
+        .map(|_| {
+            let container = container.clone();
+            tokio::spawn(async move {
+                let mut seen: Option<&'static RpcFunction> = None;
+                for _ in 0..1000 {
+                    let found = container.read().unwrap().find("add");
+                    if let Some(function) = found {
+                        assert_eq!(function.name, "add");
+                        assert!(seen.is_none_or(|seen| std::ptr::eq(seen, function)));
+                        seen = Some(function);
+                    }
+                    tokio::task::yield_now().await;
+                }
+            })
+        })
+        .collect();
+    let writers: Vec<_> = (0..TASKS_PER_FUNCTION)
+        .flat_map(|_| candidates)
+        .map(|function| {
+            let container = container.clone();
+            tokio::spawn(async move {
+                let result = container.write().unwrap().try_register(function);
+                (function, result)
+            })
+        })
+        .collect();
+
+    let mut results = Vec::new();
+    for writer in writers {
+        results.push(writer.await.unwrap());
+    }
+    for reader in readers {
+        reader.await.unwrap();
+    }
+
+    let container = container.read().unwrap();
+    let winner = container.find("add").unwrap();
+    assert!(std::ptr::eq(
+        container.find("add@2").unwrap(),
+        &*v2::__CORGI_RPC_add
+    ));
+    for (function, result) in results {
+        if std::ptr::eq(function, winner) || function.name == "add@2" {
+            assert!(result.is_ok());
+        } else {
+            assert!(matches!(result, Err(RpcError::DuplicateFunction)));
+        }
+    }
+    assert_eq!(container.len(), 2);
+}
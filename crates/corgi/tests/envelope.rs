@@ -0,0 +1,136 @@
+use std::time::Duration;
+
+use bytes::Bytes;
+use corgi::protocol::{
+    codec::EnvelopeCodec,
+    types::{DEADLINE_HEADER, Envelope, RpcError, TRACE_HEADER, TraceContext},
+};
+
+#[test]
+fn envelope_codec_should_round_trip_envelope_without_headers() {
+    let codec = EnvelopeCodec;
+    let envelope = Envelope::new(
+        "add".to_owned(),
+        vec![
+            Bytes::from_static(b"\x08\x01"),
+            Bytes::from_static(b"\x08\x02"),
+        ],
+    );
+
+    let bytes = codec.encode(envelope).unwrap();
+    let decoded = codec.decode(&bytes).unwrap();
+
+    assert_eq!(decoded.fn_name(), "add");
+    assert_eq!(decoded.parameters().len(), 2);
+    assert!(decoded.headers().is_empty());
+}
+
+#[test]
+fn envelope_codec_should_round_trip_headers() {
+    let codec = EnvelopeCodec;
+    let envelope = Envelope::new("add".to_owned(), vec![Bytes::from_static(b"\x08\x01")])
+        .with_headers(vec![
+            (
+                Bytes::from_static(b"authorization"),
+                Bytes::from_static(b"Bearer token"),
+            ),
+            (
+                Bytes::from_static(b"trace-id"),
+                Bytes::from_static(b"4bf92f3577b34da6"),
+            ),
+        ]);
+
+    let bytes = codec.encode(envelope).unwrap();
+    let decoded = codec.decode(&bytes).unwrap();
+
+    assert_eq!(decoded.headers().len(), 2);
+    assert_eq!(
+        decoded.header(b"authorization").unwrap().as_ref(),
+        b"Bearer token"
+    );
+    assert_eq!(
+        decoded.header(b"trace-id").unwrap().as_ref(),
+        b"4bf92f3577b34da6"
+    );
+    assert_eq!(decoded.parameters()[0].as_ref(), b"\x08\x01");
+}
+
+#[test]
+fn envelope_codec_should_round_trip_deadline_next_to_other_headers() {
+    let codec = EnvelopeCodec;
+    let envelope = Envelope::new("add".to_owned(), vec![])
+        .with_headers(vec![(
+            Bytes::from_static(b"trace-id"),
+            Bytes::from_static(b"abc"),
+        )])
+        .with_deadline(Duration::from_millis(1500));
+    let malformed = Envelope::new("add".to_owned(), vec![]).with_headers(vec![(
+        Bytes::from_static(DEADLINE_HEADER),
+        Bytes::from_static(b"soon"),
+    )]);
+
+    let decoded = codec.decode(&codec.encode(envelope).unwrap()).unwrap();
+
+    assert_eq!(decoded.deadline(), Some(Duration::from_millis(1500)));
+    assert_eq!(decoded.header(b"trace-id").unwrap().as_ref(), b"abc");
+    assert_eq!(decoded.header(DEADLINE_HEADER).unwrap().as_ref(), b"1500");
+    assert_eq!(malformed.deadline(), None);
+}
+
+#[test]
+fn envelope_codec_should_round_trip_trace() {
+    let codec = EnvelopeCodec;
+    let trace = TraceContext {
+        trace_id: [7; 16],
+        span_id: [9; 8],
+    };
+    let envelope = Envelope::new("add".to_owned(), vec![]).with_trace(trace);
+    let truncated = Envelope::new("add".to_owned(), vec![]).with_headers(vec![(
+        Bytes::from_static(TRACE_HEADER),
+        Bytes::from_static(&[7; 20]),
+    )]);
+
+    let decoded = codec.decode(&codec.encode(envelope).unwrap()).unwrap();
+
+    assert_eq!(decoded.trace(), Some(trace));
+    assert_eq!(decoded.header(TRACE_HEADER).unwrap().len(), 24);
+    assert_eq!(truncated.trace(), None);
+}
+
+#[test]
+fn envelope_codec_should_reject_headers_above_size_cap() {
+    let codec = EnvelopeCodec;
+    let envelope = Envelope::new("add".to_owned(), vec![]).with_headers(vec![(
+        Bytes::from_static(b"blob"),
+        Bytes::from(vec![0; 16 * 1024]),
+    )]);
+
+    let result = codec.encode(envelope);
+
+    assert!(matches!(
+        result,
+        Err(RpcError::MaxHeadersSizeConstraintViolation)
+    ));
+}
+
+#[test]
+fn envelope_codec_should_decode_utf8_function_name() {
+    let codec = EnvelopeCodec;
+    let envelope = Envelope::new("grüße".to_owned(), vec![]);
+
+    let decoded = codec.decode(&codec.encode(envelope).unwrap()).unwrap();
+
+    assert_eq!(decoded.fn_name(), "grüße");
+}
+
+#[test]
+fn envelope_codec_should_reject_invalid_utf8_function_name() {
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(&2_u16.to_le_bytes());
+    bytes.extend_from_slice(&[0xc3, 0x28]);
+    bytes.extend_from_slice(&0_u16.to_le_bytes());
+
+    let result = EnvelopeCodec.decode(&bytes);
+
+    assert!(matches!(result, Err(RpcError::InvalidFunctionName)));
+}
@@ -0,0 +1,252 @@
+use std::io;
+
+use bytes::Bytes;
+
+use corgi::protocol::{
+    codec::{ErrorFrameCodec, FailureCodec},
+    codes,
+    types::RpcError,
+};
+
+fn documented_codes() -> Vec<(RpcError, u16)> {
+    vec![
+        (RpcError::Decode, 1),
+        (RpcError::Encode, 2),
+        (RpcError::MaxFunctionNameConstraintViolation, 3),
+        (RpcError::MaxArgumentsConstraintViolation, 4),
+        (RpcError::MaxArgumentSizeConstraintViolation, 5),
+        (RpcError::ChunkHeaderSizeConstraintViolation, 6),
+        (RpcError::MaxChunkPayloadSizeConstraintViolation, 7),
+        (RpcError::InvalidChunkIndex, 8),
+        (RpcError::MaxHeadersConstraintViolation, 9),
+        (RpcError::MaxHeadersSizeConstraintViolation, 10),
+        (RpcError::GarbageBytes, 11),
+        (RpcError::ArgumentDecodeFailed, 12),
+        (
+            RpcError::SocketBinding(io::Error::from(io::ErrorKind::AddrInUse)),
+            13,
+        ),
+        (
+            RpcError::LocalAddress(io::Error::from(io::ErrorKind::NotConnected)),
+            14,
+        ),
+        (RpcError::InvalidFunctionName, 15),
+        (RpcError::UnknownFunction, 16),
+        (RpcError::DuplicateFunction, 17),
+        (RpcError::Timeout, 18),
+        (RpcError::ArityMismatch, 19),
+        (RpcError::RateLimited, 20),
+        (RpcError::ResponseTooLarge, 21),
+        (RpcError::TooManyPartialMessages, 22),
+        (RpcError::ChecksumMismatch, 23),
+        (RpcError::BadMagic, 24),
+        (RpcError::UnsupportedVersion, 25),
+        (RpcError::FunctionNameTooLong, 26),
+        (
+            RpcError::Application {
+                schema_id: 7,
+                payload: Bytes::from_static(b"failure"),
+            },
+            27,
+        ),
+        (RpcError::HandlerPanicked, 28),
+        (RpcError::EndiannessMismatch, 29),
+        (RpcError::MessageTooLarge, 30),
+        (RpcError::DeadlineExceeded, 31),
+        (
+            RpcError::Transport(io::Error::from(io::ErrorKind::ConnectionRefused)),
+            32,
+        ),
+        (RpcError::ConnectionClosed, 33),
+        (RpcError::UnsupportedMessageKind, 34),
+        (RpcError::SchemaMismatch, 35),
+        (RpcError::FeatureDisabled, 36),
+        (RpcError::ServerShuttingDown, 37),
+        (RpcError::PeerUnreachable, 38),
+        (RpcError::ClientBusy, 39),
+    ]
+}
+
+#[test]
+fn rpc_error_should_map_each_variant_to_documented_code() {
+    for (error, code) in documented_codes() {
+        assert_eq!(error.code(), code, "unexpected code for {error:?}");
+    }
+
+    assert_eq!(codes::DECODE, 1);
+    assert_eq!(codes::LOCAL_ADDRESS, 14);
+}
+
+#[test]
+fn error_frame_codec_should_round_trip_each_variant_as_remote() {
+    let codec = ErrorFrameCodec;
+
+    for (error, code) in documented_codes() {
+        let bytes = codec.encode(&error);
+        let decoded = codec.decode(&bytes).unwrap();
+
+        match decoded {
+            RpcError::Remote {
+                code: remote_code,
+                message,
+            } => {
+                assert_eq!(remote_code, code);
+                assert!(!message.is_empty());
+            }
+            other => panic!("expected remote error, got {other:?}"),
+        }
+    }
+}
+
+#[test]
+fn error_frame_codec_should_keep_remote_code_and_message() {
+    let codec = ErrorFrameCodec;
+    let error = RpcError::Remote {
+        code: 4096,
+        message: "application failure".to_owned(),
+    };
+
+    let decoded = codec.decode(&codec.encode(&error)).unwrap();
+
+    assert!(matches!(
+        decoded,
+        RpcError::Remote { code: 4096, ref message } if message == "application failure"
+    ));
+}
+
+#[test]
+fn error_frame_codec_should_reject_truncated_frame() {
+    let codec = ErrorFrameCodec;
+    let bytes = codec.encode(&RpcError::Decode);
+
+    let result = codec.decode(&bytes[..bytes.len() - 1]);
+
+    assert!(matches!(result, Err(RpcError::Decode)));
+}
+
+#[test]
+fn failure_codec_should_decode_standard_errors_as_remote_codes() {
+    let codec = FailureCodec;
+    let standard = [
+        (RpcError::UnknownFunction, codes::UNKNOWN_FUNCTION),
+        (RpcError::Decode, codes::DECODE),
+        (
+            RpcError::ArgumentDecodeFailed,
+            codes::ARGUMENT_DECODE_FAILED,
+        ),
+        (RpcError::HandlerPanicked, codes::HANDLER_PANICKED),
+    ];
+
+    for (error, code) in standard {
+        let decoded = codec.decode(&codec.encode(&error).unwrap()).unwrap();
+
+        assert!(
+            matches!(decoded, RpcError::Remote { code: remote_code, .. } if remote_code == code),
+            "{error:?} decoded to {decoded:?}"
+        );
+    }
+}
+
+#[test]
+fn failure_codec_should_keep_application_error() {
+    let codec = FailureCodec;
+    let error = RpcError::Application {
+        schema_id: 42,
+        payload: Bytes::from_static(b"declared"),
+    };
+
+    let decoded = codec.decode(&codec.encode(&error).unwrap()).unwrap();
+
+    assert!(matches!(
+        decoded,
+        RpcError::Application { schema_id: 42, ref payload } if payload == "declared"
+    ));
+}
+
+#[test]
+fn failure_codec_should_reject_unknown_status() {
+    let codec = FailureCodec;
+
+    let empty = codec.decode(&Bytes::new());
+    let unknown = codec.decode(&Bytes::from_static(&[9, 0, 0]));
+
+    assert!(matches!(empty, Err(RpcError::Decode)));
+    assert!(matches!(unknown, Err(RpcError::Decode)));
+}
+
+#[test]
+fn rpc_error_should_display_each_variant() {
+    for (error, _) in documented_codes() {
+        assert!(!error.to_string().is_empty(), "{error:?}");
+    }
+}
+
+#[test]
+fn rpc_error_should_expose_io_error_as_source() {
+    let error = RpcError::SocketBinding(io::Error::new(io::ErrorKind::AddrInUse, "in use"));
+
+    let source = std::error::Error::source(&error).unwrap();
+
+    assert_eq!(error.to_string(), "failed to bind socket: in use");
+    assert_eq!(source.to_string(), "in use");
+    assert!(std::error::Error::source(&RpcError::Timeout).is_none());
+}
+
+#[test]
+fn rpc_error_should_expose_io_error_of_failed_send_as_source() {
+    let error = RpcError::Transport(io::Error::new(io::ErrorKind::ConnectionRefused, "refused"));
+
+    let source = std::error::Error::source(&error).unwrap();
+
+    assert_eq!(error.to_string(), "failed to send datagram: refused");
+    assert_eq!(source.to_string(), "refused");
+    assert!(std::error::Error::source(&RpcError::ConnectionClosed).is_none());
+}
+
+#[test]
+fn rpc_error_should_convert_into_boxed_error() {
+    fn fails() -> Result<(), Box<dyn std::error::Error>> {
+        Err(RpcError::Timeout)?
+    }
+
+    assert_eq!(fails().unwrap_err().to_string(), "call timed out");
+}
+
+#[test]
+fn rpc_error_should_render_user_message_for_timeout() {
+    assert_eq!(
+        RpcError::Timeout.user_message(),
+        "The service did not respond in time."
+    );
+}
+
+#[test]
+fn rpc_error_should_render_user_message_for_decode_error() {
+    assert_eq!(
+        RpcError::Decode.user_message(),
+        "A malformed message was received."
+    );
+}
+
+#[test]
+fn rpc_error_should_render_user_message_for_remote_error_via_code_table() {
+    let error = RpcError::Remote {
+        code: codes::UNKNOWN_FUNCTION,
+        message: "no function named `ad` registered".to_owned(),
+    };
+
+    assert_eq!(
+        error.user_message(),
+        "The requested operation is not available."
+    );
+}
+
+#[test]
+fn rpc_error_should_render_generic_user_message_for_unknown_remote_code() {
+    let error = RpcError::Remote {
+        code: 4096,
+        message: "application failure".to_owned(),
+    };
+
+    assert_eq!(error.user_message(), "An unexpected error occurred.");
+}
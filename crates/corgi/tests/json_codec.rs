@@ -0,0 +1,113 @@
+use std::net::SocketAddr;
+
+use corgi::{
+    Container, RpcClient, RpcServer,
+    protocol::{codec::JsonCodec, types::RpcError},
+    rpc_fn,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Order {
+    id: u64,
+    item: String,
+    quantity: u32,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Receipt {
+    order_id: u64,
+    total_cents: u64,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct OutOfStock {
+    item: String,
+}
+
+#[rpc_fn(codec = corgi::protocol::codec::JsonCodec)]
+async fn place_order(order: Order, unit_cents: u64) -> Result<Receipt, OutOfStock> {
+    if order.quantity > 10 {
+        return Err(OutOfStock { item: order.item });
+    }
+    Ok(Receipt {
+        order_id: order.id,
+        total_cents: unit_cents * order.quantity as u64,
+    })
+}
+
+#[test]
+fn json_codec_should_round_trip_struct_as_readable_utf8() {
+    let codec = JsonCodec;
+    let order = Order {
+        id: 7,
+        item: "corgi plush".to_owned(),
+        quantity: 2,
+    };
+
+    let bytes = codec.encode(&order).unwrap();
+    let text = std::str::from_utf8(&bytes).unwrap();
+    println!("{text}");
+    let decoded: Order = codec.decode(&bytes).unwrap();
+
+    assert_eq!(text, r#"{"id":7,"item":"corgi plush","quantity":2}"#);
+    assert_eq!(decoded, order);
+}
+
+#[test]
+fn json_codec_should_reject_malformed_json() {
+    let result = JsonCodec.decode::<Order>(br#"{"id":7"#);
+
+    assert!(matches!(result, Err(RpcError::Decode)));
+}
+
+#[tokio::test]
+async fn rpc_server_should_run_function_declared_with_json_codec() {
+    let container: &'static Container = Box::leak(Box::new(
+        Container::default().with(&__CORGI_RPC_place_order),
+    ));
+    let address: SocketAddr = "127.0.0.1:0".parse().unwrap();
+    let server = RpcServer::create_udp(container, address).await.unwrap();
+    let client = RpcClient::connect_udp(server.local_address())
+        .await
+        .unwrap();
+    tokio::spawn(async move { server.start().await });
+    let codec = JsonCodec;
+    let order = |quantity| Order {
+        id: 7,
+        item: "corgi plush".to_owned(),
+        quantity,
+    };
+
+    let receipt = client
+        .call(
+            "place_order",
+            vec![
+                codec.encode(&order(2)).unwrap(),
+                codec.encode(&1250_u64).unwrap(),
+            ],
+        )
+        .await
+        .unwrap();
+    let rejected = client
+        .call(
+            "place_order",
+            vec![
+                codec.encode(&order(11)).unwrap(),
+                codec.encode(&1250_u64).unwrap(),
+            ],
+        )
+        .await
+        .unwrap_err();
+
+    assert_eq!(
+        std::str::from_utf8(&receipt).unwrap(),
+        r#"{"order_id":7,"total_cents":2500}"#
+    );
+    assert_eq!(
+        rejected.application_error_with::<OutOfStock, _>(&codec),
+        Some(OutOfStock {
+            item: "corgi plush".to_owned()
+        })
+    );
+}
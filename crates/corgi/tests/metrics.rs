@@ -0,0 +1,129 @@
+use std::{net::SocketAddr, sync::Arc};
+
+use corgi::{
+    Container, RpcClient, RpcServer, metrics::FunctionMetrics, protocol::codec::ProtobufCodec,
+    rpc_fn,
+};
+use prometheus_parse::{LineInfo, Scrape, Value};
+
+#[rpc_fn]
+async fn add(a: i32, b: i32) -> i32 {
+    a + b
+}
+
+#[rpc_fn(name = "quoted\"name")]
+async fn quoted() {}
+
+/// Parses `text`, failing on any line the parser doesn't recognize.
+fn parse(text: &str) -> Scrape {
+    for line in text.lines() {
+        assert!(
+            !matches!(LineInfo::parse(line), LineInfo::Ignored | LineInfo::Empty),
+            "invalid line: {line}"
+        );
+    }
+    Scrape::parse(text.lines().map(|line| Ok(line.to_owned()))).unwrap()
+}
+
+fn value(scrape: &Scrape, metric: &str, fn_name: Option<&str>) -> Option<Value> {
+    scrape
+        .samples
+        .iter()
+        .find(|sample| sample.metric == metric && sample.labels.get("fn_name") == fn_name)
+        .map(|sample| sample.value.clone())
+}
+
+#[tokio::test]
+async fn rpc_server_should_render_metrics_in_prometheus_format() {
+    let container = Container::default()
+        .with(&__CORGI_RPC_add)
+        .with(&__CORGI_RPC_quoted);
+    let container: &'static Container = Box::leak(Box::new(container));
+    let address: SocketAddr = "127.0.0.1:0".parse().unwrap();
+    let server = Arc::new(RpcServer::create_udp(container, address).await.unwrap());
+    tokio::spawn({
+        let server = Arc::clone(&server);
+        async move { server.start().await }
+    });
+    let client = RpcClient::connect_udp(server.local_address())
+        .await
+        .unwrap();
+    let codec = ProtobufCodec;
+    let args = vec![codec.encode(&1_i32).unwrap(), codec.encode(&2_i32).unwrap()];
+
+    client.call("add", args.clone()).await.unwrap();
+    client.call("add", args[..1].to_vec()).await.unwrap_err();
+    client.call("missing", vec![]).await.unwrap_err();
+    let scrape = parse(&server.metrics_prometheus());
+
+    assert_eq!(
+        server.metrics().functions,
+        [
+            FunctionMetrics {
+                name: "add",
+                calls: 2,
+                failures: 1,
+            },
+            FunctionMetrics {
+                name: "quoted\"name",
+                calls: 0,
+                failures: 0,
+            },
+        ]
+    );
+    assert_eq!(
+        value(&scrape, "corgi_server_calls_total", Some("add")),
+        Some(Value::Counter(2.0))
+    );
+    assert_eq!(
+        value(&scrape, "corgi_server_call_failures_total", Some("add")),
+        Some(Value::Counter(1.0))
+    );
+    assert!(value(&scrape, "corgi_server_calls_total", Some("quoted\\\"name")).is_some());
+    assert!(value(&scrape, "corgi_server_calls_total", Some("missing")).is_none());
+    assert_eq!(
+        value(&scrape, "corgi_server_datagrams_received_total", None),
+        Some(Value::Counter(3.0))
+    );
+    assert_eq!(
+        value(&scrape, "corgi_server_partial_messages", None),
+        Some(Value::Gauge(0.0))
+    );
+    assert!(
+        scrape
+            .docs
+            .contains_key("corgi_server_peers_quarantined_total")
+    );
+}
+
+#[tokio::test]
+async fn rpc_client_should_render_latency_histogram_in_prometheus_format() {
+    let container: &'static Container =
+        Box::leak(Box::new(Container::default().with(&__CORGI_RPC_add)));
+    let address: SocketAddr = "127.0.0.1:0".parse().unwrap();
+    let server = RpcServer::create_udp(container, address).await.unwrap();
+    let client = RpcClient::connect_udp(server.local_address())
+        .await
+        .unwrap();
+    tokio::spawn(async move { server.start().await });
+    let codec = ProtobufCodec;
+    let args = vec![codec.encode(&1_i32).unwrap(), codec.encode(&2_i32).unwrap()];
+
+    for _ in 0..3 {
+        client.call("add", args.clone()).await.unwrap();
+    }
+    let scrape = parse(&client.metrics_prometheus());
+
+    let Some(Value::Histogram(buckets)) =
+        value(&scrape, "corgi_client_call_duration_seconds", None)
+    else {
+        panic!("missing histogram in {:?}", scrape.samples);
+    };
+    assert_eq!(buckets.len(), 9);
+    assert!(
+        buckets
+            .windows(2)
+            .all(|pair| pair[0].count <= pair[1].count)
+    );
+    assert_eq!(buckets.last().unwrap().count, 3.0);
+}
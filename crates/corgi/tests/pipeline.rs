@@ -0,0 +1,100 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bytes::Bytes;
+use corgi::{
+    Container,
+    protocol::{
+        codec::{EnvelopeCodec, ProtobufCodec},
+        types::Envelope,
+    },
+    rpc_fn,
+};
+
+#[rpc_fn]
+async fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+#[rpc_fn]
+async fn concat(left: String, right: String) -> String {
+    format!("{left}{right}")
+}
+
+#[test]
+fn envelope_codec_should_round_trip_zero_argument_envelope() {
+    let codec = EnvelopeCodec;
+
+    let bytes = codec
+        .encode(Envelope::new("now".to_owned(), vec![]))
+        .unwrap();
+    let decoded = codec.decode(&bytes).unwrap();
+
+    // fn len + fn name + args count, nothing else
+    assert_eq!(bytes.len(), 2 + 3 + 2);
+    assert_eq!(decoded.fn_name(), "now");
+    assert!(decoded.parameters().is_empty());
+}
+
+#[test]
+fn envelope_codec_should_decode_shared_arguments_as_slices_of_request() {
+    let codec = EnvelopeCodec;
+    let bytes = codec
+        .encode(Envelope::new(
+            "echo".to_owned(),
+            vec![Bytes::from_static(b"payload")],
+        ))
+        .unwrap();
+
+    let decoded = codec.decode_shared(&bytes).unwrap();
+
+    let argument = &decoded.parameters()[0];
+    assert_eq!(argument.as_ref(), b"payload");
+    assert_eq!(argument.as_ptr(), bytes[bytes.len() - 7..].as_ptr());
+}
+
+#[tokio::test]
+async fn zero_argument_call_should_dispatch_from_decoded_envelope() {
+    let mut container = Container::default();
+    container.register(&__CORGI_RPC_now);
+    let envelope_codec = EnvelopeCodec;
+    let codec = ProtobufCodec;
+
+    let bytes = envelope_codec
+        .encode(Envelope::new("now".to_owned(), vec![]))
+        .unwrap();
+    let envelope = envelope_codec.decode(&bytes).unwrap();
+    let function = container.find(envelope.fn_name()).unwrap();
+    let result = (function.handler)(envelope.parameters().clone())
+        .await
+        .unwrap();
+
+    let timestamp: u64 = codec.decode(&result).unwrap();
+    assert!(timestamp > 0);
+}
+
+#[tokio::test]
+async fn macro_generated_function_should_dispatch_through_container_validation() {
+    let mut container = Container::default();
+    container.register(&__CORGI_RPC_concat);
+    let envelope_codec = EnvelopeCodec;
+    let codec = ProtobufCodec;
+    let args = vec![
+        codec.encode(&"cor".to_owned()).unwrap(),
+        codec.encode(&"gi".to_owned()).unwrap(),
+    ];
+
+    let bytes = envelope_codec
+        .encode(Envelope::new("concat".to_owned(), args))
+        .unwrap();
+    let envelope = envelope_codec.decode(&bytes).unwrap();
+    let function = container.validate(&envelope).unwrap();
+    let result = (function.handler)(envelope.parameters().clone())
+        .await
+        .unwrap();
+
+    let joined: String = codec.decode(&result).unwrap();
+    assert_eq!(joined, "corgi");
+}
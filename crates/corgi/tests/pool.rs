@@ -0,0 +1,101 @@
+use std::{net::SocketAddr, time::Duration};
+
+use corgi::{
+    ClientConfig, ClientPool,
+    pool::PoolConfig,
+    protocol::{
+        codec::{CHUNK_MAGIC, PROTOCOL_VERSION},
+        types::{MessageKind, RpcError},
+    },
+};
+use tokio::net::UdpSocket;
+
+/// Builds a single-chunk empty response to `call_id`.
+fn empty_reply(call_id: u64) -> Vec<u8> {
+    let mut reply = Vec::with_capacity(32);
+    reply.extend_from_slice(&CHUNK_MAGIC);
+    reply.push(PROTOCOL_VERSION);
+    reply.extend_from_slice(&call_id.to_le_bytes());
+    reply.extend_from_slice(&0_u16.to_le_bytes());
+    reply.extend_from_slice(&1_u16.to_le_bytes());
+    reply.extend_from_slice(&0_u32.to_le_bytes());
+    reply.push(MessageKind::Response as u8);
+    reply.extend_from_slice(&crc32fast::hash(&[]).to_le_bytes());
+    reply.extend_from_slice(&call_id.to_le_bytes());
+    reply
+}
+
+/// Answers `calls` single-chunk calls with an empty response and returns
+/// the address each came from.
+async fn answer(server: &UdpSocket, calls: usize) -> Vec<SocketAddr> {
+    let mut peers = Vec::new();
+    let mut buf = [0_u8; 2048];
+    for _ in 0..calls {
+        let (_, peer) = server.recv_from(&mut buf).await.unwrap();
+        let call_id = u64::from_le_bytes(buf[3..11].try_into().unwrap());
+        server.send_to(&empty_reply(call_id), peer).await.unwrap();
+        peers.push(peer);
+    }
+    peers
+}
+
+fn impatient() -> PoolConfig {
+    PoolConfig {
+        client: ClientConfig {
+            timeout: Duration::from_millis(20),
+            retries: 0,
+            ..ClientConfig::default()
+        },
+        max_consecutive_timeouts: 2,
+    }
+}
+
+#[tokio::test]
+async fn client_pool_should_reuse_one_client_per_address() {
+    let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let address = server.local_addr().unwrap();
+    let pool = ClientPool::default();
+
+    let (first, second, peers) = tokio::join!(
+        pool.call(address, "ping", vec![]),
+        async {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            pool.call(address, "ping", vec![]).await
+        },
+        answer(&server, 2),
+    );
+
+    assert!(first.unwrap().is_empty());
+    assert!(second.unwrap().is_empty());
+    assert_eq!(peers[0], peers[1]);
+    assert_eq!(pool.len(), 1);
+    assert!(pool.is_healthy(address));
+}
+
+#[tokio::test]
+async fn client_pool_should_mark_timed_out_server_unhealthy_and_drop_its_client() {
+    let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let address = server.local_addr().unwrap();
+    let pool = ClientPool::new(impatient());
+
+    let first = pool.call(address, "silent", vec![]).await;
+    let healthy_after_one = pool.is_healthy(address);
+    let second = pool.call(address, "silent", vec![]).await;
+
+    assert!(matches!(first, Err(RpcError::Timeout)));
+    assert!(matches!(second, Err(RpcError::Timeout)));
+    assert!(healthy_after_one);
+    assert!(!pool.is_healthy(address));
+    assert!(pool.is_empty());
+
+    // Drain the unanswered calls; an answer to a new client heals it.
+    let mut buf = [0_u8; 2048];
+    for _ in 0..2 {
+        server.recv_from(&mut buf).await.unwrap();
+    }
+    let (recovered, _) = tokio::join!(pool.call(address, "ping", vec![]), answer(&server, 1));
+
+    assert!(recovered.is_ok());
+    assert!(pool.is_healthy(address));
+    assert_eq!(pool.len(), 1);
+}
@@ -0,0 +1,26 @@
+use corgi::schema_id;
+
+#[derive(prost::Message, Clone, PartialEq)]
+struct User {
+    #[prost(string, tag = "1")]
+    name: String,
+}
+
+#[derive(prost::Message, Clone, PartialEq)]
+struct Order {
+    #[prost(uint64, tag = "1")]
+    id: u64,
+}
+
+#[test]
+fn schema_id_should_be_stable_across_invocations() {
+    assert_eq!(schema_id::<User>(), schema_id::<User>());
+    assert_eq!(schema_id::<i32>(), schema_id::<i32>());
+}
+
+#[test]
+fn schema_id_should_differ_for_different_types() {
+    assert_ne!(schema_id::<User>(), schema_id::<Order>());
+    assert_ne!(schema_id::<i32>(), schema_id::<i64>());
+    assert_ne!(schema_id::<String>(), schema_id::<Vec<u8>>());
+}
@@ -0,0 +1,839 @@
+use std::{
+    net::SocketAddr,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU32, Ordering},
+    },
+    time::Duration,
+};
+
+use bytes::Bytes;
+use corgi::{
+    ClientConfig, Container, RpcClient, RpcContext, RpcServer, ServerConfig,
+    client::CallIdGenerator,
+    protocol::{
+        codec::{Endianness, EnvelopeCodec, PackageChunkCodec, ProtobufCodec},
+        codes,
+        types::{CallId, ChunkHeader, Envelope, PackageChunk, RpcError},
+    },
+    quarantine::QuarantinePolicy,
+    rpc_fn,
+    server::{ResponseIds, UnknownMessageKinds},
+};
+use tokio::{net::UdpSocket, sync::oneshot};
+
+#[rpc_fn]
+async fn add(a: i32, b: i32) -> i32 {
+    a + b
+}
+
+#[rpc_fn]
+async fn length(data: Vec<u8>) -> u64 {
+    data.len() as u64
+}
+
+static EXECUTIONS: AtomicU32 = AtomicU32::new(0);
+
+#[rpc_fn]
+async fn count_execution() -> u32 {
+    EXECUTIONS.fetch_add(1, Ordering::SeqCst) + 1
+}
+
+const SLOW_DELAY: Duration = Duration::from_millis(50);
+
+#[rpc_fn]
+async fn slow() {
+    tokio::time::sleep(SLOW_DELAY).await;
+}
+
+struct Greeting(&'static str);
+
+struct Punctuation(char);
+
+#[rpc_fn]
+async fn greet(name: String) -> String {
+    let greeting = RpcContext::extension::<Greeting>().unwrap();
+    let punctuation = RpcContext::extension::<Punctuation>().unwrap();
+    format!("{}, {name}{}", greeting.0, punctuation.0)
+}
+
+#[rpc_fn]
+async fn describe_caller() -> String {
+    let header = |key: &[u8]| {
+        RpcContext::header(key)
+            .map(|value| String::from_utf8(value.to_vec()).unwrap())
+            .unwrap_or_default()
+    };
+    format!(
+        "{}/{} ({} headers)",
+        header(b"tenant"),
+        header(b"trace-id"),
+        RpcContext::headers().len()
+    )
+}
+
+#[rpc_fn]
+async fn explode(index: u32) -> u32 {
+    let values = [1_u32, 2, 3];
+    values[index as usize]
+}
+
+#[derive(prost::Message, Clone, PartialEq)]
+struct InsufficientFunds {
+    #[prost(uint64, tag = "1")]
+    balance: u64,
+    #[prost(uint64, tag = "2")]
+    requested: u64,
+}
+
+const BALANCE: u64 = 100;
+
+#[rpc_fn]
+async fn withdraw(amount: u64) -> Result<u64, InsufficientFunds> {
+    BALANCE.checked_sub(amount).ok_or(InsufficientFunds {
+        balance: BALANCE,
+        requested: amount,
+    })
+}
+
+#[rpc_fn]
+async fn blob(len: u32) -> Vec<u8> {
+    vec![7; len as usize]
+}
+
+static RECORDED: Mutex<Vec<(String, u32)>> = Mutex::new(Vec::new());
+
+#[rpc_fn]
+async fn record(label: String, count: u32) {
+    RECORDED.lock().unwrap().push((label, count));
+}
+
+/// Reuses one call id for every call, the way a retransmit does.
+struct SameCallId;
+
+impl CallIdGenerator for SameCallId {
+    fn next_call_id(&self) -> CallId {
+        1
+    }
+}
+
+async fn spawn_server() -> SocketAddr {
+    spawn_server_with(ServerConfig::default()).await
+}
+
+/// Starts a server with the test functions registered and returns its address.
+async fn spawn_server_with(config: ServerConfig) -> SocketAddr {
+    let address: SocketAddr = "127.0.0.1:0".parse().unwrap();
+    let server = RpcServer::create_udp(test_container(), address)
+        .await
+        .unwrap()
+        .with_config(config);
+    let local_address = server.local_address();
+    tokio::spawn(async move { server.start().await });
+
+    local_address
+}
+
+/// Returns a container with the test functions registered.
+fn test_container() -> &'static Container {
+    let mut container = Container::default();
+    container.register(&__CORGI_RPC_add);
+    container.register(&__CORGI_RPC_length);
+    container.register(&__CORGI_RPC_count_execution);
+    container.register(&__CORGI_RPC_greet);
+    container.register(&__CORGI_RPC_slow);
+    container.register(&__CORGI_RPC_withdraw);
+    container.register(&__CORGI_RPC_explode);
+    container.register(&__CORGI_RPC_describe_caller);
+    container.insert_extension(Arc::new(Greeting("Hello")));
+    container.insert_extension(Arc::new(Punctuation('!')));
+    Box::leak(Box::new(container))
+}
+
+#[tokio::test]
+async fn rpc_server_should_report_assigned_port_when_bound_to_port_zero() {
+    let container = Container::default();
+    let address: SocketAddr = "127.0.0.1:0".parse().unwrap();
+
+    let server = RpcServer::create_udp(&container, address).await.unwrap();
+    let local_address = server.local_address();
+
+    assert_eq!(local_address.ip(), address.ip());
+    assert_ne!(local_address.port(), 0);
+
+    // The port really belongs to the server: it can't be bound twice, and a
+    // client can send to it.
+    assert!(UdpSocket::bind(local_address).await.is_err());
+    let client = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    client.send_to(b"ping", local_address).await.unwrap();
+}
+
+#[cfg(target_os = "linux")]
+#[tokio::test]
+async fn rpc_server_should_be_created_from_pre_configured_socket() {
+    use socket2::{Domain, Protocol, Socket, Type};
+
+    let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP)).unwrap();
+    socket.set_recv_buffer_size(256 * 1024).unwrap();
+    let address: SocketAddr = "127.0.0.1:0".parse().unwrap();
+    socket.bind(&address.into()).unwrap();
+    let bound_address = socket.local_addr().unwrap().as_socket().unwrap();
+
+    let container = Container::default();
+    let server = RpcServer::create_udp_with(&container, socket).unwrap();
+
+    assert_eq!(server.local_address(), bound_address);
+}
+
+#[tokio::test]
+async fn rpc_server_should_reply_with_handler_result() {
+    let address = spawn_server().await;
+    let client = RpcClient::connect_udp(address).await.unwrap();
+    let codec = ProtobufCodec;
+
+    let reply = client
+        .call(
+            "add",
+            vec![codec.encode(&2_i32).unwrap(), codec.encode(&3_i32).unwrap()],
+        )
+        .await
+        .unwrap();
+
+    let result: i32 = codec.decode(&reply).unwrap();
+    assert_eq!(result, 5);
+}
+
+#[tokio::test]
+async fn rpc_server_should_reassemble_call_spanning_several_chunks() {
+    let address = spawn_server().await;
+    let client = RpcClient::connect_udp(address).await.unwrap();
+    let codec = ProtobufCodec;
+
+    let reply = client
+        .call("length", vec![codec.encode(&vec![7_u8; 5000]).unwrap()])
+        .await
+        .unwrap();
+
+    let result: u64 = codec.decode(&reply).unwrap();
+    assert_eq!(result, 5000);
+}
+
+#[tokio::test]
+async fn rpc_server_should_answer_retransmit_from_cache_until_ttl_elapses() {
+    let ttl = Duration::from_millis(200);
+    let address = spawn_server_with(ServerConfig {
+        dedup_cache_ttl: ttl,
+        ..ServerConfig::default()
+    })
+    .await;
+    let client = RpcClient::connect_udp(address)
+        .await
+        .unwrap()
+        .with_call_ids(SameCallId);
+    let codec = ProtobufCodec;
+
+    let first = client.call("count_execution", vec![]).await.unwrap();
+    let retransmit = client.call("count_execution", vec![]).await.unwrap();
+    tokio::time::sleep(ttl).await;
+    let after_ttl = client.call("count_execution", vec![]).await.unwrap();
+
+    assert_eq!(codec.decode::<u32>(&first).unwrap(), 1);
+    assert_eq!(codec.decode::<u32>(&retransmit).unwrap(), 1);
+    assert_eq!(codec.decode::<u32>(&after_ttl).unwrap(), 2);
+    assert_eq!(EXECUTIONS.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn rpc_server_should_expose_container_extensions_to_handlers() {
+    let address = spawn_server().await;
+    let client = RpcClient::connect_udp(address).await.unwrap();
+    let codec = ProtobufCodec;
+
+    let reply = client
+        .call("greet", vec![codec.encode(&"corgi".to_owned()).unwrap()])
+        .await
+        .unwrap();
+
+    let greeting: String = codec.decode(&reply).unwrap();
+    assert_eq!(greeting, "Hello, corgi!");
+}
+
+#[test]
+fn rpc_context_should_have_no_extensions_outside_of_handler() {
+    assert!(RpcContext::extension::<Greeting>().is_none());
+}
+
+#[tokio::test]
+async fn rpc_client_should_report_latency_of_at_least_server_delay() {
+    let address = spawn_server().await;
+    let client = RpcClient::connect_udp(address).await.unwrap();
+
+    let (_, latency) = client.call_timed("slow", vec![]).await.unwrap();
+    client.call("slow", vec![]).await.unwrap();
+
+    let histogram = client.latency_histogram();
+    assert!(latency >= SLOW_DELAY);
+    assert_eq!(histogram.count(), 2);
+    assert!(histogram.sum() >= SLOW_DELAY * 2);
+    assert!(histogram.max() >= latency);
+    let fast_calls: u64 = histogram
+        .buckets()
+        .filter(|(bound, _)| bound.is_some_and(|bound| bound < SLOW_DELAY))
+        .map(|(_, calls)| calls)
+        .sum();
+    assert_eq!(fast_calls, 0);
+}
+
+#[tokio::test]
+async fn rpc_client_should_decode_typed_error_returned_by_handler() {
+    let address = spawn_server().await;
+    let client = RpcClient::connect_udp(address).await.unwrap();
+    let codec = ProtobufCodec;
+
+    let ok = client
+        .call("withdraw", vec![codec.encode(&30_u64).unwrap()])
+        .await
+        .unwrap();
+    let error = client
+        .call("withdraw", vec![codec.encode(&130_u64).unwrap()])
+        .await
+        .unwrap_err();
+
+    assert_eq!(codec.decode::<u64>(&ok).unwrap(), 70);
+    assert!(matches!(error, RpcError::Application { .. }));
+    assert_eq!(
+        error.application_error::<InsufficientFunds>(),
+        Some(InsufficientFunds {
+            balance: 100,
+            requested: 130,
+        })
+    );
+    assert_eq!(error.application_error::<String>(), None);
+    assert!(matches!(
+        error.try_application_error::<String>(),
+        Err(RpcError::SchemaMismatch)
+    ));
+    assert!(matches!(
+        RpcError::Timeout.try_application_error::<String>(),
+        Ok(None)
+    ));
+}
+
+#[tokio::test]
+async fn rpc_client_should_receive_remote_error_for_unknown_function() {
+    let address = spawn_server().await;
+    let client = RpcClient::connect_udp(address).await.unwrap();
+
+    let error = client.call("missing", vec![]).await.unwrap_err();
+
+    assert!(matches!(
+        error,
+        RpcError::Remote {
+            code: codes::UNKNOWN_FUNCTION,
+            ..
+        }
+    ));
+}
+
+#[tokio::test]
+async fn rpc_client_should_receive_remote_error_for_undecodable_argument() {
+    let address = spawn_server().await;
+    let client = RpcClient::connect_udp(address).await.unwrap();
+    let truncated_varint = Bytes::from_static(&[0x80]);
+
+    let error = client
+        .call("add", vec![truncated_varint.clone(), truncated_varint])
+        .await
+        .unwrap_err();
+
+    assert!(matches!(
+        error,
+        RpcError::Remote {
+            code: codes::ARGUMENT_DECODE_FAILED,
+            ..
+        }
+    ));
+}
+
+#[tokio::test]
+async fn rpc_client_should_receive_remote_error_for_wrong_argument_count() {
+    let address = spawn_server().await;
+    let client = RpcClient::connect_udp(address).await.unwrap();
+
+    let error = client.call("add", vec![]).await.unwrap_err();
+
+    assert!(matches!(
+        error,
+        RpcError::Remote {
+            code: codes::ARITY_MISMATCH,
+            ..
+        }
+    ));
+}
+
+#[tokio::test]
+async fn rpc_server_should_answer_panicking_handler_with_error_and_keep_serving() {
+    let address = spawn_server().await;
+    let client = RpcClient::connect_udp(address).await.unwrap();
+    let codec = ProtobufCodec;
+
+    let panicked = client
+        .call("explode", vec![codec.encode(&7_u32).unwrap()])
+        .await
+        .unwrap_err();
+    let after_panic = client
+        .call("explode", vec![codec.encode(&1_u32).unwrap()])
+        .await
+        .unwrap();
+
+    assert!(matches!(
+        panicked,
+        RpcError::Remote {
+            code: codes::HANDLER_PANICKED,
+            ..
+        }
+    ));
+    assert_eq!(codec.decode::<u32>(&after_panic).unwrap(), 2);
+}
+
+#[tokio::test]
+async fn rpc_server_should_expose_call_headers_to_handler() {
+    let address = spawn_server().await;
+    let client = RpcClient::connect_udp(address).await.unwrap();
+    let codec = ProtobufCodec;
+    let headers = vec![
+        (Bytes::from_static(b"tenant"), Bytes::from_static(b"acme")),
+        (
+            Bytes::from_static(b"trace-id"),
+            Bytes::from_static(b"4bf92f"),
+        ),
+    ];
+
+    let with_headers = client
+        .call_with_headers("describe_caller", vec![], headers)
+        .await
+        .unwrap();
+    let without_headers = client.call("describe_caller", vec![]).await.unwrap();
+
+    assert_eq!(
+        codec.decode::<String>(&with_headers).unwrap(),
+        "acme/4bf92f (2 headers)"
+    );
+    assert_eq!(
+        codec.decode::<String>(&without_headers).unwrap(),
+        "/ (0 headers)"
+    );
+}
+
+#[tokio::test]
+async fn rpc_server_should_cancel_handler_exceeding_client_deadline() {
+    let address = spawn_server().await;
+    let client = RpcClient::connect_udp(address).await.unwrap();
+
+    let exceeded = client
+        .call_with_deadline("slow", vec![], SLOW_DELAY / 5)
+        .await
+        .unwrap_err();
+    let within = client
+        .call_with_deadline("slow", vec![], SLOW_DELAY * 20)
+        .await;
+    let without = client.call("slow", vec![]).await;
+
+    assert!(matches!(
+        exceeded,
+        RpcError::Remote {
+            code: codes::DEADLINE_EXCEEDED,
+            ..
+        }
+    ));
+    assert!(within.is_ok());
+    assert!(without.is_ok());
+}
+
+#[tokio::test]
+async fn rpc_server_should_finish_in_flight_calls_and_return_on_shutdown() {
+    let address: SocketAddr = "127.0.0.1:0".parse().unwrap();
+    let server = RpcServer::create_udp(test_container(), address)
+        .await
+        .unwrap();
+    let client = RpcClient::connect_udp(server.local_address())
+        .await
+        .unwrap()
+        .with_config(ClientConfig {
+            timeout: Duration::from_millis(100),
+            retries: 0,
+            ..ClientConfig::default()
+        });
+    let (shutdown, shutdown_signal) = oneshot::channel::<()>();
+    let running = tokio::spawn(async move {
+        server
+            .start_with_shutdown(async {
+                let _ = shutdown_signal.await;
+            })
+            .await
+    });
+
+    let (in_flight, stopped) = tokio::join!(client.call("slow", vec![]), async {
+        tokio::time::sleep(SLOW_DELAY / 5).await;
+        shutdown.send(()).unwrap();
+        tokio::time::timeout(SLOW_DELAY * 4, running).await
+    });
+    let after_shutdown = client.call("slow", vec![]).await;
+
+    assert!(in_flight.is_ok());
+    assert!(matches!(stopped, Ok(Ok(Ok(())))));
+    assert!(matches!(after_shutdown, Err(RpcError::Timeout)));
+}
+
+#[tokio::test]
+async fn rpc_server_should_reject_new_calls_and_finish_in_flight_ones_in_lame_duck_mode() {
+    let address: SocketAddr = "127.0.0.1:0".parse().unwrap();
+    let server = Arc::new(
+        RpcServer::create_udp(test_container(), address)
+            .await
+            .unwrap(),
+    );
+    tokio::spawn({
+        let server = Arc::clone(&server);
+        async move { server.start().await }
+    });
+    let client = RpcClient::connect_udp(server.local_address())
+        .await
+        .unwrap();
+    let codec = ProtobufCodec;
+    let args = vec![codec.encode(&2_i32).unwrap(), codec.encode(&3_i32).unwrap()];
+
+    let (in_flight, rejected) = tokio::join!(client.call("slow", vec![]), async {
+        tokio::time::sleep(SLOW_DELAY / 5).await;
+        server.enter_lame_duck();
+        client.call("add", args).await
+    });
+
+    assert!(in_flight.is_ok());
+    assert!(matches!(
+        rejected,
+        Err(RpcError::Remote { code, .. }) if code == codes::SERVER_SHUTTING_DOWN
+    ));
+}
+
+#[tokio::test]
+async fn rpc_server_should_have_sent_response_of_dispatched_call_once_flushed() {
+    let container: &'static Container =
+        Box::leak(Box::new(Container::default().with(&__CORGI_RPC_blob)));
+    let address: SocketAddr = "127.0.0.1:0".parse().unwrap();
+    let server = Arc::new(
+        RpcServer::create_udp(container, address)
+            .await
+            .unwrap()
+            .with_config(ServerConfig {
+                coalesce_window: Duration::from_millis(50),
+                ..ServerConfig::default()
+            }),
+    );
+    tokio::spawn({
+        let server = Arc::clone(&server);
+        async move { server.start().await }
+    });
+    let client = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let envelope = Envelope::new(
+        "blob".to_owned(),
+        vec![ProtobufCodec.encode(&5000_u32).unwrap()],
+    );
+    let payload = EnvelopeCodec.encode(envelope).unwrap();
+    let header = ChunkHeader::new(3, 0, 1, payload.len() as u32);
+    let datagram = PackageChunkCodec::default()
+        .encode(PackageChunk::new(header, payload))
+        .unwrap();
+    client
+        .send_to(&datagram, server.local_address())
+        .await
+        .unwrap();
+    while server.metrics().datagrams_received == 0 {
+        tokio::task::yield_now().await;
+    }
+
+    server.flush().await;
+
+    // Every chunk is queued at the client already, so none has to be waited
+    // for.
+    let mut buf = [0_u8; 2048];
+    let first = client.try_recv(&mut buf).unwrap();
+    let total = u16::from_le_bytes(buf[13..15].try_into().unwrap());
+    assert!(first > 0);
+    assert!(total > 1);
+    for _ in 1..total {
+        client.try_recv(&mut buf).unwrap();
+    }
+}
+
+#[tokio::test]
+async fn rpc_server_should_run_handler_on_injected_datagram_without_socket() {
+    let mut container = Container::default();
+    container.register(&__CORGI_RPC_record);
+    let server = RpcServer::detached(&container);
+    let codec = ProtobufCodec;
+    let envelope = Envelope::new(
+        "record".to_owned(),
+        vec![
+            codec.encode(&"captured".to_owned()).unwrap(),
+            codec.encode(&3_u32).unwrap(),
+        ],
+    );
+    let payload = EnvelopeCodec.encode(envelope).unwrap();
+    let header = ChunkHeader::new(9, 0, 1, payload.len() as u32);
+    let datagram = PackageChunkCodec::default()
+        .encode(PackageChunk::new(header, payload))
+        .unwrap();
+    let peer: SocketAddr = "127.0.0.1:4000".parse().unwrap();
+
+    let injected = server.debug_inject(peer, &datagram).await.unwrap().unwrap();
+    let rejected = server.debug_inject(peer, b"garbage").await;
+
+    assert_eq!(injected.call.call_id(), 9);
+    assert!(injected.result.unwrap().is_empty());
+    assert_eq!(*RECORDED.lock().unwrap(), [("captured".to_owned(), 3_u32)]);
+    assert!(rejected.is_err());
+}
+
+#[tokio::test]
+async fn rpc_client_should_correlate_responses_under_every_response_id_strategy() {
+    let codec = ProtobufCodec;
+
+    for response_ids in [ResponseIds::ReuseCallId, ResponseIds::Allocated] {
+        let address = spawn_server_with(ServerConfig {
+            response_ids,
+            ..ServerConfig::default()
+        })
+        .await;
+        let client = RpcClient::connect_udp(address).await.unwrap();
+
+        let (sum, len) = tokio::join!(
+            client.call(
+                "add",
+                vec![codec.encode(&2_i32).unwrap(), codec.encode(&3_i32).unwrap()],
+            ),
+            client.call("length", vec![codec.encode(&vec![0_u8; 4000]).unwrap()]),
+        );
+
+        assert_eq!(codec.decode::<i32>(&sum.unwrap()).unwrap(), 5);
+        assert_eq!(codec.decode::<u64>(&len.unwrap()).unwrap(), 4000);
+    }
+}
+
+#[tokio::test]
+async fn rpc_server_should_answer_with_allocated_id_correlated_to_request() {
+    let address = spawn_server_with(ServerConfig {
+        response_ids: ResponseIds::Allocated,
+        ..ServerConfig::default()
+    })
+    .await;
+    let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let codec = ProtobufCodec;
+    let envelope = Envelope::new(
+        "add".to_owned(),
+        vec![codec.encode(&1_i32).unwrap(), codec.encode(&1_i32).unwrap()],
+    );
+    let payload = EnvelopeCodec.encode(envelope).unwrap();
+    let header = ChunkHeader::new(7, 0, 1, payload.len() as u32);
+    let datagram = PackageChunkCodec::default()
+        .encode(PackageChunk::new(header, payload))
+        .unwrap();
+
+    socket.send_to(&datagram, address).await.unwrap();
+    let mut buf = [0_u8; 2048];
+    let (len, _) = socket.recv_from(&mut buf).await.unwrap();
+    let response = PackageChunkCodec::default().decode(&buf[..len]).unwrap();
+
+    assert_ne!(response.header().call_id(), 7);
+    assert_eq!(response.header().correlation_id(), 7);
+}
+
+#[tokio::test]
+async fn rpc_server_should_quarantine_peer_sending_malformed_datagrams() {
+    let config = ServerConfig {
+        quarantine: Some(QuarantinePolicy {
+            max_violations: 3,
+            window: Duration::from_secs(10),
+            duration: Duration::from_secs(60),
+        }),
+        ..ServerConfig::default()
+    };
+    let address: SocketAddr = "127.0.0.1:0".parse().unwrap();
+    let server = RpcServer::create_udp(test_container(), address)
+        .await
+        .unwrap()
+        .with_config(config);
+    let address = server.local_address();
+    let server = Arc::new(server);
+    tokio::spawn({
+        let server = Arc::clone(&server);
+        async move { server.start().await }
+    });
+    let misbehaving = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let well_behaved = RpcClient::connect_udp(address).await.unwrap();
+    let codec = ProtobufCodec;
+    let args = vec![codec.encode(&1_i32).unwrap(), codec.encode(&1_i32).unwrap()];
+    let payload = EnvelopeCodec
+        .encode(Envelope::new("add".to_owned(), args.clone()))
+        .unwrap();
+    let header = ChunkHeader::new(7, 0, 1, payload.len() as u32);
+    let valid_call = PackageChunkCodec::default()
+        .encode(PackageChunk::new(header, payload))
+        .unwrap();
+
+    for _ in 0..3 {
+        misbehaving.send_to(b"garbage", address).await.unwrap();
+    }
+    misbehaving.send_to(&valid_call, address).await.unwrap();
+    let mut buf = [0_u8; 2048];
+    let quarantined_reply =
+        tokio::time::timeout(Duration::from_millis(300), misbehaving.recv_from(&mut buf)).await;
+    let well_behaved_reply = well_behaved.call("add", args).await.unwrap();
+
+    assert!(quarantined_reply.is_err());
+    assert_eq!(codec.decode::<i32>(&well_behaved_reply).unwrap(), 2);
+    let metrics = server.metrics();
+    assert_eq!(metrics.peers_quarantined, 1);
+    assert_eq!(metrics.quarantined_datagrams, 1);
+}
+
+#[tokio::test]
+async fn rpc_server_should_only_answer_clients_of_its_endianness() {
+    let address = spawn_server_with(ServerConfig {
+        endianness: Endianness::Big,
+        ..ServerConfig::default()
+    })
+    .await;
+    let matching = RpcClient::connect_udp(address)
+        .await
+        .unwrap()
+        .with_config(ClientConfig {
+            endianness: Endianness::Big,
+            ..ClientConfig::default()
+        });
+    let mismatched = RpcClient::connect_udp(address)
+        .await
+        .unwrap()
+        .with_config(ClientConfig {
+            timeout: Duration::from_millis(100),
+            retries: 0,
+            ..ClientConfig::default()
+        });
+    let codec = ProtobufCodec;
+    let args = vec![codec.encode(&2_i32).unwrap(), codec.encode(&3_i32).unwrap()];
+
+    let reply = matching.call("add", args.clone()).await.unwrap();
+    let error = mismatched.call("add", args).await.unwrap_err();
+
+    assert_eq!(codec.decode::<i32>(&reply).unwrap(), 5);
+    assert!(matches!(error, RpcError::Timeout));
+}
+
+#[tokio::test]
+async fn rpc_server_should_apply_unknown_message_kind_policy() {
+    let codec = ProtobufCodec;
+    let args = vec![codec.encode(&1_i32).unwrap(), codec.encode(&1_i32).unwrap()];
+    let payload = EnvelopeCodec
+        .encode(Envelope::new("add".to_owned(), args))
+        .unwrap();
+    let header = ChunkHeader::new(7, 0, 1, payload.len() as u32);
+    let valid_call = PackageChunkCodec::default()
+        .encode(PackageChunk::new(header, payload))
+        .unwrap();
+    // The kind byte is not covered by the checksum.
+    let mut unknown_kind = valid_call.to_vec();
+    unknown_kind[19] = 0x7f;
+
+    for (policy, answered) in [
+        (UnknownMessageKinds::Ignore, true),
+        (UnknownMessageKinds::Reject, false),
+    ] {
+        let config = ServerConfig {
+            quarantine: Some(QuarantinePolicy {
+                max_violations: 1,
+                window: Duration::from_secs(10),
+                duration: Duration::from_secs(60),
+            }),
+            unknown_message_kinds: policy,
+            ..ServerConfig::default()
+        };
+        let address = spawn_server_with(config).await;
+        let peer = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+
+        peer.send_to(&unknown_kind, address).await.unwrap();
+        peer.send_to(&valid_call, address).await.unwrap();
+        let mut buf = [0_u8; 2048];
+        let reply =
+            tokio::time::timeout(Duration::from_millis(300), peer.recv_from(&mut buf)).await;
+
+        assert_eq!(reply.is_ok(), answered, "{policy:?}");
+    }
+}
+
+#[tokio::test]
+async fn rpc_server_should_run_v0_request_with_compat_v0() {
+    let mut container = Container::default();
+    container.register(&__CORGI_RPC_add);
+    let server = RpcServer::detached(&container).with_config(ServerConfig {
+        compat_v0: true,
+        ..ServerConfig::default()
+    });
+    let codec = ProtobufCodec;
+    // Version 0 envelope and chunk: no headers section, no chunk magic,
+    // version, kind or checksum.
+    let mut payload = Vec::new();
+    payload.extend_from_slice(&3_u16.to_le_bytes());
+    payload.extend_from_slice(b"add");
+    payload.extend_from_slice(&2_u16.to_le_bytes());
+    for arg in [2_i32, 3] {
+        let arg = codec.encode(&arg).unwrap();
+        payload.extend_from_slice(&(arg.len() as u64).to_le_bytes());
+        payload.extend_from_slice(&arg);
+    }
+    let mut datagram = Vec::new();
+    datagram.extend_from_slice(&11_u64.to_le_bytes());
+    datagram.extend_from_slice(&0_u16.to_le_bytes());
+    datagram.extend_from_slice(&1_u16.to_le_bytes());
+    datagram.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    datagram.extend_from_slice(&payload);
+    let peer: SocketAddr = "127.0.0.1:4000".parse().unwrap();
+
+    let injected = server.debug_inject(peer, &datagram).await.unwrap().unwrap();
+
+    assert_eq!(injected.call.call_id(), 11);
+    let sum: i32 = codec.decode(&injected.result.unwrap()).unwrap();
+    assert_eq!(sum, 5);
+}
+
+#[tokio::test]
+async fn rpc_client_should_talk_to_server_under_every_checksum_combination() {
+    let codec = ProtobufCodec;
+
+    for server_checksums in [true, false] {
+        let address = spawn_server_with(ServerConfig {
+            checksums: server_checksums,
+            ..ServerConfig::default()
+        })
+        .await;
+        for client_checksums in [true, false] {
+            let client = RpcClient::connect_udp(address)
+                .await
+                .unwrap()
+                .with_config(ClientConfig {
+                    checksums: client_checksums,
+                    ..ClientConfig::default()
+                });
+
+            let (sum, len) = tokio::join!(
+                client.call(
+                    "add",
+                    vec![codec.encode(&2_i32).unwrap(), codec.encode(&3_i32).unwrap()],
+                ),
+                client.call("length", vec![codec.encode(&vec![0_u8; 4000]).unwrap()]),
+            );
+
+            assert_eq!(codec.decode::<i32>(&sum.unwrap()).unwrap(), 5);
+            assert_eq!(codec.decode::<u64>(&len.unwrap()).unwrap(), 4000);
+        }
+    }
+}
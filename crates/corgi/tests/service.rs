@@ -0,0 +1,113 @@
+use std::{
+    net::SocketAddr,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use bytes::Bytes;
+use corgi::{
+    Container, RpcClient, RpcServer, RpcService,
+    client::CallIdGenerator,
+    protocol::{
+        codec::{MAX_MESSAGE_SIZE, ProtobufCodec},
+        codes,
+        types::{CallId, Envelope, RpcCall, RpcError},
+    },
+    rpc_fn,
+};
+use tower::{ServiceExt, limit::ConcurrencyLimit, service_fn};
+
+static RUNNING: AtomicUsize = AtomicUsize::new(0);
+static MAX_RUNNING: AtomicUsize = AtomicUsize::new(0);
+
+#[rpc_fn]
+async fn busy() {
+    let running = RUNNING.fetch_add(1, Ordering::SeqCst) + 1;
+    MAX_RUNNING.fetch_max(running, Ordering::SeqCst);
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    RUNNING.fetch_sub(1, Ordering::SeqCst);
+}
+
+#[rpc_fn]
+async fn double(value: u32) -> u32 {
+    value * 2
+}
+
+#[tokio::test]
+async fn rpc_service_should_dispatch_call_to_registered_function() {
+    let mut container = Container::default();
+    container.register(&__CORGI_RPC_double);
+    let codec = ProtobufCodec;
+    let call = RpcCall::new(
+        1,
+        Envelope::new("double".to_owned(), vec![codec.encode(&21_u32).unwrap()]),
+    );
+    let unknown = RpcCall::new(2, Envelope::new("missing".to_owned(), vec![]));
+
+    let response = RpcService::new(&container).oneshot(call).await.unwrap();
+    let error = RpcService::new(&container).oneshot(unknown).await;
+
+    assert_eq!(codec.decode::<u32>(&response).unwrap(), 42);
+    assert!(matches!(error, Err(RpcError::UnknownFunction)));
+}
+
+#[tokio::test]
+async fn rpc_server_should_bound_concurrency_with_tower_concurrency_limit() {
+    let mut container = Container::default();
+    container.register(&__CORGI_RPC_busy);
+    let container: &'static Container = Box::leak(Box::new(container));
+    let address: SocketAddr = "127.0.0.1:0".parse().unwrap();
+    let server = RpcServer::create_udp(container, address).await.unwrap();
+    let address = server.local_address();
+    tokio::spawn(async move {
+        let service = ConcurrencyLimit::new(server.service(), 2);
+        server.serve(service).await
+    });
+    let client = RpcClient::connect_udp(address).await.unwrap();
+
+    let calls = (0..6).map(|_| client.call("busy", vec![]));
+    let results = futures::future::join_all(calls).await;
+
+    assert!(results.iter().all(Result::is_ok));
+    assert_eq!(MAX_RUNNING.load(Ordering::SeqCst), 2);
+}
+
+/// Reuses one call id for every call, the way a retransmit does.
+struct SameCallId;
+
+impl CallIdGenerator for SameCallId {
+    fn next_call_id(&self) -> CallId {
+        1
+    }
+}
+
+#[tokio::test]
+async fn rpc_server_should_answer_unsendable_response_with_failure_and_cache_it() {
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+    let container: &'static Container = Box::leak(Box::default());
+    let address: SocketAddr = "127.0.0.1:0".parse().unwrap();
+    let server = RpcServer::create_udp(container, address).await.unwrap();
+    let address = server.local_address();
+    tokio::spawn(async move {
+        let service = service_fn(|_call: RpcCall| async {
+            CALLS.fetch_add(1, Ordering::SeqCst);
+            Ok::<_, RpcError>(Bytes::from(vec![0; MAX_MESSAGE_SIZE + 1]))
+        });
+        server.serve(service).await
+    });
+    let client = RpcClient::connect_udp(address)
+        .await
+        .unwrap()
+        .with_call_ids(SameCallId);
+
+    let first = client.call("huge", vec![]).await;
+    let retransmit = client.call("huge", vec![]).await;
+
+    for result in [first, retransmit] {
+        assert!(matches!(
+            result,
+            Err(RpcError::Remote { code, .. }) if code == codes::MESSAGE_TOO_LARGE
+        ));
+    }
+    assert_eq!(CALLS.load(Ordering::SeqCst), 1);
+}
@@ -0,0 +1,110 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+};
+
+use corgi::{
+    Container, RpcClient, RpcContext, RpcServer,
+    protocol::{codec::ProtobufCodec, types::TraceContext},
+    rpc_fn,
+};
+use tracing::{
+    Event, Id, Metadata, Subscriber,
+    field::{Field, Visit},
+    span::{Attributes, Record},
+};
+
+#[rpc_fn]
+async fn traced() -> Vec<u8> {
+    RpcContext::trace()
+        .map(|trace| trace.trace_id.to_vec())
+        .unwrap_or_default()
+}
+
+type Fields = HashMap<&'static str, String>;
+
+/// Keeps the name and fields of every span created while it is the default.
+#[derive(Default)]
+struct SpanRecorder {
+    spans: Arc<Mutex<Vec<(&'static str, Fields)>>>,
+}
+
+struct FieldVisitor<'a>(&'a mut Fields);
+
+impl Visit for FieldVisitor<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0.insert(field.name(), format!("{value:?}"));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name(), value.to_owned());
+    }
+}
+
+impl Subscriber for SpanRecorder {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        let mut fields = Fields::new();
+        span.record(&mut FieldVisitor(&mut fields));
+        let mut spans = self.spans.lock().unwrap();
+        spans.push((span.metadata().name(), fields));
+        Id::from_u64(spans.len() as u64)
+    }
+
+    fn record(&self, span: &Id, values: &Record<'_>) {
+        let mut spans = self.spans.lock().unwrap();
+        let (_, fields) = &mut spans[span.into_u64() as usize - 1];
+        values.record(&mut FieldVisitor(fields));
+    }
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, _: &Event<'_>) {}
+
+    fn enter(&self, _: &Id) {}
+
+    fn exit(&self, _: &Id) {}
+}
+
+#[tokio::test]
+async fn rpc_server_should_join_call_span_to_trace_sent_by_client() {
+    let recorder = SpanRecorder::default();
+    let spans = Arc::clone(&recorder.spans);
+    // The test runtime is single-threaded, so the server task spawned below
+    // runs on this thread and reports to the recorder.
+    let _default = tracing::subscriber::set_default(recorder);
+    let container: &'static Container =
+        Box::leak(Box::new(Container::default().with(&__CORGI_RPC_traced)));
+    let address: SocketAddr = "127.0.0.1:0".parse().unwrap();
+    let server = RpcServer::create_udp(container, address).await.unwrap();
+    let client = RpcClient::connect_udp(server.local_address())
+        .await
+        .unwrap();
+    tokio::spawn(async move { server.start().await });
+    let trace = TraceContext {
+        trace_id: *b"\x4b\xf9\x2f\x35\x77\xb3\x4d\xa6\xa3\xce\x92\x9d\x0e\x0e\x47\x36",
+        span_id: *b"\x00\xf0\x67\xaa\x0b\xa9\x02\xb7",
+    };
+
+    let reply = client
+        .call_with_trace("traced", vec![], trace)
+        .await
+        .unwrap();
+
+    let spans = spans.lock().unwrap();
+    let (_, fields) = spans
+        .iter()
+        .find(|(name, _)| *name == "rpc_call")
+        .expect("no rpc_call span");
+    assert_eq!(fields["fn_name"], "traced");
+    assert_eq!(fields["trace_id"], "4bf92f3577b34da6a3ce929d0e0e4736");
+    assert_eq!(fields["parent_span_id"], "00f067aa0ba902b7");
+    // The handler sees the trace too, to pass it on.
+    let seen: Vec<u8> = ProtobufCodec.decode(&reply).unwrap();
+    assert_eq!(seen, trace.trace_id);
+}
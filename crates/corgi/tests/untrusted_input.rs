@@ -0,0 +1,91 @@
+//! Arbitrary and malformed input must never panic a decoder: every byte
+//! sequence either decodes or is rejected with an `RpcError`.
+
+use corgi::protocol::codec::{CHUNK_MAGIC, EnvelopeCodec, PROTOCOL_VERSION, PackageChunkCodec};
+use proptest::prelude::*;
+
+/// Lengths clustered around the boundaries decoders check.
+fn boundary_len() -> impl Strategy<Value = usize> {
+    prop_oneof![
+        0..=4_usize,
+        15..=31_usize,
+        1180..=1220_usize,
+        0..=2048_usize
+    ]
+}
+
+fn bytes() -> impl Strategy<Value = Vec<u8>> {
+    boundary_len().prop_flat_map(|len| proptest::collection::vec(any::<u8>(), len))
+}
+
+/// A chunk with a plausible header whose fields may disagree with each other
+/// and with the payload that follows.
+fn malformed_chunk() -> impl Strategy<Value = Vec<u8>> {
+    (
+        any::<u64>(),
+        prop_oneof![Just(0_u16), Just(1), Just(u16::MAX), any::<u16>()],
+        prop_oneof![Just(0_u16), Just(1), Just(u16::MAX), any::<u16>()],
+        prop_oneof![Just(0_u32), Just(u32::MAX), 0..=2048_u32],
+        0..=2_u8,
+        any::<u32>(),
+        any::<u64>(),
+        bytes(),
+    )
+        .prop_map(
+            |(call_id, index, total, len, kind, checksum, correlation_id, payload)| {
+                let mut bytes = Vec::with_capacity(32 + payload.len());
+                bytes.extend_from_slice(&CHUNK_MAGIC);
+                bytes.push(PROTOCOL_VERSION);
+                bytes.extend_from_slice(&call_id.to_le_bytes());
+                bytes.extend_from_slice(&index.to_le_bytes());
+                bytes.extend_from_slice(&total.to_le_bytes());
+                bytes.extend_from_slice(&len.to_le_bytes());
+                bytes.push(kind);
+                bytes.extend_from_slice(&checksum.to_le_bytes());
+                bytes.extend_from_slice(&correlation_id.to_le_bytes());
+                bytes.extend_from_slice(&payload);
+                bytes
+            },
+        )
+}
+
+/// An envelope whose length prefixes may point past the end of the buffer.
+fn malformed_envelope() -> impl Strategy<Value = Vec<u8>> {
+    (
+        prop_oneof![Just(0_u16), Just(u16::MAX), 0..=64_u16],
+        prop_oneof![Just(0_u16), Just(u16::MAX), 0..=20_u16],
+        prop_oneof![Just(0_u64), Just(u64::MAX), 0..=64_u64],
+        bytes(),
+    )
+        .prop_map(|(fn_len, arg_count, arg_len, rest)| {
+            let mut bytes = Vec::new();
+            bytes.extend_from_slice(&fn_len.to_le_bytes());
+            bytes.extend(std::iter::repeat_n(b'f', fn_len.min(64) as usize));
+            bytes.extend_from_slice(&arg_count.to_le_bytes());
+            bytes.extend_from_slice(&arg_len.to_le_bytes());
+            bytes.extend_from_slice(&rest);
+            bytes
+        })
+}
+
+proptest! {
+    #[test]
+    fn package_chunk_codec_should_never_panic_on_arbitrary_bytes(bytes in bytes()) {
+        let _ = PackageChunkCodec::default().decode(&bytes);
+    }
+
+    #[test]
+    fn package_chunk_codec_should_never_panic_on_malformed_chunk(bytes in malformed_chunk()) {
+        let _ = PackageChunkCodec::default().decode(&bytes);
+    }
+
+    #[test]
+    fn envelope_codec_should_never_panic_on_arbitrary_bytes(bytes in bytes()) {
+        let _ = EnvelopeCodec.decode(&bytes);
+    }
+
+    #[test]
+    fn envelope_codec_should_never_panic_on_malformed_envelope(bytes in malformed_envelope()) {
+        let _ = EnvelopeCodec.decode(&bytes);
+    }
+}
@@ -0,0 +1,62 @@
+use bytes::Bytes;
+use corgi::protocol::{
+    codec::{EnvelopeCodec, estimate_wire_size},
+    types::{Envelope, RpcError},
+};
+
+const MTU: usize = 1200;
+const CHUNK_HEADER_SIZE: usize = 32;
+
+fn encoded_len(fn_name: &str, args: &[Bytes]) -> usize {
+    let envelope = Envelope::new(fn_name.to_owned(), args.to_vec());
+    EnvelopeCodec.encode(envelope).unwrap().len()
+}
+
+#[test]
+fn estimate_wire_size_should_match_encoded_size_for_various_payloads() {
+    let payloads = [
+        vec![],
+        vec![Bytes::from_static(b"\x08\x01")],
+        vec![Bytes::from(vec![7; 1000]), Bytes::from(vec![9; 500])],
+        vec![Bytes::from(vec![1; 64 * 1024])],
+    ];
+
+    for args in payloads {
+        let (envelope_bytes, chunks) = estimate_wire_size("add", &args, MTU).unwrap();
+        let actual = encoded_len("add", &args);
+
+        assert_eq!(envelope_bytes, actual);
+        assert_eq!(chunks, actual.div_ceil(MTU - CHUNK_HEADER_SIZE));
+    }
+}
+
+#[test]
+fn estimate_wire_size_should_count_exactly_filled_chunk_once() {
+    let mtu = encoded_len("add", &[]) + CHUNK_HEADER_SIZE;
+
+    let (_, chunks) = estimate_wire_size("add", &[], mtu).unwrap();
+
+    assert_eq!(chunks, 1);
+}
+
+#[test]
+fn estimate_wire_size_should_reject_mtu_without_room_for_payload() {
+    let result = estimate_wire_size("add", &[], CHUNK_HEADER_SIZE);
+
+    assert!(matches!(
+        result,
+        Err(RpcError::ChunkHeaderSizeConstraintViolation)
+    ));
+}
+
+#[test]
+fn estimate_wire_size_should_reject_too_many_arguments() {
+    let args = vec![Bytes::new(); 17];
+
+    let result = estimate_wire_size("add", &args, MTU);
+
+    assert!(matches!(
+        result,
+        Err(RpcError::MaxArgumentsConstraintViolation)
+    ));
+}